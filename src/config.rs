@@ -0,0 +1,245 @@
+//! Assembles tries from a description, such as a TOML or YAML file, so that the node backend
+//! and tokenizers can be changed without recompiling. For example, in TOML:
+//!
+//! ```toml
+//! backend = "auto"
+//! tokenizer = "whitespace"
+//! search-tokenizer = "unicode-words"
+//!
+//! [options]
+//! max-gap = 1
+//!
+//! [entries]
+//! "new york" = 1
+//! "status re:[0-9]+" = 2
+//! ```
+
+#[cfg(feature = "regex")]
+use crate::immutable::nodes::{RegexSetTrieNode, RegexSetTrieNodeBuilder};
+use crate::{
+    Result,
+    immutable::{
+        AutoTrie, DuplicatePolicy, ImmutableTrie, ImmutableTrieBuilder, OrderedTrie, SmallKeyTrie,
+        StringTrie,
+        nodes::{
+            AutoTrieNodeBuilder, ImmutableTrieNodeBuilder, OrderedTrieNodeBuilder,
+            SmallKeyTrieNodeBuilder, StringTrieNodeBuilder, parse_gap, pattern_of,
+        },
+    },
+    tokenization::{DynTokenizer, Tokenizer, TokenizerKind},
+};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use std::collections::BTreeMap;
+
+/// The tokenizer of a [`ConfiguredTrie`], which is picked at runtime.
+pub type BoxedTokenizer = Box<dyn DynTokenizer + Send + Sync>;
+
+/// The most tokens a key can have for [`NodeBackend::Auto`] to pick
+/// [`NodeBackend::SmallKey`].
+pub const SMALL_KEY_MAX_TOKENS: usize = 4;
+
+/// The kinds of nodes a [`ConfiguredTrie`] can be made of.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NodeBackend {
+    /// [`StringTrieNode`](crate::immutable::nodes::StringTrieNode)s.
+    #[default]
+    String,
+    /// [`OrderedTrieNode`](crate::immutable::nodes::OrderedTrieNode)s.
+    Ordered,
+    /// [`AutoTrieNode`](crate::immutable::nodes::AutoTrieNode)s, which also support
+    /// [`re:`](crate::immutable::nodes::PATTERN_PREFIX) pattern tokens when the `regex` feature is
+    /// enabled. If every key has at most [`SMALL_KEY_MAX_TOKENS`] tokens and none of them are
+    /// gaps or patterns, [`SmallKey`](Self::SmallKey) nodes are used instead.
+    Auto,
+    /// [`SmallKeyTrieNode`](crate::immutable::nodes::SmallKeyTrieNode)s, for dictionaries whose
+    /// keys have at most [`SMALL_KEY_MAX_TOKENS`] tokens.
+    SmallKey,
+    /// [`RegexSetTrieNode`]s, where every key part is a regex.
+    #[cfg(feature = "regex")]
+    RegexSet,
+}
+
+/// Options for a [`TrieConfig`]. Anything that isn't given is left as its default.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ConfigOptions {
+    /// See [`ImmutableTrie::with_max_gap`].
+    pub max_gap: usize,
+    /// See [`ImmutableTrie::with_version`].
+    pub version: u32,
+    /// See [`ImmutableTrieBuilder::with_pruning_hints`].
+    pub pruning_hints: bool,
+    /// Fail if two entries have the same key once they're tokenized, instead of keeping the
+    /// last one.
+    pub reject_duplicates: bool,
+}
+
+/// A description of a trie. See the [module docs](self) for an example.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case", bound(deserialize = "V: DeserializeOwned"))]
+pub struct TrieConfig<V> {
+    #[serde(default)]
+    pub backend: NodeBackend,
+    /// The tokenizer keys are split with.
+    #[serde(default)]
+    pub tokenizer: TokenizerKind,
+    /// The tokenizer search strings are split with. If this isn't given, it's the same as
+    /// [`tokenizer`](Self::tokenizer).
+    #[serde(default)]
+    pub search_tokenizer: Option<TokenizerKind>,
+    #[serde(default)]
+    pub options: ConfigOptions,
+    #[serde(default = "BTreeMap::new")]
+    pub entries: BTreeMap<String, V>,
+}
+
+impl<V: DeserializeOwned> TrieConfig<V> {
+    #[cfg(feature = "toml")]
+    pub fn from_toml(config: &str) -> Result<Self> {
+        Ok(toml::from_str(config)?)
+    }
+
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(config: &str) -> Result<Self> {
+        Ok(serde_yaml_ng::from_str(config)?)
+    }
+}
+
+impl<V> TrieConfig<V> {
+    pub fn build(self) -> Result<ConfiguredTrie<V>> {
+        Ok(match self.backend {
+            NodeBackend::String => {
+                ConfiguredTrie::String(self.build_with(StringTrieNodeBuilder::default())?)
+            }
+            NodeBackend::Ordered => {
+                ConfiguredTrie::Ordered(self.build_with(OrderedTrieNodeBuilder::default())?)
+            }
+            NodeBackend::Auto if self.has_small_keys() => {
+                ConfiguredTrie::SmallKey(self.build_with(SmallKeyTrieNodeBuilder::default())?)
+            }
+            NodeBackend::Auto => {
+                ConfiguredTrie::Auto(self.build_with(AutoTrieNodeBuilder::default())?)
+            }
+            NodeBackend::SmallKey => {
+                ConfiguredTrie::SmallKey(self.build_with(SmallKeyTrieNodeBuilder::default())?)
+            }
+            #[cfg(feature = "regex")]
+            NodeBackend::RegexSet => {
+                ConfiguredTrie::RegexSet(self.build_with(RegexSetTrieNodeBuilder::default())?)
+            }
+        })
+    }
+
+    /// Checks if every key is short enough for a [`SmallKeyTrie`] and only has literal tokens.
+    fn has_small_keys(&self) -> bool {
+        let tokenizer = self.tokenizer.build();
+        self.entries.keys().all(|key| {
+            let tokens = tokenizer.tokenize(key);
+            tokens.len() <= SMALL_KEY_MAX_TOKENS
+                && tokens
+                    .iter()
+                    .all(|token| parse_gap(token).is_none() && pattern_of(token).is_none())
+        })
+    }
+
+    fn build_with<B>(self, builder: B) -> Result<ImmutableTrie<B::Node, V, BoxedTokenizer>>
+    where
+        B: ImmutableTrieNodeBuilder<V>,
+    {
+        let mut trie_builder = ImmutableTrieBuilder::new(self.tokenizer.build(), builder);
+        if self.options.reject_duplicates {
+            trie_builder = trie_builder.with_duplicate_policy(DuplicatePolicy::Error);
+        }
+        if self.options.pruning_hints {
+            trie_builder = trie_builder.with_pruning_hints();
+        }
+        trie_builder.reserve(self.entries.len());
+        for (key, value) in self.entries {
+            trie_builder.add(key, value)?;
+        }
+        let search_tokenizer = self.search_tokenizer.unwrap_or(self.tokenizer);
+        Ok(trie_builder
+            .build(search_tokenizer.build())?
+            .with_max_gap(self.options.max_gap)
+            .with_version(self.options.version))
+    }
+}
+
+/// A trie built from a [`TrieConfig`], whose node backend is picked at runtime.
+pub enum ConfiguredTrie<V> {
+    String(StringTrie<V, BoxedTokenizer>),
+    Ordered(OrderedTrie<V, BoxedTokenizer>),
+    Auto(AutoTrie<V, BoxedTokenizer>),
+    SmallKey(SmallKeyTrie<V, SMALL_KEY_MAX_TOKENS, BoxedTokenizer>),
+    #[cfg(feature = "regex")]
+    RegexSet(ImmutableTrie<RegexSetTrieNode<V>, V, BoxedTokenizer>),
+}
+
+/// Calls the same method on whichever trie is configured.
+macro_rules! dispatch {
+    ($self:ident, $trie:ident => $call:expr) => {
+        match $self {
+            Self::String($trie) => $call,
+            Self::Ordered($trie) => $call,
+            Self::Auto($trie) => $call,
+            Self::SmallKey($trie) => $call,
+            #[cfg(feature = "regex")]
+            Self::RegexSet($trie) => $call,
+        }
+    };
+}
+
+impl<V> ConfiguredTrie<V> {
+    #[inline]
+    pub fn backend(&self) -> NodeBackend {
+        match self {
+            Self::String(_) => NodeBackend::String,
+            Self::Ordered(_) => NodeBackend::Ordered,
+            Self::Auto(_) => NodeBackend::Auto,
+            Self::SmallKey(_) => NodeBackend::SmallKey,
+            #[cfg(feature = "regex")]
+            Self::RegexSet(_) => NodeBackend::RegexSet,
+        }
+    }
+
+    /// See [`ImmutableTrie::find_any`].
+    pub fn find_any<S: AsRef<str>>(&self, search_str: S) -> Option<&V> {
+        dispatch!(self, trie => trie.find_any(search_str))
+    }
+
+    /// See [`ImmutableTrie::find_all`].
+    pub fn find_all<S: AsRef<str>>(&self, search_str: S) -> Vec<&V> {
+        dispatch!(self, trie => trie.find_all(search_str))
+    }
+
+    /// See [`ImmutableTrie::find_longest`].
+    pub fn find_longest<S: AsRef<str>>(&self, search_str: S) -> Vec<&V> {
+        dispatch!(self, trie => trie.find_longest(search_str))
+    }
+
+    /// See [`ImmutableTrie::find_all_with_keys`].
+    pub fn find_all_with_keys<S: AsRef<str>>(&self, search_str: S) -> Vec<(Vec<&str>, &V)> {
+        dispatch!(self, trie => trie.find_all_with_keys(search_str))
+    }
+
+    /// See [`ImmutableTrie::get_exact`].
+    pub fn get_exact<S: AsRef<str>>(&self, key: S) -> Option<&V> {
+        dispatch!(self, trie => trie.get_exact(key))
+    }
+
+    /// See [`ImmutableTrie::contains_key`].
+    pub fn contains_key<S: AsRef<str>>(&self, key: S) -> bool {
+        dispatch!(self, trie => trie.contains_key(key))
+    }
+
+    /// See [`ImmutableTrie::num_keys`].
+    pub fn num_keys(&self) -> usize {
+        dispatch!(self, trie => trie.num_keys())
+    }
+
+    /// See [`ImmutableTrie::version`].
+    pub fn version(&self) -> u32 {
+        dispatch!(self, trie => trie.version())
+    }
+}
@@ -0,0 +1,14 @@
+//! Small public corpora for tests and examples. Each corpus has one entry per line, and lines
+//! starting with `#` are comments.
+
+pub const NAMES: &str = include_str!("../corpora/names.txt");
+pub const CITIES: &str = include_str!("../corpora/cities.txt");
+pub const PROFANITY: &str = include_str!("../corpora/profanity.txt");
+
+/// Gets the entries of a corpus, skipping comments and blank lines.
+pub fn entries(corpus: &str) -> impl Iterator<Item = &str> {
+    corpus
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
@@ -1 +1,122 @@
+use snafu::Snafu;
 
+#[derive(Debug, Snafu)]
+#[snafu(visibility(pub(crate)))]
+pub enum Error {
+    #[cfg(feature = "regex")]
+    #[snafu(context(false), display("Failed to build regexes: {source}"))]
+    Regex { source: regex::Error },
+    #[cfg(feature = "regex-filtered")]
+    #[snafu(context(false), display("Failed to build regexes: {source}"))]
+    RegexFilteredBuild { source: regex_filtered::BuildError },
+    #[cfg(feature = "aho-corasick")]
+    #[snafu(context(false), display("Failed to build the automaton: {source}"))]
+    AhoCorasick { source: aho_corasick::BuildError },
+    #[cfg(feature = "csv")]
+    #[snafu(context(false), display("Invalid CSV: {source}"))]
+    Csv { source: csv::Error },
+    #[cfg(feature = "toml")]
+    #[snafu(context(false), display("Invalid TOML: {source}"))]
+    Toml { source: toml::de::Error },
+    #[cfg(feature = "yaml")]
+    #[snafu(context(false), display("Invalid YAML: {source}"))]
+    Yaml { source: serde_yaml_ng::Error },
+    #[cfg(feature = "json")]
+    #[snafu(context(false), display("Invalid JSON: {source}"))]
+    Json { source: serde_json::Error },
+    #[snafu(context(false), display("Failed to read: {source}"))]
+    Io { source: std::io::Error },
+    #[snafu(display("Failed to add the key on line {line}: {source}"))]
+    InvalidLine {
+        line: usize,
+        #[snafu(source(from(Error, Box::new)))]
+        source: Box<Error>,
+    },
+    #[snafu(display("Failed to compile the pattern {pattern:?}: {source}"))]
+    PatternCompile {
+        pattern: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    #[snafu(display("The keys {first:?} and {second:?} both compile to the pattern {pattern:?}"))]
+    BuildConflict {
+        first: String,
+        second: String,
+        pattern: String,
+    },
+    #[snafu(display("Invalid {field} {value:?}: {source}"))]
+    InvalidField {
+        field: &'static str,
+        value: String,
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+    #[snafu(display("No tokenizer is named {name:?}"))]
+    UnknownTokenizer { name: String },
+    #[snafu(display("No predicate is registered as {label:?}"))]
+    UnknownPredicate { label: String },
+    #[snafu(display("{key:?} is not a valid number or range of numbers"))]
+    InvalidNumericRange { key: String },
+    #[snafu(display("The text isn't valid UTF-8: {source}"))]
+    InvalidUtf8 { source: std::str::Utf8Error },
+    #[snafu(display("Keys can have at most {max} tokens"))]
+    KeyTooLong { max: usize },
+    #[snafu(display("The trie does not match its manifest: {field} differs"))]
+    ManifestMismatch { field: &'static str },
+    #[snafu(display("The key {key:?} starts or ends with a gap"))]
+    MisplacedGap { key: String },
+    #[snafu(display("Flat tries don't support gap tokens such as {token:?}"))]
+    GapNotSupported { token: String },
+    #[snafu(display("Flat tries don't support a max gap, but the trie's is {max_gap}"))]
+    MaxGapNotSupported { max_gap: usize },
+    #[snafu(display("Flat tries can have at most {max} nodes, tokens or values"))]
+    FlatTrieTooLarge { max: u32 },
+    #[snafu(display("The token {token:?} contains the reserved character U+001F"))]
+    ReservedCharacter { token: String },
+    #[snafu(display("The key {key:?} has no tokens"))]
+    EmptyKey { key: String },
+    #[snafu(display("The key {key:?} was added more than once"))]
+    DuplicateKey { key: String },
+    #[snafu(display("Invalid pattern {pattern:?} at token {index} of the key {key:?}: {source}"))]
+    InvalidPattern {
+        key: String,
+        index: usize,
+        pattern: String,
+        #[snafu(source(from(Error, Box::new)))]
+        source: Box<Error>,
+    },
+    #[snafu(display("Expected {expected} query fields, but got {found}"))]
+    FieldCountMismatch { expected: usize, found: usize },
+}
+
+/// Wraps an error from compiling a single pattern in [`Error::PatternCompile`].
+pub(crate) fn pattern_compile<E>(pattern: &str) -> impl FnOnce(E) -> Error + '_
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    move |source| Error::PatternCompile {
+        pattern: pattern.to_owned(),
+        source: Box::new(source),
+    }
+}
+
+impl Error {
+    /// Replaces the key of an [`InvalidPattern`](Self::InvalidPattern) error with the key as it
+    /// was before it was tokenized.
+    pub(crate) fn with_key(self, key: &str) -> Self {
+        match self {
+            Self::InvalidPattern {
+                index,
+                pattern,
+                source,
+                ..
+            } => Self::InvalidPattern {
+                key: key.to_owned(),
+                index,
+                pattern,
+                source,
+            },
+            other => other,
+        }
+    }
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,87 @@
+use super::{ImmutableTrieBuilder, ImmutableTrieNodeBuilder};
+use crate::{Result, tokenization::Tokenizer};
+
+/// A value that may have been derived from another entry, such as an acronym generated by
+/// [`ImmutableTrieBuilder::add_with_acronym`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Derivable<V> {
+    pub value: V,
+    pub derived: bool,
+}
+
+impl<V> Derivable<V> {
+    #[inline]
+    pub fn original(value: V) -> Self {
+        Self {
+            value,
+            derived: false,
+        }
+    }
+
+    #[inline]
+    pub fn derived(value: V) -> Self {
+        Self {
+            value,
+            derived: true,
+        }
+    }
+}
+
+impl<V> From<V> for Derivable<V> {
+    #[inline]
+    fn from(value: V) -> Self {
+        Self::original(value)
+    }
+}
+
+/// Creates an acronym out of the first character of each token, or returns `None` if there are
+/// too few tokens for an acronym to make sense.
+fn acronym<S: AsRef<str>>(tokens: &[S]) -> Option<String> {
+    if tokens.len() < 2 {
+        return None;
+    }
+    tokens
+        .iter()
+        .map(|t| t.as_ref().chars().next())
+        .collect()
+}
+
+impl<B, V, T> ImmutableTrieBuilder<B, Derivable<V>, T>
+where
+    B: ImmutableTrieNodeBuilder<Derivable<V>>,
+    V: Clone,
+    T: Tokenizer,
+{
+    /// Adds the key and, if it's made up of multiple tokens, an acronym of it as well. For
+    /// example, adding `north atlantic treaty organization` will also add `nato`. The value of
+    /// the acronym is marked as [derived](Derivable::derived).
+    ///
+    /// Acronyms never replace the values of keys that were added on their own, and keys that
+    /// are added on their own always replace acronyms. Otherwise, the builder's
+    /// [`DuplicatePolicy`](super::DuplicatePolicy) is followed.
+    pub fn add_with_acronym<S: AsRef<str>>(&mut self, key: S, value: V) -> Result<()> {
+        let tokens = self.tokenizer.tokenize(key);
+        let policy = self.duplicate_policy;
+        if let Some(acronym) = acronym(&tokens) {
+            let acronym = [acronym];
+            self.add_tokens_with(
+                &acronym,
+                Derivable::derived(value.clone()),
+                |existing, new| {
+                    if existing.derived {
+                        policy.resolve(&acronym, existing, new)
+                    } else {
+                        Ok(existing)
+                    }
+                },
+            )?;
+        }
+        self.add_tokens_with(&tokens, Derivable::original(value), |existing, new| {
+            if existing.derived {
+                Ok(new)
+            } else {
+                policy.resolve(&tokens, existing, new)
+            }
+        })
+    }
+}
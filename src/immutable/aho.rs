@@ -0,0 +1,188 @@
+use super::nodes::parse_gap;
+use crate::{
+    Result,
+    error::{EmptyKeySnafu, GapNotSupportedSnafu, ReservedCharacterSnafu},
+    tokenization::{Tokenizer, TokenizerExt, WhitespaceTokenizer},
+};
+use aho_corasick::{AhoCorasick, MatchKind};
+use educe::Educe;
+use std::collections::HashMap;
+
+/// Separates the tokens of keys and search strings, so that keys only match whole tokens. Keys
+/// can't contain it.
+const SEPARATOR: char = '\u{1F}';
+
+/// Joins tokens into the string that's matched against, with a separator before, between and
+/// after them. Tokens that contain the separator can't match any key, so they're left empty
+/// instead of being allowed to match across token boundaries.
+fn join_tokens<S: AsRef<str>>(tokens: &[S]) -> String {
+    let mut joined = String::from(SEPARATOR);
+    for token in tokens {
+        if !token.as_ref().contains(SEPARATOR) {
+            joined.push_str(token.as_ref());
+        }
+        joined.push(SEPARATOR);
+    }
+    joined
+}
+
+/// A matcher for dictionaries of literal keys, which compiles every key into a single
+/// [Aho-Corasick](aho_corasick) automaton. Search strings are matched in one pass instead of
+/// walking a trie from every token, which is much faster for large dictionaries. Gap tokens and
+/// regex patterns aren't supported.
+#[derive(Clone, Educe)]
+#[educe(Debug)]
+pub struct AhoCorasickTrie<V, T = WhitespaceTokenizer> {
+    #[educe(Debug(ignore))]
+    tokenizer: T,
+    #[educe(Debug(ignore))]
+    automaton: AhoCorasick,
+    /// The value of each of the automaton's patterns.
+    values: Vec<V>,
+}
+
+impl<V, T: Tokenizer> AhoCorasickTrie<V, T> {
+    /// The number of keys in the automaton.
+    #[inline]
+    pub fn num_keys(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Finds the value of a key in the search string. Like
+    /// [`ImmutableTrie::find_any`](super::ImmutableTrie::find_any), this is the shortest key that
+    /// starts at the first token any key starts at, which is the first value
+    /// [`find_all`](Self::find_all) would return.
+    pub fn find_any<S: AsRef<str>>(&self, search_str: S) -> Option<&V> {
+        self.find_any_tokens(&self.tokenizer.tokenize_ref(search_str.as_ref()))
+    }
+
+    /// Like [`find_any`](Self::find_any), but with already tokenized input.
+    pub fn find_any_tokens<S: AsRef<str>>(&self, tokens: &[S]) -> Option<&V> {
+        self.automaton
+            .find_overlapping_iter(&join_tokens(tokens))
+            .min_by_key(|found| (found.start(), found.end()))
+            .map(|found| &self.values[found.pattern()])
+    }
+
+    /// Finds the values of every key in the search string, in the same order as
+    /// [`ImmutableTrie::find_all`](super::ImmutableTrie::find_all).
+    pub fn find_all<S: AsRef<str>>(&self, search_str: S) -> Vec<&V> {
+        self.find_all_tokens(&self.tokenizer.tokenize_ref(search_str.as_ref()))
+    }
+
+    /// Like [`find_all`](Self::find_all), but with already tokenized input.
+    pub fn find_all_tokens<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<&V> {
+        let mut found: Vec<_> = self
+            .automaton
+            .find_overlapping_iter(&join_tokens(tokens))
+            .collect();
+        found.sort_by_key(|found| (found.start(), found.end()));
+        found
+            .into_iter()
+            .map(|found| &self.values[found.pattern()])
+            .collect()
+    }
+}
+
+/// Builds an [`AhoCorasickTrie`]. Like an [`ImmutableTrieBuilder`](super::ImmutableTrieBuilder),
+/// keys are tokenized with the builder's tokenizer.
+#[derive(Clone, Educe)]
+#[educe(Debug)]
+pub struct AhoCorasickTrieBuilder<V, T = WhitespaceTokenizer> {
+    #[educe(Debug(ignore))]
+    tokenizer: T,
+    patterns: Vec<String>,
+    values: Vec<V>,
+    /// The index of each pattern, so that adding a key again replaces its value.
+    indexes: HashMap<String, usize>,
+}
+
+impl<V, T: Tokenizer + Default> Default for AhoCorasickTrieBuilder<V, T> {
+    #[inline]
+    fn default() -> Self {
+        Self::with_tokenizer(T::default())
+    }
+}
+
+impl<V, T: Tokenizer> AhoCorasickTrieBuilder<V, T> {
+    pub fn with_tokenizer(tokenizer: T) -> Self {
+        Self {
+            tokenizer,
+            patterns: Vec::new(),
+            values: Vec::new(),
+            indexes: HashMap::new(),
+        }
+    }
+
+    /// Reserves room for at least `additional` more keys.
+    pub fn reserve(&mut self, additional: usize) {
+        self.patterns.reserve(additional);
+        self.values.reserve(additional);
+        self.indexes.reserve(additional);
+    }
+
+    /// Adds a key, replacing its value if it already has one. Keys without any tokens (which
+    /// the automaton can't match), keys with [gap tokens](super::nodes::parse_gap) and keys with
+    /// tokens that contain U+001F (which separates tokens in the automaton) are rejected.
+    pub fn add<S: AsRef<str>>(&mut self, key: S, value: V) -> Result<()> {
+        let key = key.as_ref();
+        let tokens = self.tokenizer.tokenize(key);
+        if tokens.is_empty() {
+            return EmptyKeySnafu { key }.fail();
+        }
+        self.add_tokens(&tokens, value)
+    }
+
+    /// Like [`add`](Self::add), but with an already tokenized key.
+    pub fn add_tokens<S: AsRef<str>>(&mut self, tokens: &[S], value: V) -> Result<()> {
+        if tokens.is_empty() {
+            return EmptyKeySnafu { key: "" }.fail();
+        }
+        if let Some(token) = tokens
+            .iter()
+            .find(|token| parse_gap(token.as_ref()).is_some())
+        {
+            return GapNotSupportedSnafu {
+                token: token.as_ref(),
+            }
+            .fail();
+        }
+        if let Some(token) = tokens
+            .iter()
+            .find(|token| token.as_ref().contains(SEPARATOR))
+        {
+            return ReservedCharacterSnafu {
+                token: token.as_ref(),
+            }
+            .fail();
+        }
+        let pattern = join_tokens(tokens);
+        match self.indexes.get(&pattern) {
+            Some(idx) => self.values[*idx] = value,
+            None => {
+                self.indexes.insert(pattern.clone(), self.patterns.len());
+                self.patterns.push(pattern);
+                self.values.push(value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Compiles the keys into an automaton that searches strings tokenized by
+    /// `trie_tokenizer`.
+    pub fn build<TT: Tokenizer>(self, trie_tokenizer: TT) -> Result<AhoCorasickTrie<V, TT>> {
+        let automaton = AhoCorasick::builder()
+            .match_kind(MatchKind::Standard)
+            .build(&self.patterns)?;
+        Ok(AhoCorasickTrie {
+            tokenizer: trie_tokenizer,
+            automaton,
+            values: self.values,
+        })
+    }
+
+    #[inline]
+    pub fn build_default<TT: Tokenizer + Default>(self) -> Result<AhoCorasickTrie<V, TT>> {
+        self.build(TT::default())
+    }
+}
@@ -0,0 +1,53 @@
+use super::{ImmutableTrie, ImmutableTrieNode};
+use crate::tokenization::Tokenizer;
+use std::{collections::BTreeMap, ops::Range};
+
+/// All of the distinct values that matched the same range of tokens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpanMatches<'a, V> {
+    pub tokens: Range<usize>,
+    pub values: Vec<&'a V>,
+}
+
+impl<V> SpanMatches<'_, V> {
+    /// If more than one distinct value matched this span.
+    #[inline]
+    pub fn is_ambiguous(&self) -> bool {
+        self.values.len() > 1
+    }
+
+    /// How confident we can be that any single value is the correct one for this span, assuming
+    /// that all of the competing values are equally likely.
+    #[inline]
+    pub fn confidence(&self) -> f64 {
+        1.0 / self.values.len().max(1) as f64
+    }
+}
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    V: PartialEq,
+    T: Tokenizer,
+{
+    /// Finds all matches, grouping together the values that matched the exact same span so that
+    /// contested spans can be told apart from unrelated matches.
+    pub fn find_grouped<S: AsRef<str>>(&self, search_str: S) -> Vec<SpanMatches<'_, V>> {
+        let tokens = self.tokenizer.tokenize(search_str.as_ref());
+        let mut found = Vec::new();
+        for i in 0..tokens.len() {
+            let mut by_depth: BTreeMap<usize, Vec<&V>> = BTreeMap::new();
+            for (depth, value) in self.matches_at(&tokens[i..]) {
+                let values = by_depth.entry(depth).or_default();
+                if !values.contains(&value) {
+                    values.push(value);
+                }
+            }
+            found.extend(by_depth.into_iter().map(|(depth, values)| SpanMatches {
+                tokens: i..i + depth,
+                values,
+            }));
+        }
+        found
+    }
+}
@@ -0,0 +1,65 @@
+use super::{ImmutableTrie, ImmutableTrieNode};
+use crate::tokenization::{Token, Tokenizer};
+use std::ops::Range;
+
+/// A match found in a sequence of pre-tokenized [`Token`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnnotatedMatch<'a, 't, V> {
+    pub value: &'a V,
+    pub tokens: &'t [Token],
+}
+
+impl<V> AnnotatedMatch<'_, '_, V> {
+    /// The byte span that the matched tokens cover in the source text.
+    #[inline]
+    pub fn span(&self) -> Range<usize> {
+        let start = self.tokens.first().map(|t| t.start).unwrap_or_default();
+        let end = self.tokens.last().map(|t| t.end).unwrap_or(start);
+        start..end
+    }
+
+    /// The part-of-speech tags of the matched tokens.
+    #[inline]
+    pub fn pos_tags(&self) -> impl Iterator<Item = Option<&str>> {
+        self.tokens.iter().map(|t| t.pos_tag.as_deref())
+    }
+}
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    /// Finds all matches in already tokenized input. The tokens' text is matched as-is, so it
+    /// should already be normalized the same way that this trie's keys were.
+    #[inline]
+    pub fn find_all_annotated<'t>(&self, tokens: &'t [Token]) -> Vec<AnnotatedMatch<'_, 't, V>> {
+        self.find_all_annotated_with(tokens, |_| true)
+    }
+
+    /// Like [`find_all_annotated`](Self::find_all_annotated), but only keeps the matches that
+    /// `filter` accepts. This can be used to check each entry's part-of-speech constraints
+    /// against the tags of the tokens it matched.
+    pub fn find_all_annotated_with<'t, F>(
+        &self,
+        tokens: &'t [Token],
+        mut filter: F,
+    ) -> Vec<AnnotatedMatch<'_, 't, V>>
+    where
+        F: FnMut(&AnnotatedMatch<'_, 't, V>) -> bool,
+    {
+        let mut found = Vec::new();
+        for i in 0..tokens.len() {
+            for (depth, value) in self.matches_at(&tokens[i..]) {
+                let found_match = AnnotatedMatch {
+                    value,
+                    tokens: &tokens[i..i + depth],
+                };
+                if filter(&found_match) {
+                    found.push(found_match);
+                }
+            }
+        }
+        found
+    }
+}
@@ -0,0 +1,26 @@
+use super::{ImmutableTrie, ImmutableTrieNode};
+use crate::tokenization::Tokenizer;
+use rayon::prelude::*;
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V> + Sync,
+    V: Sync,
+    T: Tokenizer + Sync,
+{
+    /// Runs [`find_all`](Self::find_all) on each of the documents in parallel, returning the
+    /// results in the same order as the documents.
+    ///
+    /// Tries are never modified after they're built, so a trie whose nodes, values, and tokenizer
+    /// are [`Sync`] can be shared between threads (for example, behind an
+    /// [`Arc`](std::sync::Arc)) without any locking.
+    pub fn find_all_batch<S>(&self, documents: &[S]) -> Vec<Vec<&V>>
+    where
+        S: AsRef<str> + Sync,
+    {
+        documents
+            .par_iter()
+            .map(|document| self.find_all(document))
+            .collect()
+    }
+}
@@ -0,0 +1,138 @@
+use super::{ImmutableTrie, ImmutableTrieNode};
+use crate::{Result, error::pattern_compile, tokenization::Tokenizer};
+use regex::{Regex, RegexBuilder};
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    ops::Range,
+    ptr,
+};
+
+/// A value found by [`ImmutableTrie::find_all_captures`], along with the named groups that each
+/// of the matched tokens captured.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Captured<'a, V> {
+    pub value: &'a V,
+    /// The range of tokens that matched.
+    pub tokens: Range<usize>,
+    /// The named groups captured by the pattern each token matched, in token order. Groups
+    /// that didn't participate in the match are left out.
+    pub groups: Vec<HashMap<String, String>>,
+}
+
+impl<V> Captured<'_, V> {
+    /// Gets the text captured by the named group in any of the tokens. If more than one token
+    /// captured the group, the last one is returned.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.groups
+            .iter()
+            .rev()
+            .find_map(|groups| groups.get(name))
+            .map(String::as_str)
+    }
+}
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    /// Like [`find_all`](Self::find_all), but also returns the named groups captured by each
+    /// matched token. This is meant for tries with regex keys, such as a
+    /// [`RegexSetTrieNode`](super::nodes::RegexSetTrieNode) or the
+    /// [pattern tokens](super::nodes::PATTERN_PREFIX) of an
+    /// [`AutoTrieNode`](super::nodes::AutoTrieNode). Each key is matched with the pattern its
+    /// node compiled it to, so the node's anchoring and case sensitivity still apply. Keys that
+    /// are matched literally don't capture anything.
+    pub fn find_all_captures<S: AsRef<str>>(&self, search_str: S) -> Result<Vec<Captured<'_, V>>> {
+        let tokens = self.tokenizer.tokenize(search_str.as_ref());
+        // The same patterns are likely to match more than once, so they're only compiled once.
+        let mut regexes: HashMap<(&str, bool), Regex> = HashMap::new();
+        let mut found = Vec::new();
+        for i in 0..tokens.len() {
+            for (depth, keys, value) in self.matches_with_keys_at(&tokens[i..]) {
+                let mut groups = Vec::with_capacity(keys.len());
+                for (pattern, token) in key_patterns(&self.root, &keys)
+                    .into_iter()
+                    .zip(&tokens[i..])
+                {
+                    let Some(pattern) = pattern else {
+                        groups.push(HashMap::new());
+                        continue;
+                    };
+                    let regex = match regexes.entry(pattern) {
+                        Entry::Occupied(entry) => entry.into_mut(),
+                        Entry::Vacant(entry) => {
+                            let (source, case_sensitive) = pattern;
+                            entry.insert(
+                                RegexBuilder::new(source)
+                                    .unicode(true)
+                                    .case_insensitive(!case_sensitive)
+                                    .build()
+                                    .map_err(pattern_compile(source))?,
+                            )
+                        }
+                    };
+                    groups.push(named_groups(regex, token));
+                }
+                found.push(Captured {
+                    value,
+                    tokens: i..i + depth,
+                    groups,
+                });
+            }
+        }
+        Ok(found)
+    }
+}
+
+/// Follows the keys that led to a value down from the root, getting the
+/// [pattern](ImmutableTrieNode::key_pattern) that each of them was compiled to. Keys that are
+/// matched literally, tail tokens, gaps and tokens skipped because of the max gap get `None`.
+fn key_patterns<'a, N, V>(root: &'a N, keys: &[&str]) -> Vec<Option<(&'a str, bool)>>
+where
+    N: ImmutableTrieNode<V>,
+{
+    let mut node = root;
+    // How many of the current node's tail tokens are still to come.
+    let mut tail = 0;
+    // The key of the gap the current node was reached with, which is repeated for each token
+    // that it skipped.
+    let mut gap = None;
+    let mut patterns = Vec::with_capacity(keys.len());
+    for key in keys {
+        if key.is_empty() || gap == Some(*key) {
+            patterns.push(None);
+            continue;
+        }
+        if tail > 0 {
+            tail -= 1;
+            patterns.push(None);
+            continue;
+        }
+        let Some((_, child)) = node.children().into_iter().find(|(other, _)| other == key) else {
+            patterns.push(None);
+            continue;
+        };
+        let is_gap = node.gaps().any(|(_, other)| ptr::eq(other, child));
+        patterns.push(if is_gap { None } else { node.key_pattern(key) });
+        gap = is_gap.then_some(*key);
+        tail = child.tail().len();
+        node = child;
+    }
+    patterns
+}
+
+fn named_groups(regex: &Regex, token: &str) -> HashMap<String, String> {
+    let Some(captures) = regex.captures(token) else {
+        return HashMap::new();
+    };
+    regex
+        .capture_names()
+        .flatten()
+        .filter_map(|name| {
+            captures
+                .name(name)
+                .map(|group| (String::from(name), String::from(group.as_str())))
+        })
+        .collect()
+}
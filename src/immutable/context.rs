@@ -0,0 +1,153 @@
+use super::{ImmutableTrie, ImmutableTrieBuilder, ImmutableTrieNode, ImmutableTrieNodeBuilder};
+use crate::{Result, tokenization::Tokenizer};
+use std::{collections::HashSet, ops::Range};
+
+/// A constraint on the tokens surrounding a match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContextRule {
+    /// One of the tokens must appear within `within` tokens before the match.
+    PrecededBy {
+        within: usize,
+        tokens: HashSet<String>,
+    },
+    /// One of the tokens must appear within `within` tokens after the match.
+    FollowedBy {
+        within: usize,
+        tokens: HashSet<String>,
+    },
+    /// None of the tokens may appear within `within` tokens before the match.
+    NotPrecededBy {
+        within: usize,
+        tokens: HashSet<String>,
+    },
+    /// None of the tokens may appear within `within` tokens after the match.
+    NotFollowedBy {
+        within: usize,
+        tokens: HashSet<String>,
+    },
+}
+
+impl ContextRule {
+    pub fn preceded_by<I, S>(within: usize, tokens: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::PrecededBy {
+            within,
+            tokens: tokens.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn followed_by<I, S>(within: usize, tokens: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::FollowedBy {
+            within,
+            tokens: tokens.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn not_preceded_by<I, S>(within: usize, tokens: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::NotPrecededBy {
+            within,
+            tokens: tokens.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    pub fn not_followed_by<I, S>(within: usize, tokens: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::NotFollowedBy {
+            within,
+            tokens: tokens.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Checks this rule for a match that covers `span` (a range of token indexes) in `tokens`.
+    pub fn is_satisfied<S: AsRef<str>>(&self, tokens: &[S], span: Range<usize>) -> bool {
+        let before = |within: usize| &tokens[span.start.saturating_sub(within)..span.start];
+        let after = |within: usize| &tokens[span.end..(span.end + within).min(tokens.len())];
+        let any_in = |window: &[S], wanted: &HashSet<String>| {
+            window.iter().any(|t| wanted.contains(t.as_ref()))
+        };
+        match self {
+            Self::PrecededBy { within, tokens } => any_in(before(*within), tokens),
+            Self::FollowedBy { within, tokens } => any_in(after(*within), tokens),
+            Self::NotPrecededBy { within, tokens } => !any_in(before(*within), tokens),
+            Self::NotFollowedBy { within, tokens } => !any_in(after(*within), tokens),
+        }
+    }
+}
+
+/// A value along with the [`ContextRule`]s that all have to be satisfied for it to match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contextual<V> {
+    pub value: V,
+    pub rules: Vec<ContextRule>,
+}
+
+impl<V> Contextual<V> {
+    #[inline]
+    pub fn new(value: V, rules: Vec<ContextRule>) -> Self {
+        Self { value, rules }
+    }
+
+    pub fn is_satisfied<S: AsRef<str>>(&self, tokens: &[S], span: Range<usize>) -> bool {
+        self.rules
+            .iter()
+            .all(|rule| rule.is_satisfied(tokens, span.clone()))
+    }
+}
+
+impl<V> From<V> for Contextual<V> {
+    #[inline]
+    fn from(value: V) -> Self {
+        Self::new(value, Vec::new())
+    }
+}
+
+impl<B, V, T> ImmutableTrieBuilder<B, Contextual<V>, T>
+where
+    B: ImmutableTrieNodeBuilder<Contextual<V>>,
+    T: Tokenizer,
+{
+    #[inline]
+    pub fn add_with_context<S: AsRef<str>>(
+        &mut self,
+        key: S,
+        value: V,
+        rules: Vec<ContextRule>,
+    ) -> Result<()> {
+        self.add(key, Contextual::new(value, rules))
+    }
+}
+
+impl<N, V, T> ImmutableTrie<N, Contextual<V>, T>
+where
+    N: ImmutableTrieNode<Contextual<V>>,
+    T: Tokenizer,
+{
+    /// Finds all values whose context rules are satisfied by the tokens surrounding the match.
+    pub fn find_all_in_context<S: AsRef<str>>(&self, search_str: S) -> Vec<&V> {
+        let tokens = self.tokenizer.tokenize(search_str.as_ref());
+        let mut found = Vec::new();
+        for i in 0..tokens.len() {
+            found.extend(
+                self.matches_at(&tokens[i..])
+                    .into_iter()
+                    .filter(|(depth, value)| value.is_satisfied(&tokens, i..i + depth))
+                    .map(|(_, value)| &value.value),
+            );
+        }
+        found
+    }
+}
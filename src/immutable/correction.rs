@@ -0,0 +1,93 @@
+use super::{
+    ImmutableTrie, ImmutableTrieNode,
+    nodes::{get_all_from_child_with_max_gap, strip_tail},
+};
+use crate::tokenization::Tokenizer;
+
+/// A value returned by [`ImmutableTrie::find_all_corrected`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Corrected<'a, V> {
+    pub value: &'a V,
+    /// If the value was only found after correcting the first token of the match.
+    pub corrected: bool,
+}
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    /// Like [`find_all`](Self::find_all), but if nothing matches then the search is retried with
+    /// the first token at each position replaced by any key that's one edit away from it. Values
+    /// found this way are marked as [corrected](Corrected::corrected).
+    pub fn find_all_corrected<S: AsRef<str>>(&self, search_str: S) -> Vec<Corrected<'_, V>> {
+        let tokens = self.tokenizer.tokenize(search_str.as_ref());
+        let mut found = Vec::new();
+        for i in 0..tokens.len() {
+            found.extend(
+                self.matches_at(&tokens[i..])
+                    .into_iter()
+                    .map(|(_, value)| Corrected {
+                        value,
+                        corrected: false,
+                    }),
+            );
+        }
+        if !found.is_empty() {
+            return found;
+        }
+        let candidates: Vec<_> = self.root.children();
+        for i in 0..tokens.len() {
+            for (_, child) in candidates
+                .iter()
+                .filter(|(key, _)| is_one_edit_away(&tokens[i], key))
+            {
+                if self.max_gap != 0 {
+                    found.extend(
+                        get_all_from_child_with_max_gap(*child, &tokens[i..], self.max_gap)
+                            .into_iter()
+                            .map(|value| Corrected {
+                                value,
+                                corrected: true,
+                            }),
+                    );
+                    continue;
+                }
+                let Some(rest) = strip_tail(*child, &tokens[i + 1..]) else {
+                    continue;
+                };
+                found.extend(
+                    child
+                        .value()
+                        .into_iter()
+                        .chain(child.get_all(rest))
+                        .map(|value| Corrected {
+                            value,
+                            corrected: true,
+                        }),
+                );
+            }
+        }
+        found
+    }
+}
+
+/// Checks if the Levenshtein distance between the two strings is exactly one.
+fn is_one_edit_away(a: &str, b: &str) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let (shorter, longer) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    if longer.len() - shorter.len() > 1 {
+        return false;
+    }
+    let prefix = shorter
+        .iter()
+        .zip(&longer)
+        .take_while(|(s, l)| s == l)
+        .count();
+    if shorter.len() == longer.len() {
+        prefix < shorter.len() && shorter[prefix + 1..] == longer[prefix + 1..]
+    } else {
+        shorter[prefix..] == longer[prefix + 1..]
+    }
+}
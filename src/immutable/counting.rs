@@ -0,0 +1,50 @@
+use super::{ImmutableTrie, ImmutableTrieBuilder, ImmutableTrieNode, ImmutableTrieNodeBuilder};
+use crate::{Result, tokenization::Tokenizer};
+use std::collections::HashMap;
+
+impl<B, T> ImmutableTrieBuilder<B, u64, T>
+where
+    B: ImmutableTrieNodeBuilder<u64>,
+    T: Tokenizer,
+{
+    /// Adds one to the number of times the key has been seen.
+    #[inline]
+    pub fn increment<S: AsRef<str>>(&mut self, key: S) -> Result<()> {
+        self.increment_by(key, 1)
+    }
+
+    /// Adds `count` to the number of times the key has been seen.
+    #[inline]
+    pub fn increment_by<S: AsRef<str>>(&mut self, key: S, count: u64) -> Result<()> {
+        self.add_or_merge(key, count, u64::saturating_add)
+    }
+}
+
+impl<N, T> ImmutableTrie<N, u64, T>
+where
+    N: ImmutableTrieNode<u64>,
+    T: Tokenizer,
+{
+    /// Gets the number of times the key was seen, which is `0` if it was never added.
+    #[inline]
+    pub fn frequency<S: AsRef<str>>(&self, key: S) -> u64 {
+        self.get_exact(key).copied().unwrap_or_default()
+    }
+
+    /// Gets the total of every key's count.
+    pub fn total_frequency(&self) -> u64 {
+        self.root
+            .values_recursive()
+            .into_iter()
+            .fold(0, |total, count| total.saturating_add(*count))
+    }
+
+    /// Gets the frequency of every key found in the search string, keyed by the matched tokens
+    /// joined with spaces.
+    pub fn term_frequencies<S: AsRef<str>>(&self, search_str: S) -> HashMap<String, u64> {
+        self.find_all_with_keys(search_str)
+            .into_iter()
+            .map(|(key, count)| (key.join(" "), *count))
+            .collect()
+    }
+}
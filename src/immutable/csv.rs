@@ -0,0 +1,183 @@
+use super::{ImmutableTrieBuilder, ImmutableTrieNodeBuilder, Weighted};
+use crate::{
+    Error, Result,
+    error::{EmptyKeySnafu, InvalidLineSnafu},
+    tokenization::{DynTokenizer, Tokenizer, TokenizerKind},
+};
+use ::csv::{ReaderBuilder, StringRecord, Trim};
+use snafu::ResultExt;
+use std::{collections::HashMap, io::Read, str::FromStr};
+
+/// A row of a CSV rule file: `key,value[,weight][,tokenizer]`.
+struct CsvRule<V> {
+    key: String,
+    value: V,
+    weight: Option<u32>,
+    tokenizer: Option<TokenizerKind>,
+}
+
+impl<V> CsvRule<V>
+where
+    V: FromStr,
+    V::Err: std::error::Error + Send + Sync + 'static,
+{
+    fn parse(record: &StringRecord) -> Result<Self> {
+        let field = |idx| record.get(idx).filter(|field| !field.is_empty());
+        let key = field(0).unwrap_or_default();
+        if key.is_empty() {
+            return EmptyKeySnafu { key }.fail();
+        }
+        Ok(Self {
+            key: key.to_owned(),
+            value: parse_field("value", record.get(1).unwrap_or_default())?,
+            weight: field(2)
+                .map(|weight| parse_field("weight", weight))
+                .transpose()?,
+            tokenizer: field(3).map(TokenizerKind::from_str).transpose()?,
+        })
+    }
+}
+
+fn parse_field<F>(field: &'static str, value: &str) -> Result<F>
+where
+    F: FromStr,
+    F::Err: std::error::Error + Send + Sync + 'static,
+{
+    value.parse().map_err(|source| Error::InvalidField {
+        field,
+        value: value.to_owned(),
+        source: Box::new(source),
+    })
+}
+
+/// Reads every rule in a CSV file. The first row is a header, lines that start with `#` are
+/// comments, and whitespace around each field is ignored. If a row can't be read or `add` fails,
+/// the error says which line it's on, starting at 1.
+fn read_rules<R, V, F>(reader: R, mut add: F) -> Result<()>
+where
+    R: Read,
+    V: FromStr,
+    V::Err: std::error::Error + Send + Sync + 'static,
+    F: FnMut(CsvRule<V>) -> Result<()>,
+{
+    let mut reader = ReaderBuilder::new()
+        .comment(Some(b'#'))
+        .flexible(true)
+        .trim(Trim::All)
+        .from_reader(reader);
+    for record in reader.records() {
+        let record = record?;
+        let line = record
+            .position()
+            .map_or(0, |position| position.line() as usize);
+        CsvRule::parse(&record)
+            .and_then(&mut add)
+            .context(InvalidLineSnafu { line })?;
+    }
+    Ok(())
+}
+
+impl<B, V, T> ImmutableTrieBuilder<B, V, T>
+where
+    B: ImmutableTrieNodeBuilder<V>,
+    T: Tokenizer,
+{
+    /// Adds a key, tokenizing it with a built-in tokenizer if one is given, or with this
+    /// builder's tokenizer otherwise. The built-in tokenizers are only built once per load.
+    fn add_with_kind(
+        &mut self,
+        key: &str,
+        value: V,
+        kind: Option<TokenizerKind>,
+        tokenizers: &mut HashMap<TokenizerKind, Box<dyn DynTokenizer + Send + Sync>>,
+    ) -> Result<()> {
+        let Some(kind) = kind else {
+            return self.add(key, value);
+        };
+        let tokens = tokenizers
+            .entry(kind)
+            .or_insert_with(|| kind.build())
+            .tokenize_dyn(key);
+        if tokens.is_empty() {
+            return EmptyKeySnafu { key }.fail();
+        }
+        self.add_tokens(tokens, value)
+    }
+}
+
+impl<B, V, T> ImmutableTrieBuilder<B, V, T>
+where
+    B: ImmutableTrieNodeBuilder<V> + Default,
+    T: Tokenizer + Default,
+    V: FromStr,
+    V::Err: std::error::Error + Send + Sync + 'static,
+{
+    /// Creates a builder out of a CSV rule file. See [`add_csv`](Self::add_csv).
+    pub fn from_csv<R: Read>(reader: R) -> Result<Self> {
+        let mut builder = Self::default();
+        builder.add_csv(reader)?;
+        Ok(builder)
+    }
+}
+
+impl<B, V, T> ImmutableTrieBuilder<B, V, T>
+where
+    B: ImmutableTrieNodeBuilder<V>,
+    T: Tokenizer,
+    V: FromStr,
+    V::Err: std::error::Error + Send + Sync + 'static,
+{
+    /// Adds every rule in a CSV file. Each row is `key,value[,weight][,tokenizer]`, where the
+    /// optional tokenizer is the [name](TokenizerKind::name) of a built-in tokenizer to split
+    /// that row's key with instead of this builder's tokenizer. Weights are ignored; use
+    /// [`add_weighted_csv`](ImmutableTrieBuilder::add_weighted_csv) to keep them.
+    ///
+    /// Search strings are always split with the trie's own tokenizer, so a row with its own
+    /// tokenizer is only found by searches if the trie's tokenizer splits the same text into the
+    /// same tokens. For example, `/api/users,3,,path` is found in a trie built with a
+    /// [`PathTokenizer`](crate::tokenization::PathTokenizer), but not in one that splits on
+    /// whitespace, where it can only be looked up with its tokens.
+    ///
+    /// The first row is a header, lines that start with `#` are comments, and whitespace around
+    /// each field is ignored. If a row can't be added, the error says which line it's on.
+    pub fn add_csv<R: Read>(&mut self, reader: R) -> Result<()> {
+        let mut tokenizers = HashMap::new();
+        read_rules(reader, |rule: CsvRule<V>| {
+            self.add_with_kind(&rule.key, rule.value, rule.tokenizer, &mut tokenizers)
+        })
+    }
+}
+
+impl<B, V, T> ImmutableTrieBuilder<B, Weighted<V>, T>
+where
+    B: ImmutableTrieNodeBuilder<Weighted<V>> + Default,
+    T: Tokenizer + Default,
+    V: FromStr,
+    V::Err: std::error::Error + Send + Sync + 'static,
+{
+    /// Creates a builder out of a CSV rule file with weights. See
+    /// [`add_weighted_csv`](Self::add_weighted_csv).
+    pub fn from_weighted_csv<R: Read>(reader: R) -> Result<Self> {
+        let mut builder = Self::default();
+        builder.add_weighted_csv(reader)?;
+        Ok(builder)
+    }
+}
+
+impl<B, V, T> ImmutableTrieBuilder<B, Weighted<V>, T>
+where
+    B: ImmutableTrieNodeBuilder<Weighted<V>>,
+    T: Tokenizer,
+    V: FromStr,
+    V::Err: std::error::Error + Send + Sync + 'static,
+{
+    /// Like [`add_csv`](ImmutableTrieBuilder::add_csv), but each value is
+    /// [weighted](Weighted) by its row's weight. Rows without a weight get a weight of `0`.
+    pub fn add_weighted_csv<R: Read>(&mut self, reader: R) -> Result<()> {
+        let mut tokenizers = HashMap::new();
+        read_rules(reader, |rule: CsvRule<V>| {
+            let value = Weighted::new(rule.value, rule.weight.unwrap_or_default());
+            self.add_with_kind(&rule.key, value, rule.tokenizer, &mut tokenizers)
+        })
+    }
+}
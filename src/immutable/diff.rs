@@ -0,0 +1,64 @@
+use super::{ImmutableTrie, ImmutableTrieNode};
+use crate::tokenization::Tokenizer;
+use std::{collections::HashMap, hash::Hash};
+
+/// How the matches for a single document differ between two tries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentDiff<'a, V> {
+    /// The index of the document in the corpus.
+    pub index: usize,
+    /// Values that only the new trie matched (or matched more times).
+    pub added: Vec<&'a V>,
+    /// Values that only the old trie matched (or matched more times).
+    pub removed: Vec<&'a V>,
+}
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    V: Eq + Hash,
+    T: Tokenizer,
+{
+    /// Runs every document in the corpus through both tries and reports the documents whose
+    /// matches differ. Each trie tokenizes the documents with its own tokenizer, so this can also
+    /// be used to check how changing the tokenizer changes what's matched.
+    pub fn replay_diff<'a, N2, T2, I, S>(
+        old: &'a Self,
+        new: &'a ImmutableTrie<N2, V, T2>,
+        corpus: I,
+    ) -> Vec<DocumentDiff<'a, V>>
+    where
+        N2: ImmutableTrieNode<V>,
+        T2: Tokenizer,
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut diffs = Vec::new();
+        for (index, document) in corpus.into_iter().enumerate() {
+            let mut counts: HashMap<&V, isize> = HashMap::new();
+            for value in old.find_all(document.as_ref()) {
+                *counts.entry(value).or_default() -= 1;
+            }
+            for value in new.find_all(document.as_ref()) {
+                *counts.entry(value).or_default() += 1;
+            }
+            let mut diff = DocumentDiff {
+                index,
+                added: Vec::new(),
+                removed: Vec::new(),
+            };
+            for (value, count) in counts {
+                let changes = if count > 0 {
+                    &mut diff.added
+                } else {
+                    &mut diff.removed
+                };
+                changes.extend(std::iter::repeat_n(value, count.unsigned_abs()));
+            }
+            if !diff.added.is_empty() || !diff.removed.is_empty() {
+                diffs.push(diff);
+            }
+        }
+        diffs
+    }
+}
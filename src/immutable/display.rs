@@ -0,0 +1,64 @@
+use super::{ImmutableTrie, ImmutableTrieNode};
+use crate::tokenization::Tokenizer;
+use std::{fmt, marker::PhantomData};
+
+const INDENT: &str = "  ";
+
+/// Displays a trie as an indented tree, with one key per line and the children of each key
+/// indented beneath it. Nodes with a [tail](ImmutableTrieNode::tail) are shown with all of its
+/// tokens on the same line. Created by [`ImmutableTrie::display_tree`].
+pub struct TreeDisplay<'a, N, V> {
+    root: &'a N,
+    _spooky: PhantomData<V>,
+}
+
+impl<N, V> TreeDisplay<'_, N, V>
+where
+    N: ImmutableTrieNode<V>,
+    V: fmt::Debug,
+{
+    fn fmt_node(node: &N, depth: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut children = node.children();
+        children.sort_by_key(|(key, _)| *key);
+        for (key, child) in children {
+            write!(f, "{}{key}", INDENT.repeat(depth))?;
+            for token in child.tail() {
+                write!(f, " {}", token.as_ref())?;
+            }
+            if let Some(value) = child.value() {
+                write!(f, " => {value:?}")?;
+            }
+            writeln!(f)?;
+            Self::fmt_node(child, depth + 1, f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<N, V> fmt::Display for TreeDisplay<'_, N, V>
+where
+    N: ImmutableTrieNode<V>,
+    V: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(value) = self.root.value() {
+            writeln!(f, "=> {value:?}")?;
+        }
+        Self::fmt_node(self.root, 0, f)
+    }
+}
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    V: fmt::Debug,
+    T: Tokenizer,
+{
+    #[inline]
+    pub fn display_tree(&self) -> TreeDisplay<'_, N, V> {
+        TreeDisplay {
+            root: &self.root,
+            _spooky: PhantomData,
+        }
+    }
+}
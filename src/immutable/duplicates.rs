@@ -0,0 +1,62 @@
+use crate::{Error, error::DuplicateKeySnafu};
+use std::fmt;
+
+/// What an [`ImmutableTrieBuilder`](super::ImmutableTrieBuilder) does when a key is added more
+/// than once.
+#[derive(Default)]
+pub enum DuplicatePolicy<V> {
+    /// Fail with [`Error::DuplicateKey`](crate::Error::DuplicateKey).
+    Error,
+    /// Replace the existing value with the new one.
+    #[default]
+    Overwrite,
+    /// Keep the existing value and drop the new one.
+    KeepFirst,
+    /// Combine the existing value with the new one.
+    Merge(fn(V, V) -> V),
+}
+
+impl<V> DuplicatePolicy<V> {
+    /// Decides what to store for a key that already has a value. If the duplicate is rejected,
+    /// the existing value is handed back along with the error, so that it isn't lost.
+    pub(crate) fn resolve<S: AsRef<str>>(
+        &self,
+        tokens: &[S],
+        existing: V,
+        new: V,
+    ) -> Result<V, (V, Error)> {
+        match self {
+            Self::Error => {
+                let key = tokens
+                    .iter()
+                    .map(AsRef::as_ref)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                Err((existing, DuplicateKeySnafu { key }.build()))
+            }
+            Self::Overwrite => Ok(new),
+            Self::KeepFirst => Ok(existing),
+            Self::Merge(merge) => Ok(merge(existing, new)),
+        }
+    }
+}
+
+impl<V> Clone for DuplicatePolicy<V> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V> Copy for DuplicatePolicy<V> {}
+
+impl<V> fmt::Debug for DuplicatePolicy<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => f.write_str("Error"),
+            Self::Overwrite => f.write_str("Overwrite"),
+            Self::KeepFirst => f.write_str("KeepFirst"),
+            Self::Merge(_) => f.write_str("Merge(..)"),
+        }
+    }
+}
@@ -0,0 +1,433 @@
+use super::{
+    ImmutableTrie,
+    nodes::{ChildKey, ImmutableTrieNode, StringTrieNode, TokenInterner, gap_of},
+};
+use crate::{
+    Result,
+    error::{FlatTrieTooLargeSnafu, GapNotSupportedSnafu, MaxGapNotSupportedSnafu},
+    tokenization::{Tokenizer, TokenizerExt},
+};
+use snafu::{OptionExt, ensure};
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    mem,
+    ops::Range,
+};
+
+/// Marks a [`FlatNode`] that doesn't have a value.
+const NO_VALUE: u32 = u32::MAX;
+
+#[derive(Clone, Copy, Debug)]
+struct FlatNode {
+    /// The tokens of the edge leading to this node, which can be more than one since chains of
+    /// nodes that only have one child and no value are collapsed into a single node. The root's
+    /// label is empty.
+    label_start: u32,
+    label_end: u32,
+    /// The indexes of this node's children, which are stored next to each other and sorted by
+    /// the first token of their labels.
+    children_start: u32,
+    children_end: u32,
+    /// The index of this node's value, or [`NO_VALUE`].
+    value: u32,
+}
+
+/// A read-only [`StringTrie`](super::StringTrie) that's laid out in a few flat arrays instead of
+/// a tree of separately allocated nodes. Every node's children are stored next to each other (in
+/// breadth-first order) and are found by binary searching their keys, so lookups touch far fewer
+/// cache lines on deep tries. Chains of nodes that only have a single child are collapsed into
+/// one node, so long keys with many tokens don't need a node for each one. Created with
+/// [`ImmutableTrie::flatten`] or [`ImmutableTrie::flatten_minimized`].
+///
+/// Gap tokens and [max gaps](ImmutableTrie::with_max_gap) aren't supported.
+#[derive(Clone, Debug)]
+pub struct FlatTrie<V, T> {
+    tokenizer: T,
+    version: u32,
+    /// The root is always the first node.
+    nodes: Box<[FlatNode]>,
+    /// The tokens of every node's label.
+    labels: Box<[ChildKey]>,
+    values: Box<[V]>,
+    /// This can be more than the number of values if the trie was minimized.
+    num_keys: usize,
+}
+
+impl<V, T> ImmutableTrie<StringTrieNode<V>, V, T>
+where
+    V: Clone,
+    T: Tokenizer + Clone,
+{
+    /// Copies this trie into a [`FlatTrie`]. Fails if any key has a [gap](super::nodes::parse_gap)
+    /// or [parameter](super::nodes::param_name) in it, if the trie has a
+    /// [max gap](ImmutableTrie::with_max_gap), or if the trie has more nodes, tokens or values
+    /// than can be indexed with a `u32`.
+    pub fn flatten(&self) -> Result<FlatTrie<V, T>> {
+        ensure!(
+            self.max_gap == 0,
+            MaxGapNotSupportedSnafu {
+                max_gap: self.max_gap
+            }
+        );
+        let mut interner = TokenInterner::default();
+        let mut nodes = Vec::new();
+        let mut labels = Vec::new();
+        let mut values = Vec::new();
+        // The label of each queued node, and the node its label ends at.
+        let mut queue = VecDeque::from([(Vec::new(), &self.root)]);
+        let mut num_queued = 1;
+        while let Some((label, node)) = queue.pop_front() {
+            let label_start = index(labels.len())?;
+            labels.extend(label.into_iter().map(|token| interner.intern(token)));
+            let value = match node.value() {
+                Some(value) => {
+                    values.push(value.clone());
+                    index(values.len() - 1)?
+                }
+                None => NO_VALUE,
+            };
+            // Each node's children are queued right after the children of the nodes before it,
+            // so they end up next to each other.
+            let children_start = index(num_queued)?;
+            for child in compressed_children(node, self.has_params)? {
+                queue.push_back(child);
+                num_queued += 1;
+            }
+            nodes.push(FlatNode {
+                label_start,
+                label_end: index(labels.len())?,
+                children_start,
+                children_end: index(num_queued)?,
+                value,
+            });
+        }
+        Ok(FlatTrie {
+            tokenizer: self.tokenizer.clone(),
+            version: self.version,
+            nodes: nodes.into_boxed_slice(),
+            labels: labels.into_boxed_slice(),
+            num_keys: values.len(),
+            values: values.into_boxed_slice(),
+        })
+    }
+
+    /// Like [`flatten`](Self::flatten), but identical subtries are only stored once, turning the
+    /// trie into a DAWG. Two subtries are identical if they have the same keys with equal
+    /// values, so dictionaries with many keys that end the same way (such as the different
+    /// forms of a word) shrink the most. Equal values are also only stored once.
+    pub fn flatten_minimized(&self) -> Result<FlatTrie<V, T>>
+    where
+        V: Eq + Hash,
+    {
+        ensure!(
+            self.max_gap == 0,
+            MaxGapNotSupportedSnafu {
+                max_gap: self.max_gap
+            }
+        );
+        let mut minimizer = Minimizer {
+            interner: TokenInterner::default(),
+            // The root is filled in once its children have been added.
+            nodes: vec![FlatNode {
+                label_start: 0,
+                label_end: 0,
+                children_start: 0,
+                children_end: 0,
+                value: NO_VALUE,
+            }],
+            labels: Vec::new(),
+            values: Vec::new(),
+            value_indexes: HashMap::new(),
+            groups: HashMap::new(),
+            parameters: self.has_params,
+        };
+        let ((children_start, children_end), mut num_keys) = minimizer.add_children(&self.root)?;
+        let value = match self.root.value() {
+            Some(value) => {
+                num_keys += 1;
+                minimizer.value_index(value)?
+            }
+            None => NO_VALUE,
+        };
+        minimizer.nodes[0] = FlatNode {
+            label_start: 0,
+            label_end: 0,
+            children_start,
+            children_end,
+            value,
+        };
+        Ok(FlatTrie {
+            tokenizer: self.tokenizer.clone(),
+            version: self.version,
+            nodes: minimizer.nodes.into_boxed_slice(),
+            labels: minimizer.labels.into_boxed_slice(),
+            values: minimizer.values.into_boxed_slice(),
+            num_keys,
+        })
+    }
+}
+
+/// Gets the children of a node, each with the tokens of its label. Chains of nodes that have a
+/// single child and no value were already merged into the first node's
+/// [tail](ImmutableTrieNode::tail) when the trie was built, so the label is the child's key
+/// followed by its tail. `parameters` is whether the trie's
+/// [parameters](super::nodes::param_name) are gaps.
+fn compressed_children<V>(
+    node: &StringTrieNode<V>,
+    parameters: bool,
+) -> Result<Vec<(Vec<&str>, &StringTrieNode<V>)>> {
+    let mut children = node.children();
+    children.sort_unstable_by_key(|(key, _)| *key);
+    children
+        .into_iter()
+        .map(|(key, child)| {
+            let mut label = vec![key];
+            label.extend(child.tail().iter().map(|token| token.as_ref()));
+            match label
+                .iter()
+                .find(|token| gap_of(token, parameters).is_some())
+            {
+                Some(token) => GapNotSupportedSnafu { token: *token }.fail(),
+                None => Ok((label, child)),
+            }
+        })
+        .collect()
+}
+
+/// A child's label, value index and children, which is everything that makes two subtries
+/// identical.
+type MinimizedChild<'a> = (Vec<&'a str>, u32, (u32, u32));
+
+/// Builds the arrays of a minimized [`FlatTrie`]. See [`ImmutableTrie::flatten_minimized`].
+struct Minimizer<'a, V> {
+    interner: TokenInterner,
+    nodes: Vec<FlatNode>,
+    labels: Vec<ChildKey>,
+    values: Vec<V>,
+    value_indexes: HashMap<&'a V, u32>,
+    /// Every group of children that's been added, along with where it was added and how many
+    /// keys are stored under it.
+    groups: HashMap<Vec<MinimizedChild<'a>>, ((u32, u32), usize)>,
+    /// Whether the trie's [parameters](super::nodes::param_name) are gaps.
+    parameters: bool,
+}
+
+impl<'a, V> Minimizer<'a, V>
+where
+    V: Clone + Eq + Hash,
+{
+    fn value_index(&mut self, value: &'a V) -> Result<u32> {
+        if let Some(idx) = self.value_indexes.get(value) {
+            return Ok(*idx);
+        }
+        let idx = index(self.values.len())?;
+        self.values.push(value.clone());
+        self.value_indexes.insert(value, idx);
+        Ok(idx)
+    }
+
+    /// Adds the children of `node` (and all of their descendants), unless an identical group of
+    /// children was already added. Returns the range the children are stored at, along with how
+    /// many keys are stored under them.
+    fn add_children(&mut self, node: &'a StringTrieNode<V>) -> Result<((u32, u32), usize)> {
+        let mut children = Vec::new();
+        let mut num_keys = 0;
+        for (label, child) in compressed_children(node, self.parameters)? {
+            let (grandchildren, child_keys) = self.add_children(child)?;
+            let value = match child.value() {
+                Some(value) => {
+                    num_keys += 1;
+                    self.value_index(value)?
+                }
+                None => NO_VALUE,
+            };
+            num_keys += child_keys;
+            children.push((label, value, grandchildren));
+        }
+        if let Some(group) = self.groups.get(&children) {
+            return Ok(*group);
+        }
+        let start = index(self.nodes.len())?;
+        for (label, value, (children_start, children_end)) in &children {
+            let label_start = index(self.labels.len())?;
+            self.labels
+                .extend(label.iter().map(|token| self.interner.intern(token)));
+            self.nodes.push(FlatNode {
+                label_start,
+                label_end: index(self.labels.len())?,
+                children_start: *children_start,
+                children_end: *children_end,
+                value: *value,
+            });
+        }
+        let group = ((start, index(self.nodes.len())?), num_keys);
+        self.groups.insert(children, group);
+        Ok(group)
+    }
+}
+
+/// Converts a position in one of a [`FlatTrie`]'s arrays into the `u32` it's stored as. Fails
+/// if the trie is too large for that, since [`NO_VALUE`] is reserved.
+#[inline]
+fn index(idx: usize) -> Result<u32> {
+    u32::try_from(idx)
+        .ok()
+        .filter(|idx| *idx != NO_VALUE)
+        .context(FlatTrieTooLargeSnafu { max: NO_VALUE - 1 })
+}
+
+impl<V, T> FlatTrie<V, T> {
+    #[inline]
+    fn value(&self, node: usize) -> Option<&V> {
+        let value = self.nodes[node].value;
+        (value != NO_VALUE).then(|| &self.values[value as usize])
+    }
+
+    #[inline]
+    fn label(&self, node: usize) -> &[ChildKey] {
+        &self.labels[self.nodes[node].label_start as usize..self.nodes[node].label_end as usize]
+    }
+
+    #[inline]
+    fn children(&self, node: usize) -> Range<usize> {
+        self.nodes[node].children_start as usize..self.nodes[node].children_end as usize
+    }
+
+    /// Finds the child whose label starts with the first token, and checks how much of the rest
+    /// of the label the other tokens match. Returns the child and how many tokens matched.
+    fn step<S: AsRef<str>>(&self, node: usize, tokens: &[S]) -> Option<(usize, usize)> {
+        let first = tokens.first()?.as_ref();
+        let children = self.children(node);
+        let child = self.nodes[children.clone()]
+            .binary_search_by(|child| str::cmp(&self.labels[child.label_start as usize], first))
+            .ok()
+            .map(|idx| children.start + idx)?;
+        let matched = self
+            .label(child)
+            .iter()
+            .zip(tokens)
+            .take_while(|(key, token)| ***key == *token.as_ref())
+            .count();
+        Some((child, matched))
+    }
+
+    /// Follows the tokens from the root for as long as they fully match the labels, calling
+    /// `visit` with each node that's reached. Returns how many tokens were consumed.
+    fn walk<S: AsRef<str>>(&self, tokens: &[S], mut visit: impl FnMut(usize)) -> usize {
+        let (mut node, mut pos) = (0, 0);
+        while let Some((child, matched)) = self.step(node, &tokens[pos..]) {
+            if matched < self.label(child).len() {
+                break;
+            }
+            node = child;
+            pos += matched;
+            visit(node);
+        }
+        pos
+    }
+
+    #[inline]
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// The number of keys stored in the trie.
+    #[inline]
+    pub fn num_keys(&self) -> usize {
+        self.num_keys
+    }
+
+    /// Estimates the number of bytes used by this trie, not counting anything the values point
+    /// to. Tokens shared by more than one label are counted every time.
+    pub fn approx_memory_bytes(&self) -> usize {
+        mem::size_of::<Self>()
+            + self.nodes.len() * mem::size_of::<FlatNode>()
+            + self
+                .labels
+                .iter()
+                .map(|token| mem::size_of::<ChildKey>() + token.len())
+                .sum::<usize>()
+            + self.values.len() * mem::size_of::<V>()
+    }
+
+    /// Like [`ImmutableTrie::get_exact_tokens`].
+    pub fn get_exact_tokens<S: AsRef<str>>(&self, tokens: &[S]) -> Option<&V> {
+        let mut node = 0;
+        let consumed = self.walk(tokens, |reached| node = reached);
+        (consumed == tokens.len())
+            .then(|| self.value(node))
+            .flatten()
+    }
+
+    /// Like [`ImmutableTrie::find_any_tokens`].
+    pub fn find_any_tokens<S: AsRef<str>>(&self, tokens: &[S]) -> Option<&V> {
+        (0..tokens.len()).find_map(|start| {
+            let mut found = self.value(0);
+            self.walk(&tokens[start..], |node| {
+                found = found.or_else(|| self.value(node));
+            });
+            found
+        })
+    }
+
+    /// Like [`ImmutableTrie::find_all_tokens`].
+    pub fn find_all_tokens<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<&V> {
+        let mut found = Vec::new();
+        for start in 0..tokens.len() {
+            self.walk(&tokens[start..], |node| found.extend(self.value(node)));
+        }
+        found
+    }
+
+    /// Gets the values of all keys that start with the given tokens.
+    pub fn find_prefixed_tokens<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<&V> {
+        let mut node = 0;
+        let consumed = self.walk(tokens, |reached| node = reached);
+        if consumed < tokens.len() {
+            // The prefix can also end partway through a label.
+            match self.step(node, &tokens[consumed..]) {
+                Some((child, matched)) if consumed + matched == tokens.len() => node = child,
+                _ => return Vec::new(),
+            }
+        }
+        let mut found = Vec::new();
+        let mut to_visit = vec![node];
+        while let Some(node) = to_visit.pop() {
+            found.extend(self.value(node));
+            to_visit.extend(self.children(node).rev());
+        }
+        found
+    }
+}
+
+impl<V, T: Tokenizer> FlatTrie<V, T> {
+    /// Like [`ImmutableTrie::get_exact`].
+    #[inline]
+    pub fn get_exact<S: AsRef<str>>(&self, key: S) -> Option<&V> {
+        self.get_exact_tokens(&self.tokenizer.tokenize_ref(key.as_ref()))
+    }
+
+    #[inline]
+    pub fn contains_key<S: AsRef<str>>(&self, key: S) -> bool {
+        self.get_exact(key).is_some()
+    }
+
+    /// Like [`ImmutableTrie::find_any`].
+    #[inline]
+    pub fn find_any<S: AsRef<str>>(&self, search_str: S) -> Option<&V> {
+        self.find_any_tokens(&self.tokenizer.tokenize_ref(search_str.as_ref()))
+    }
+
+    /// Like [`ImmutableTrie::find_all`].
+    #[inline]
+    pub fn find_all<S: AsRef<str>>(&self, search_str: S) -> Vec<&V> {
+        self.find_all_tokens(&self.tokenizer.tokenize_ref(search_str.as_ref()))
+    }
+
+    /// Like [`ImmutableTrie::find_prefixed`].
+    #[inline]
+    pub fn find_prefixed<S: AsRef<str>>(&self, prefix: S) -> Vec<&V> {
+        self.find_prefixed_tokens(&self.tokenizer.tokenize_ref(prefix.as_ref()))
+    }
+}
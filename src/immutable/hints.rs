@@ -0,0 +1,69 @@
+use super::nodes::gap_of;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Default)]
+struct FirstTokenHint {
+    /// If any token can follow this one, either because it's a key on its own or because it's
+    /// followed by a gap.
+    any_second: bool,
+    second_tokens: HashSet<String>,
+}
+
+/// Records which tokens can follow the first token of each key, so that searches can skip start
+/// positions that can't possibly match without walking the trie. Enabled with
+/// [`ImmutableTrieBuilder::with_pruning_hints`](super::ImmutableTrieBuilder::with_pruning_hints).
+///
+/// Hints compare tokens literally, so they must not be used with tries whose keys are patterns.
+#[derive(Debug, Clone, Default)]
+pub struct PruningHints {
+    first_tokens: HashMap<String, FirstTokenHint>,
+    /// If the empty key has a value or a key starts with a gap, in which case every position can
+    /// match.
+    always_matches: bool,
+}
+
+impl PruningHints {
+    #[inline]
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        self.first_tokens.reserve(additional);
+    }
+
+    /// Records a key's first two tokens. `parameters` is whether the key's
+    /// [parameters](super::nodes::param_name) can match any token.
+    pub(crate) fn record<S: AsRef<str>>(&mut self, tokens: &[S], parameters: bool) {
+        let Some(first) = tokens
+            .first()
+            .filter(|first| gap_of(first.as_ref(), parameters).is_none())
+        else {
+            self.always_matches = true;
+            return;
+        };
+        let hint = self
+            .first_tokens
+            .entry(String::from(first.as_ref()))
+            .or_default();
+        match tokens.get(1) {
+            Some(second) if gap_of(second.as_ref(), parameters).is_none() => {
+                hint.second_tokens.insert(String::from(second.as_ref()));
+            }
+            _ => hint.any_second = true,
+        }
+    }
+
+    /// Checks if any key could match at the start of the tokens.
+    pub fn may_match<S: AsRef<str>>(&self, tokens: &[S]) -> bool {
+        if self.always_matches {
+            return true;
+        }
+        let Some(hint) = tokens
+            .first()
+            .and_then(|first| self.first_tokens.get(first.as_ref()))
+        else {
+            return false;
+        };
+        hint.any_second
+            || tokens
+                .get(1)
+                .is_some_and(|second| hint.second_tokens.contains(second.as_ref()))
+    }
+}
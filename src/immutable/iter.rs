@@ -0,0 +1,139 @@
+use super::{ImmutableTrie, ImmutableTrieNode, PruningHints, nodes::strip_tail};
+use crate::tokenization::{Tokenizer, TokenizerExt};
+use std::{borrow::Cow, marker::PhantomData, ops::ControlFlow};
+
+/// A lazy iterator over the values found in a search string, in the same order that
+/// [`ImmutableTrie::find_all`] returns them. Created by [`ImmutableTrie::find_iter`].
+pub struct FindIter<'a, 's, N, V> {
+    root: &'a N,
+    hints: Option<&'a PruningHints>,
+    /// The tokens of the search string, borrowed from it wherever the tokenizer allows.
+    tokens: Vec<Cow<'s, str>>,
+    /// The token the current matches start at.
+    start: usize,
+    next_start: usize,
+    /// Nodes that still need to be visited for the current start position, along with how many
+    /// tokens were consumed to reach them. The same stack is reused for every start position.
+    stack: Vec<(&'a N, usize)>,
+    max_gap: usize,
+    /// With a [max gap](ImmutableTrie::with_max_gap), the values found at the current start
+    /// position are all found at once, and are kept here in reverse order until they're
+    /// returned.
+    pending: Vec<&'a V>,
+    _spooky: PhantomData<V>,
+}
+
+impl<'a, N, V> FindIter<'a, '_, N, V>
+where
+    N: ImmutableTrieNode<V>,
+{
+    fn push_children(&mut self, node: &'a N, depth: usize) {
+        // Gaps are visited after the other children, so they're pushed first.
+        let remaining = self.tokens.len() - self.start - depth;
+        for (skips, child) in node.gaps().rev() {
+            let (first, last) = skips.into_inner();
+            self.stack.extend(
+                (first..=last.min(remaining))
+                    .rev()
+                    .map(|skip| (child, depth + skip)),
+            );
+        }
+        let pos = self.start + depth;
+        if let Some(token) = self.tokens.get(pos) {
+            let rest = &self.tokens[pos + 1..];
+            // The children are pushed in order and then flipped in place, so that they're
+            // popped in order without collecting them first.
+            let pushed = self.stack.len();
+            self.stack
+                .extend(node.get_children(token).into_iter().filter_map(|child| {
+                    let consumed = rest.len() - strip_tail(child, rest)?.len();
+                    Some((child, depth + 1 + consumed))
+                }));
+            self.stack[pushed..].reverse();
+        }
+    }
+}
+
+impl<'a, N, V> Iterator for FindIter<'a, '_, N, V>
+where
+    N: ImmutableTrieNode<V> + 'a,
+    V: 'a,
+{
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(value) = self.pending.pop() {
+                return Some(value);
+            }
+            while let Some((node, depth)) = self.stack.pop() {
+                self.push_children(node, depth);
+                if let Some(value) = node.value() {
+                    return Some(value);
+                }
+            }
+            if self.next_start >= self.tokens.len() {
+                return None;
+            }
+            self.start = self.next_start;
+            self.next_start += 1;
+            let tokens = &self.tokens[self.start..];
+            if self.hints.is_some_and(|hints| !hints.may_match(tokens)) {
+                continue;
+            }
+            if self.max_gap == 0 {
+                self.push_children(self.root, 0);
+            } else {
+                let found = self.root.get_all_with_max_gap(tokens, self.max_gap);
+                self.pending
+                    .extend(found.into_iter().rev().map(|(_, value)| value));
+            }
+        }
+    }
+}
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    /// Like [`find_all`](Self::find_all), but the values are found lazily as the iterator is
+    /// advanced instead of all being collected up front. The tokens borrow from `search_str`
+    /// wherever the tokenizer allows, so it has to outlive the iterator. With a
+    /// [max gap](Self::with_max_gap), the values that start at the same token are found together.
+    pub fn find_iter<'s>(&self, search_str: &'s str) -> FindIter<'_, 's, N, V> {
+        FindIter {
+            root: &self.root,
+            hints: self.hints.as_ref(),
+            tokens: self.tokenizer.tokenize_ref(search_str),
+            start: 0,
+            next_start: 0,
+            stack: Vec::new(),
+            max_gap: self.max_gap,
+            pending: Vec::new(),
+            _spooky: PhantomData,
+        }
+    }
+
+    /// Like [`find_all`](Self::find_all), but stops searching once `n` values have been found.
+    /// Since the search is done lazily, at most `n` values are ever held, no matter how many
+    /// overlapping matches the search string has.
+    #[inline]
+    pub fn find_first_n<S: AsRef<str>>(&self, search_str: S, n: usize) -> Vec<&V> {
+        self.find_iter(search_str.as_ref()).take(n).collect()
+    }
+
+    /// Calls `f` with each value found in the search string until it returns
+    /// [`ControlFlow::Break`], at which point the search stops and the break value is returned.
+    pub fn find_until<S, B, F>(&self, search_str: S, mut f: F) -> Option<B>
+    where
+        S: AsRef<str>,
+        F: FnMut(&V) -> ControlFlow<B>,
+    {
+        self.find_iter(search_str.as_ref())
+            .find_map(|value| match f(value) {
+                ControlFlow::Break(b) => Some(b),
+                ControlFlow::Continue(()) => None,
+            })
+    }
+}
@@ -0,0 +1,123 @@
+use super::{ImmutableTrie, ImmutableTrieBuilder, ImmutableTrieNode, ImmutableTrieNodeBuilder};
+use crate::{Error, Result, tokenization::Tokenizer};
+use serde::{
+    Deserializer, Serialize,
+    de::{self, DeserializeOwned, DeserializeSeed, MapAccess, Visitor},
+};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{Read, Write},
+};
+
+impl<B, V, T> ImmutableTrieBuilder<B, V, T>
+where
+    B: ImmutableTrieNodeBuilder<V> + Default,
+    T: Tokenizer + Default,
+    V: DeserializeOwned,
+{
+    /// Creates a builder out of a JSON object. See [`add_json`](Self::add_json).
+    pub fn from_json<R: Read>(reader: R) -> Result<Self> {
+        let mut builder = Self::default();
+        builder.add_json(reader)?;
+        Ok(builder)
+    }
+}
+
+impl<B, V, T> ImmutableTrieBuilder<B, V, T>
+where
+    B: ImmutableTrieNodeBuilder<V>,
+    T: Tokenizer,
+    V: DeserializeOwned,
+{
+    /// Adds every entry of a JSON object, such as `{"key phrase": value}`. Each key is tokenized
+    /// like it would be by [`add`](Self::add). The entries are added in the order they appear in
+    /// the object, so keys that show up more than once (or that tokenize the same) are handled by
+    /// the [`DuplicatePolicy`](super::DuplicatePolicy) just like they would be if they were added
+    /// one by one.
+    pub fn add_json<R: Read>(&mut self, reader: R) -> Result<()> {
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        let mut entries = JsonEntries {
+            builder: self,
+            error: None,
+        };
+        let parsed = (&mut entries).deserialize(&mut deserializer);
+        if let Some(error) = entries.error {
+            return Err(error);
+        }
+        parsed?;
+        deserializer.end()?;
+        Ok(())
+    }
+}
+
+/// Adds the entries of a JSON object to a builder as they're parsed, instead of collecting them
+/// first. Errors from adding an entry are kept in `error`, since they can't be returned through
+/// serde.
+struct JsonEntries<'b, B, V, T>
+where
+    B: ImmutableTrieNodeBuilder<V>,
+    T: Tokenizer,
+{
+    builder: &'b mut ImmutableTrieBuilder<B, V, T>,
+    error: Option<Error>,
+}
+
+impl<'de, B, V, T> DeserializeSeed<'de> for &mut JsonEntries<'_, B, V, T>
+where
+    B: ImmutableTrieNodeBuilder<V>,
+    T: Tokenizer,
+    V: DeserializeOwned,
+{
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, B, V, T> Visitor<'de> for &mut JsonEntries<'_, B, V, T>
+where
+    B: ImmutableTrieNodeBuilder<V>,
+    T: Tokenizer,
+    V: DeserializeOwned,
+{
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an object of keys and values")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        if let Some(len) = map.size_hint() {
+            self.builder.reserve(len);
+        }
+        while let Some((key, value)) = map.next_entry::<String, V>()? {
+            if let Err(error) = self.builder.add(key, value) {
+                self.error = Some(error);
+                return Err(de::Error::custom("failed to add a key"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+    V: Serialize,
+{
+    /// Writes every entry of this trie as a JSON object, with the tokens of each key joined by
+    /// spaces. The object can be read back with
+    /// [`ImmutableTrieBuilder::from_json`] as long as the builder's tokenizer splits on spaces.
+    pub fn to_json<W: Write>(&self, writer: W) -> Result<()> {
+        let entries: BTreeMap<String, &V> = self
+            .entries()
+            .into_iter()
+            .map(|(key, value)| (key.join(" "), value))
+            .collect();
+        serde_json::to_writer_pretty(writer, &entries)?;
+        Ok(())
+    }
+}
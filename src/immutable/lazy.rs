@@ -0,0 +1,113 @@
+use super::{ImmutableTrie, ImmutableTrieNode};
+use crate::tokenization::{Tokenizer, TokenizerExt};
+use std::borrow::Cow;
+
+/// Tokens that are pulled from a tokenizer only as they're needed, and then kept around so
+/// searches starting at later tokens can reuse them.
+pub(crate) struct LazyTokens<'s, I> {
+    tokens: Vec<Cow<'s, str>>,
+    iter: I,
+}
+
+impl<'s, I> LazyTokens<'s, I>
+where
+    I: Iterator<Item = Cow<'s, str>>,
+{
+    #[inline]
+    pub(crate) fn new(iter: I) -> Self {
+        Self {
+            tokens: Vec::new(),
+            iter,
+        }
+    }
+
+    /// Gets the token at `i`, tokenizing up to it if needed. Returns `None` if there aren't that
+    /// many tokens.
+    pub(crate) fn get(&mut self, i: usize) -> Option<&str> {
+        while self.tokens.len() <= i {
+            self.tokens.push(self.iter.next()?);
+        }
+        Some(&self.tokens[i])
+    }
+
+    /// Checks if there are more than `i` tokens.
+    #[inline]
+    pub(crate) fn has(&mut self, i: usize) -> bool {
+        self.get(i).is_some()
+    }
+
+    /// Gets the tokens from `start` up to (but not including) `end`, or fewer if there aren't
+    /// that many.
+    pub(crate) fn window(&mut self, start: usize, end: usize) -> &[Cow<'s, str>] {
+        if end > start {
+            self.get(end - 1);
+        }
+        let end = end.min(self.tokens.len());
+        &self.tokens[start.min(end)..end]
+    }
+}
+
+/// Like [`ImmutableTrieNode::get_any`], but the tokens starting at `pos` are tokenized lazily,
+/// so only as many tokens as the trie can use are ever tokenized.
+fn get_any_lazy<'a, 's, N, V, I>(
+    node: &'a N,
+    tokens: &mut LazyTokens<'s, I>,
+    pos: usize,
+) -> Option<&'a V>
+where
+    N: ImmutableTrieNode<V>,
+    I: Iterator<Item = Cow<'s, str>>,
+{
+    if let Some(value) = node.value() {
+        return Some(value);
+    }
+    let found = match tokens.get(pos) {
+        Some(token) => node.get_child(token).and_then(|child| {
+            let tail = child.tail();
+            tail.iter()
+                .enumerate()
+                .all(|(i, key)| tokens.get(pos + 1 + i) == Some(key.as_ref()))
+                .then(|| get_any_lazy(child, tokens, pos + 1 + tail.len()))?
+        }),
+        None => None,
+    };
+    if found.is_some() {
+        return found;
+    }
+    for (skips, child) in node.gaps() {
+        for skip in skips {
+            if skip > 0 && !tokens.has(pos + skip - 1) {
+                break;
+            }
+            if let Some(value) = get_any_lazy(child, tokens, pos + skip) {
+                return Some(value);
+            }
+        }
+    }
+    None
+}
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    /// Finds a value using lazily tokenized input, stopping at the first match found. Matches
+    /// starting at earlier tokens are still found first. The input is only as lazy as the
+    /// tokenizer's [`tokenize_iter`](TokenizerExt::tokenize_iter), which tokenizes everything up
+    /// front unless the tokenizer overrides [`tokenize_lazily`](Tokenizer::tokenize_lazily).
+    pub(crate) fn find_any_lazy(&self, search_str: &str) -> Option<&V> {
+        let mut tokens = LazyTokens::new(self.tokenizer.tokenize_iter(search_str));
+        let mut i = 0;
+        while tokens.has(i) {
+            // The hints only ever look at the first two tokens.
+            if self.may_match(tokens.window(i, i + 2))
+                && let Some(value) = get_any_lazy(&self.root, &mut tokens, i)
+            {
+                return Some(value);
+            }
+            i += 1;
+        }
+        None
+    }
+}
@@ -0,0 +1,90 @@
+use super::{ImmutableTrie, ImmutableTrieNode};
+use crate::{
+    Error, Result,
+    hashing::StableHasher,
+    tokenization::{Tokenizer, TokenizerExt},
+};
+use std::hash::{Hash, Hasher as _};
+
+/// Describes the contents of a trie so that a deployed trie can be checked against the
+/// dictionary it was built from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Manifest {
+    /// The number of values stored in the trie.
+    pub entry_count: usize,
+    /// A hash of all of the trie's keys, in sorted order.
+    pub keys_hash: u64,
+    /// A hash of the tokenizer's configuration.
+    pub tokenizer_hash: u64,
+    /// The version of this crate that the trie was built with.
+    pub crate_version: String,
+}
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    pub fn manifest(&self) -> Manifest {
+        let mut tokenizer_hasher = StableHasher::default();
+        self.tokenizer.hash_config(&mut tokenizer_hasher);
+        Manifest {
+            entry_count: self.num_keys(),
+            keys_hash: hash_entries(self.entries(), |_, _| ()),
+            tokenizer_hash: tokenizer_hasher.finish(),
+            crate_version: String::from(env!("CARGO_PKG_VERSION")),
+        }
+    }
+
+    /// Checks that this trie has the same contents and tokenizer as the given manifest. The crate
+    /// version is not checked.
+    pub fn verify_manifest(&self, manifest: &Manifest) -> Result<()> {
+        let actual = self.manifest();
+        if actual.entry_count != manifest.entry_count {
+            Err(Error::ManifestMismatch {
+                field: "entry_count",
+            })
+        } else if actual.keys_hash != manifest.keys_hash {
+            Err(Error::ManifestMismatch { field: "keys_hash" })
+        } else if actual.tokenizer_hash != manifest.tokenizer_hash {
+            Err(Error::ManifestMismatch {
+                field: "tokenizer_hash",
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    V: Hash,
+    T: Tokenizer,
+{
+    /// Hashes every key along with its value. The keys are hashed in sorted order, so two tries
+    /// with the same [entries](Self::entries) always have the same hash, no matter how they were
+    /// built or what kind of nodes they're made of. The keys are hashed the same way on every
+    /// platform, but the values go through their own [`Hash`] implementations, which the
+    /// standard library doesn't promise to keep the same between Rust versions. Hashes that are
+    /// stored should be compared against ones made with the same toolchain.
+    pub fn content_hash(&self) -> u64 {
+        hash_entries(self.entries(), |value, hasher| value.hash(hasher))
+    }
+}
+
+/// Hashes each key, in sorted order, along with whatever `hash_value` adds for its value.
+fn hash_entries<V>(
+    mut entries: Vec<(Vec<&str>, &V)>,
+    mut hash_value: impl FnMut(&V, &mut StableHasher),
+) -> u64 {
+    entries.sort_unstable_by(|(left, _), (right, _)| left.cmp(right));
+    let mut hasher = StableHasher::default();
+    for (key, value) in entries {
+        hasher.write_usize(key.len());
+        key.into_iter().for_each(|part| hasher.write_str(part));
+        hash_value(value, &mut hasher);
+    }
+    hasher.finish()
+}
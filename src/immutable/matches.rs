@@ -0,0 +1,248 @@
+use super::{ImmutableTrie, ImmutableTrieNode, param_name};
+use crate::tokenization::SpannedTokenizer;
+use std::ops::Range;
+
+/// A value found in a search string, along with where it was found.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Match<'a, V> {
+    pub value: &'a V,
+    /// The range of tokens that matched.
+    pub tokens: Range<usize>,
+    /// The byte span of the matched tokens in the search string.
+    pub span: Range<usize>,
+    /// The name of each [parameter](super::nodes::param_name) in the matched key, along with the
+    /// text it matched.
+    pub params: Vec<(&'a str, String)>,
+}
+
+impl<'a, V> Match<'a, V> {
+    /// Creates a match of the tokens in `range`, filling in its parameters from `keys`, which are
+    /// the keys that led to the value (one for each token), or nothing if the trie doesn't have
+    /// any parameters.
+    fn new(
+        value: &'a V,
+        range: Range<usize>,
+        keys: &[&'a str],
+        search_str: &str,
+        spans: &[Range<usize>],
+    ) -> Self {
+        let params = keys
+            .iter()
+            .zip(range.clone())
+            .filter_map(|(key, idx)| {
+                param_name(key).map(|name| (name, String::from(&search_str[spans[idx].clone()])))
+            })
+            .collect();
+        Self {
+            value,
+            span: spans[range.start].start..spans[range.end - 1].end,
+            tokens: range,
+            params,
+        }
+    }
+
+    /// Gets the matched text out of the string that was searched.
+    #[inline]
+    pub fn as_str<'s>(&self, search_str: &'s str) -> &'s str {
+        &search_str[self.span.clone()]
+    }
+
+    #[inline]
+    pub fn params(&self) -> &[(&'a str, String)] {
+        &self.params
+    }
+
+    /// Gets the text matched by the parameter with the given name, without its leading `:`.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| *param == name)
+            .map(|(_, text)| text.as_str())
+    }
+}
+
+/// A piece of an annotated search string. See [`ImmutableTrie::annotate`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Segment<'s, 'a, V> {
+    /// Text that isn't part of any match.
+    Unmatched(&'s str),
+    Matched {
+        text: &'s str,
+        found: Match<'a, V>,
+    },
+}
+
+impl<'s, 'a, V> Segment<'s, 'a, V> {
+    #[inline]
+    pub fn text(&self) -> &'s str {
+        match self {
+            Self::Unmatched(text) | Self::Matched { text, .. } => text,
+        }
+    }
+
+    #[inline]
+    pub fn value(&self) -> Option<&'a V> {
+        match self {
+            Self::Unmatched(_) => None,
+            Self::Matched { found, .. } => Some(found.value),
+        }
+    }
+}
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: SpannedTokenizer,
+{
+    /// Like [`find_all`](Self::find_all), but also returns where each value was found.
+    pub fn find_matches<S: AsRef<str>>(&self, search_str: S) -> Vec<Match<'_, V>> {
+        let (tokens, spans): (Vec<_>, Vec<_>) = self
+            .tokenizer
+            .tokenize_spanned(search_str.as_ref())
+            .into_iter()
+            .unzip();
+        let mut found = Vec::new();
+        for i in 0..tokens.len() {
+            found.extend(self.keyed_matches_at(&tokens[i..]).into_iter().map(
+                |(depth, keys, value)| {
+                    Match::new(value, i..i + depth, &keys, search_str.as_ref(), &spans)
+                },
+            ));
+        }
+        found
+    }
+
+    /// Like [`find_non_overlapping`](Self::find_non_overlapping), but also returns where each
+    /// value was found.
+    pub fn find_matches_non_overlapping<S: AsRef<str>>(&self, search_str: S) -> Vec<Match<'_, V>> {
+        let (tokens, spans): (Vec<_>, Vec<_>) = self
+            .tokenizer
+            .tokenize_spanned(search_str.as_ref())
+            .into_iter()
+            .unzip();
+        self.non_overlapping_by(&tokens, |tokens| {
+            self.keyed_matches_at(tokens)
+                .into_iter()
+                .reduce(|longest, next| if next.0 > longest.0 { next } else { longest })
+                .map(|(depth, keys, value)| (depth, (keys, value)))
+        })
+        .into_iter()
+        .map(|(range, (keys, value))| Match::new(value, range, &keys, search_str.as_ref(), &spans))
+        .collect()
+    }
+
+    /// Rebuilds the search string with each of the
+    /// [non-overlapping matches](Self::find_matches_non_overlapping) replaced by the result of
+    /// `replacer`. Everything between the matches is kept as-is.
+    pub fn replace_all<S, F>(&self, search_str: S, mut replacer: F) -> String
+    where
+        S: AsRef<str>,
+        F: FnMut(&Match<'_, V>) -> String,
+    {
+        let search_str = search_str.as_ref();
+        let mut replaced = String::with_capacity(search_str.len());
+        let mut last_end = 0;
+        for found in self.find_matches_non_overlapping(search_str) {
+            replaced.push_str(&search_str[last_end..found.span.start]);
+            replaced.push_str(&replacer(&found));
+            last_end = found.span.end;
+        }
+        replaced.push_str(&search_str[last_end..]);
+        replaced
+    }
+
+    /// Replaces every character of every match (including overlapping ones) with `mask_char`.
+    /// Everything else is kept as-is, so the result has as many characters as the search string.
+    pub fn redact<S: AsRef<str>>(&self, search_str: S, mask_char: char) -> String {
+        let search_str = search_str.as_ref();
+        let mut masked = vec![false; search_str.len()];
+        for found in self.find_matches(search_str) {
+            masked[found.span].fill(true);
+        }
+        search_str
+            .char_indices()
+            .map(|(idx, c)| if masked[idx] { mask_char } else { c })
+            .collect()
+    }
+
+    /// Splits the search string into segments that are either one of the
+    /// [non-overlapping matches](Self::find_matches_non_overlapping) or the text between them.
+    /// Concatenating the text of every segment gives back the search string. Empty unmatched
+    /// segments are left out.
+    pub fn annotate<'s>(&self, search_str: &'s str) -> Vec<Segment<'s, '_, V>> {
+        let mut segments = Vec::new();
+        let mut last_end = 0;
+        for found in self.find_matches_non_overlapping(search_str) {
+            if last_end < found.span.start {
+                segments.push(Segment::Unmatched(&search_str[last_end..found.span.start]));
+            }
+            last_end = found.span.end;
+            segments.push(Segment::Matched {
+                text: found.as_str(search_str),
+                found,
+            });
+        }
+        if last_end < search_str.len() {
+            segments.push(Segment::Unmatched(&search_str[last_end..]));
+        }
+        segments
+    }
+
+    /// Gets the text between the [non-overlapping matches](Self::find_matches_non_overlapping),
+    /// like [`Regex::split`](https://docs.rs/regex/latest/regex/struct.Regex.html#method.split).
+    /// As with `Regex::split`, the text before the first match and after the last one is always
+    /// included, even if it's empty.
+    pub fn split_on_matches<'s>(&self, search_str: &'s str) -> Vec<&'s str> {
+        let mut chunks = Vec::new();
+        let mut last_end = 0;
+        for found in self.find_matches_non_overlapping(search_str) {
+            chunks.push(&search_str[last_end..found.span.start]);
+            last_end = found.span.end;
+        }
+        chunks.push(&search_str[last_end..]);
+        chunks
+    }
+
+    /// Adds up the score that `scorer` gives each match (including overlapping ones). The
+    /// matches are scored as they're found, so they're never all held at once.
+    pub fn score<S, F>(&self, search_str: S, mut scorer: F) -> f64
+    where
+        S: AsRef<str>,
+        F: FnMut(&Match<'_, V>) -> f64,
+    {
+        let (tokens, spans): (Vec<_>, Vec<_>) = self
+            .tokenizer
+            .tokenize_spanned(search_str.as_ref())
+            .into_iter()
+            .unzip();
+        let mut score = 0.0;
+        for i in 0..tokens.len() {
+            for (depth, keys, value) in self.keyed_matches_at(&tokens[i..]) {
+                let range = i..i + depth;
+                score += scorer(&Match::new(
+                    value,
+                    range,
+                    &keys,
+                    search_str.as_ref(),
+                    &spans,
+                ));
+            }
+        }
+        score
+    }
+
+    /// Like [`matches_at`](Self::matches_at), but each value also comes with the keys that led to
+    /// it, one for each matched token, so that its parameters can be filled in without walking
+    /// the trie again. The keys are only collected for tries that have parameters, and are left
+    /// empty otherwise.
+    fn keyed_matches_at<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<(usize, Vec<&str>, &V)> {
+        if self.has_params {
+            self.matches_with_keys_at(tokens)
+        } else {
+            self.matches_at(tokens)
+                .into_iter()
+                .map(|(depth, value)| (depth, Vec::new(), value))
+                .collect()
+        }
+    }
+}
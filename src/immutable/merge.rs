@@ -0,0 +1,84 @@
+use super::{
+    ImmutableTrie, ImmutableTrieNode, ImmutableTrieNodeBuilder, PruningHints,
+    nodes::{collect_entries, param_name},
+};
+use crate::{Result, tokenization::Tokenizer};
+use std::collections::HashMap;
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer + Clone,
+{
+    /// Creates a new trie out of the keys that are stored in both this trie and `other`. The
+    /// value of each key is computed by `f` from the values in both tries. Keys are compared
+    /// exactly as they were added, so regex patterns are carried over as-is. The new trie keeps this
+    /// trie's version and max gap, its parameters are enabled if either trie's were, and it has
+    /// pruning hints if this trie does.
+    pub fn map_merge<B, N2, V2, T2, W, F>(
+        &self,
+        other: &ImmutableTrie<N2, V2, T2>,
+        mut builder: B,
+        mut f: F,
+    ) -> Result<ImmutableTrie<B::Node, W, T>>
+    where
+        B: ImmutableTrieNodeBuilder<W>,
+        N2: ImmutableTrieNode<V2>,
+        T2: Tokenizer,
+        F: FnMut(&V, &V2) -> W,
+    {
+        let parameters = self.has_params || other.has_params;
+        if parameters {
+            builder.enable_parameters();
+        }
+        let mut hints = self.hints.as_ref().map(|_| PruningHints::default());
+        let has_params = merge_nodes(
+            &self.root,
+            &other.root,
+            &mut builder,
+            parameters,
+            &mut hints,
+            &mut f,
+        )?;
+        let mut trie = ImmutableTrie::new(self.tokenizer.clone(), builder.build()?)
+            .with_version(self.version)
+            .with_max_gap(self.max_gap);
+        trie.hints = hints;
+        trie.has_params = has_params;
+        Ok(trie)
+    }
+}
+
+fn merge_nodes<'a, N1, V1, N2, V2, B, W, F>(
+    left: &'a N1,
+    right: &'a N2,
+    builder: &mut B,
+    parameters: bool,
+    hints: &mut Option<PruningHints>,
+    f: &mut F,
+) -> Result<bool>
+where
+    N1: ImmutableTrieNode<V1>,
+    N2: ImmutableTrieNode<V2>,
+    B: ImmutableTrieNodeBuilder<W>,
+    F: FnMut(&V1, &V2) -> W,
+{
+    // The keys are compared rather than the nodes, since either side may have merged chains of
+    // nodes into their tails.
+    let mut left_entries = Vec::new();
+    collect_entries(left, &mut Vec::new(), &mut left_entries);
+    let left_entries: HashMap<Vec<&str>, &V1> = left_entries.into_iter().collect();
+    let mut right_entries = Vec::new();
+    collect_entries(right, &mut Vec::new(), &mut right_entries);
+    let mut has_params = false;
+    for (key, v2) in right_entries {
+        if let Some(v1) = left_entries.get(&key) {
+            if let Some(hints) = hints.as_mut() {
+                hints.record(&key, parameters);
+            }
+            has_params |= parameters && key.iter().any(|token| param_name(token).is_some());
+            builder.add(key.iter(), f(v1, v2))?;
+        }
+    }
+    Ok(has_params)
+}
@@ -1,18 +1,83 @@
 use crate::{
     Error, Result,
-    tokenization::{BoundaryTokenizer, Tokenizer, WhitespaceTokenizer},
+    error::{EmptyKeySnafu, InvalidPatternSnafu, MisplacedGapSnafu},
+    tokenization::{
+        BoundaryTokenizer, CharTokenizer, CompoundSplitter, Token, Tokenizer, TokenizerExt,
+        WhitespaceTokenizer, decode_utf16_lossy,
+    },
 };
 use educe::Educe;
+use snafu::ResultExt;
 use std::{
     collections::{BTreeSet, HashMap, HashSet},
     fmt,
     hash::Hash,
     marker::PhantomData,
-    ops::Deref,
+    mem,
+    ops::{ControlFlow, Deref, Range},
 };
 
+mod acronym;
+#[cfg(feature = "aho-corasick")]
+mod aho;
+mod ambiguity;
+mod annotated;
+#[cfg(feature = "rayon")]
+mod batch;
+#[cfg(feature = "regex")]
+mod captures;
+mod context;
+mod correction;
+mod counting;
+#[cfg(feature = "csv")]
+mod csv;
+mod diff;
+mod display;
+mod duplicates;
+mod flat;
+mod hints;
+mod iter;
+#[cfg(feature = "json")]
+mod json;
+mod lazy;
+mod manifest;
+mod matches;
+mod merge;
 pub mod nodes;
+mod options;
+mod persistent;
+mod prepared;
+mod query;
+mod reader;
+mod shared;
+mod stream;
+mod weighted;
+mod wordlist;
+pub use acronym::*;
+#[cfg(feature = "aho-corasick")]
+pub use aho::*;
+pub use ambiguity::*;
+pub use annotated::*;
+#[cfg(feature = "regex")]
+pub use captures::*;
+pub use context::*;
+pub use correction::*;
+pub use diff::*;
+pub use display::*;
+pub use duplicates::*;
+pub use flat::*;
+pub use hints::*;
+pub use iter::*;
+pub use manifest::*;
+pub use matches::*;
 use nodes::*;
+pub use options::*;
+pub use prepared::*;
+pub use query::*;
+pub use reader::*;
+pub use shared::*;
+pub use stream::*;
+pub use weighted::*;
 
 pub struct ImmutableTrieBuilder<B, V, T = WhitespaceTokenizer>
 where
@@ -21,6 +86,12 @@ where
 {
     tokenizer: T,
     builder: B,
+    hints: Option<PruningHints>,
+    /// If tokens like `:id` are [parameters](nodes::param_name) rather than literal tokens.
+    parameters: bool,
+    /// If any key has a [parameter](nodes::param_name).
+    has_params: bool,
+    duplicate_policy: DuplicatePolicy<V>,
     _spooky: PhantomData<V>,
 }
 
@@ -66,13 +137,84 @@ where
         Self {
             tokenizer,
             builder,
+            hints: None,
+            parameters: false,
+            has_params: false,
+            duplicate_policy: DuplicatePolicy::Overwrite,
             _spooky: PhantomData,
         }
     }
 
-    pub fn add<S: AsRef<str>>(&mut self, key: S, value: V) -> Result<()> {
+    /// Sets what happens when [`add`](Self::add) or [`add_tokens`](Self::add_tokens) is called
+    /// with a key that already has a value. By default, the old value is overwritten.
+    #[inline]
+    pub fn with_duplicate_policy(mut self, duplicate_policy: DuplicatePolicy<V>) -> Self {
+        self.duplicate_policy = duplicate_policy;
+        self
+    }
+
+    /// Records [`PruningHints`] for every key that's added, which the built trie will use to skip
+    /// start positions that can't match. This should only be used with literal keys.
+    #[inline]
+    pub fn with_pruning_hints(mut self) -> Self {
+        self.hints = Some(Default::default());
+        self
+    }
+
+    /// Makes tokens like `:id` [parameters](nodes::param_name), which match any one token and
+    /// capture it. Without this, they're matched literally, so that keys like `hello :)` work.
+    /// Only nodes that support gaps support parameters.
+    #[inline]
+    pub fn with_parameters(mut self) -> Self {
+        self.parameters = true;
+        self.builder.enable_parameters();
+        self
+    }
+
+    /// Tokenizes a key, failing if it has no tokens even though it isn't empty, which usually
+    /// means it's made up of only delimiters or stop words. The empty key itself can be added.
+    fn tokenize_key(&self, key: &str) -> Result<Vec<String>> {
         let tokens = self.tokenizer.tokenize(key);
-        self.builder.add(tokens.into_iter(), value)
+        if tokens.is_empty() && !key.is_empty() {
+            return EmptyKeySnafu { key }.fail();
+        }
+        Ok(tokens)
+    }
+
+    pub fn add<S: AsRef<str>>(&mut self, key: S, value: V) -> Result<()> {
+        let tokens = self.tokenize_key(key.as_ref())?;
+        self.add_tokens(tokens, value)
+            .map_err(|err| err.with_key(key.as_ref()))
+    }
+
+    /// Reserves room for at least `additional` more keys. See
+    /// [`ImmutableTrieNodeBuilder::reserve`].
+    pub fn reserve(&mut self, additional: usize) {
+        self.builder.reserve(additional);
+        if let Some(hints) = self.hints.as_mut() {
+            hints.reserve(additional);
+        }
+    }
+
+    /// Adds every key, carrying on past the ones that can't be added. If any of them fail, all
+    /// of their errors are returned along with each key's position in `entries`. A key that's
+    /// rejected as a duplicate keeps the value it already had.
+    pub fn add_all<S, I>(&mut self, entries: I) -> Result<(), Vec<(usize, Error)>>
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = (S, V)>,
+    {
+        let entries = entries.into_iter();
+        self.reserve(entries.size_hint().0);
+        let errors: Vec<_> = entries
+            .enumerate()
+            .filter_map(|(idx, (key, value))| self.add(key, value).err().map(|err| (idx, err)))
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
     }
 
     pub fn add_tokens<S, I>(&mut self, tokens: I, value: V) -> Result<()>
@@ -80,17 +222,107 @@ where
         S: AsRef<str>,
         I: IntoIterator<Item = S>,
     {
-        self.builder.add(tokens.into_iter(), value)
+        let tokens: Vec<S> = tokens.into_iter().collect();
+        let policy = self.duplicate_policy;
+        self.add_tokens_with(&tokens, value, |existing, new| {
+            policy.resolve(&tokens, existing, new)
+        })
+    }
+
+    /// Adds a key, using `merge` to combine the value with the one the key already has, if it
+    /// has one. This ignores the builder's [`DuplicatePolicy`].
+    pub fn add_or_merge<S, F>(&mut self, key: S, value: V, merge: F) -> Result<()>
+    where
+        S: AsRef<str>,
+        F: FnOnce(V, V) -> V,
+    {
+        let tokens = self.tokenize_key(key.as_ref())?;
+        self.add_tokens_with(tokens, value, |existing, new| Ok(merge(existing, new)))
+            .map_err(|err| err.with_key(key.as_ref()))
+    }
+
+    /// See [`ImmutableTrieNodeBuilder::add_with`].
+    pub(crate) fn add_tokens_with<S, I, F>(
+        &mut self,
+        tokens: I,
+        value: V,
+        on_duplicate: F,
+    ) -> Result<()>
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = S>,
+        F: FnOnce(V, V) -> Result<V, (V, Error)>,
+    {
+        let tokens: Vec<S> = tokens.into_iter().collect();
+        let joined_key = || {
+            tokens
+                .iter()
+                .map(AsRef::as_ref)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        check_gaps(&tokens)?;
+        for (index, token) in tokens.iter().enumerate() {
+            self.builder
+                .validate_token(token.as_ref())
+                .with_context(|_| InvalidPatternSnafu {
+                    key: joined_key(),
+                    index,
+                    pattern: token.as_ref(),
+                })?;
+        }
+        if let Some(hints) = self.hints.as_mut() {
+            hints.record(&tokens, self.parameters);
+        }
+        self.has_params |= self.parameters
+            && tokens
+                .iter()
+                .any(|token| param_name(token.as_ref()).is_some());
+        self.builder
+            .add_with(tokens.into_iter(), value, on_duplicate)
     }
 
     pub fn build<TT: Tokenizer>(self, trie_tokenizer: TT) -> Result<ImmutableTrie<B::Node, V, TT>> {
         let root = self.builder.build()?;
-        Ok(ImmutableTrie::new(trie_tokenizer, root))
+        let mut trie = ImmutableTrie::new(trie_tokenizer, root);
+        trie.hints = self.hints;
+        trie.has_params = self.has_params;
+        Ok(trie)
     }
 
+    #[inline]
     pub fn build_default<TT: Tokenizer + Default>(self) -> Result<ImmutableTrie<B::Node, V, TT>> {
-        let root = self.builder.build()?;
-        Ok(ImmutableTrie::new(Default::default(), root))
+        self.build(Default::default())
+    }
+}
+
+/// Fails if a key starts or ends with a gap. Parameters are the only gaps that can, so they
+/// aren't checked.
+fn check_gaps<S: AsRef<str>>(tokens: &[S]) -> Result<()> {
+    let is_gap = |token: Option<&S>| token.is_some_and(|token| parse_gap(token.as_ref()).is_some());
+    if is_gap(tokens.first()) || is_gap(tokens.last()) {
+        let key = tokens
+            .iter()
+            .map(AsRef::as_ref)
+            .collect::<Vec<_>>()
+            .join(" ");
+        return MisplacedGapSnafu { key }.fail();
+    }
+    Ok(())
+}
+
+impl<B, V, T> ImmutableTrieBuilder<B, Vec<V>, T>
+where
+    B: ImmutableTrieNodeBuilder<Vec<V>>,
+    T: Tokenizer,
+{
+    /// Adds another value to a key, so that keys can have more than one value.
+    #[inline]
+    pub fn add_value<S: AsRef<str>>(&mut self, key: S, value: V) -> Result<()> {
+        self.add_or_merge(key, vec![value], |mut values, new| {
+            values.extend(new);
+            values
+        })
     }
 }
 
@@ -104,6 +336,13 @@ where
     #[educe(Debug(ignore))]
     tokenizer: T,
     root: N,
+    version: u32,
+    /// How many tokens can be skipped between each of a key's tokens.
+    max_gap: usize,
+    #[educe(Debug(ignore))]
+    hints: Option<PruningHints>,
+    /// If any key has a [parameter](nodes::param_name).
+    has_params: bool,
     #[educe(Debug(ignore))]
     _spooky: PhantomData<V>,
 }
@@ -117,26 +356,319 @@ where
         Self {
             tokenizer,
             root,
+            version: 0,
+            max_gap: 0,
+            hints: None,
+            has_params: false,
             _spooky: PhantomData,
         }
     }
 
+    /// Sets the metadata version of this trie. This is an arbitrary, user-defined number that is
+    /// reported alongside query results so that they can be traced back to the dictionary that
+    /// produced them.
+    #[inline]
+    pub fn with_version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    #[inline]
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Allows up to `max_gap` unrelated tokens between each of a key's tokens when searching, so
+    /// that with a `max_gap` of 1, `mister bobby` also matches `mister the bobby`. By default this
+    /// is `0`, meaning a key's tokens have to be next to each other. Unlike
+    /// [gap tokens](nodes::parse_gap), this applies to every key.
+    ///
+    /// [Pruning hints](ImmutableTrieBuilder::with_pruning_hints) only look at the first two tokens
+    /// of each key, so they shouldn't be used along with this.
+    #[inline]
+    pub fn with_max_gap(mut self, max_gap: usize) -> Self {
+        self.max_gap = max_gap;
+        self
+    }
+
+    #[inline]
+    pub fn max_gap(&self) -> usize {
+        self.max_gap
+    }
+
+    /// Gets every value whose key matches at the start of the tokens, along with how many tokens
+    /// the match covers, taking the [max gap](Self::with_max_gap) into account.
+    pub(crate) fn matches_at<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<(usize, &V)> {
+        if self.max_gap == 0 {
+            self.root.get_all_with_depth(tokens)
+        } else {
+            self.root.get_all_with_max_gap(tokens, self.max_gap)
+        }
+    }
+
+    /// Like [`matches_at`](Self::matches_at), but each value also comes with the keys (or
+    /// patterns) that led to it, one for each matched token.
+    pub(crate) fn matches_with_keys_at<S: AsRef<str>>(
+        &self,
+        tokens: &[S],
+    ) -> Vec<(usize, Vec<&str>, &V)> {
+        if self.max_gap == 0 {
+            self.root
+                .get_all_with_keys(tokens)
+                .into_iter()
+                .map(|(keys, value)| (keys.len(), keys, value))
+                .collect()
+        } else {
+            self.root
+                .get_all_with_keys_and_max_gap(tokens, self.max_gap)
+        }
+    }
+
+    /// Gets the value of the longest match at the start of the tokens.
+    fn longest_at<S: AsRef<str>>(&self, tokens: &[S]) -> Option<(usize, &V)> {
+        if self.max_gap == 0 {
+            self.root.get_longest(tokens)
+        } else {
+            self.matches_at(tokens)
+                .into_iter()
+                .reduce(|longest, next| if next.0 > longest.0 { next } else { longest })
+        }
+    }
+
+    /// Checks the trie's [`PruningHints`] (if it has any) to see if a key could match at the
+    /// start of the tokens.
+    #[inline]
+    fn may_match<S: AsRef<str>>(&self, tokens: &[S]) -> bool {
+        self.hints
+            .as_ref()
+            .is_none_or(|hints| hints.may_match(tokens))
+    }
+
+    /// Finds the value of a key that matches the search string. Unless the trie has a
+    /// [max gap](Self::with_max_gap), the search string is tokenized lazily, so finding a match
+    /// near the start of a long string doesn't tokenize the rest of it. This relies on the
+    /// tokenizer's [`tokenize_iter`](TokenizerExt::tokenize_iter), so tokenizers that don't
+    /// override [`tokenize_lazily`](Tokenizer::tokenize_lazily) still tokenize the whole string up
+    /// front. These include the
+    /// [`ShingleTokenizer`](crate::tokenization::ShingleTokenizer),
+    /// [`NgramTokenizer`](crate::tokenization::NgramTokenizer) and boxed
+    /// [`DynTokenizer`](crate::tokenization::DynTokenizer)s.
     pub fn find_any<S: AsRef<str>>(&self, search_str: S) -> Option<&V> {
-        let tokens = self.tokenizer.tokenize(search_str.as_ref());
-        for i in 0..tokens.len() {
-            if let Some(value) = self.root.get_any(&tokens[i..]) {
-                return Some(value);
-            }
+        if self.max_gap == 0 {
+            return self.find_any_lazy(search_str.as_ref());
         }
-        None
+        let tokens = self.tokenizer.tokenize_ref(search_str.as_ref());
+        self.find_any_tokens(&tokens)
+    }
+
+    /// Like [`find_any`](Self::find_any), but with already tokenized input.
+    pub fn find_any_tokens<S: AsRef<str>>(&self, tokens: &[S]) -> Option<&V> {
+        (0..tokens.len())
+            .filter(|i| self.may_match(&tokens[*i..]))
+            .find_map(|i| {
+                if self.max_gap == 0 {
+                    self.root.get_any(&tokens[i..])
+                } else {
+                    self.matches_at(&tokens[i..])
+                        .into_iter()
+                        .next()
+                        .map(|(_, value)| value)
+                }
+            })
     }
 
     pub fn find_all<S: AsRef<str>>(&self, search_str: S) -> Vec<&V> {
-        let tokens = self.tokenizer.tokenize(search_str.as_ref());
+        let tokens = self.tokenizer.tokenize_ref(search_str.as_ref());
         log::trace!("find_all tokens: {tokens:?}");
+        self.find_all_tokens(&tokens)
+    }
+
+    /// Like [`find_all`](Self::find_all), but the search string is tokenized with `tokenizer`
+    /// instead of the trie's own tokenizer. The keys were tokenized when the trie was built, so
+    /// this only makes sense with tokenizers that produce compatible tokens, such as strict
+    /// whitespace tokenization for logs and boundary-based tokenization for prose.
+    pub fn find_all_with_tokenizer<S, TT>(&self, search_str: S, tokenizer: &TT) -> Vec<&V>
+    where
+        S: AsRef<str>,
+        TT: Tokenizer,
+    {
+        let tokens = tokenizer.tokenize_ref(search_str.as_ref());
+        self.find_all_tokens(&tokens)
+    }
+
+    /// Like [`find_all`](Self::find_all), but with already tokenized input, such as tokens from
+    /// an upstream NLP stage. The tokens are used as-is, so they need to be normalized the same
+    /// way as the trie's tokenizer would normalize them.
+    pub fn find_all_tokens<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<&V> {
+        let mut found = Vec::new();
+        for i in 0..tokens.len() {
+            if self.may_match(&tokens[i..]) {
+                found.extend(
+                    self.matches_at(&tokens[i..])
+                        .into_iter()
+                        .map(|(_, value)| value),
+                );
+            }
+        }
+        found
+    }
+
+    /// Like [`find_any`](Self::find_any), but for UTF-16 input. See [`decode_utf16_lossy`] for
+    /// how the input is decoded.
+    #[inline]
+    pub fn find_any_utf16(&self, search_str: &[u16]) -> Option<&V> {
+        self.find_any(decode_utf16_lossy(search_str))
+    }
+
+    /// Like [`find_all`](Self::find_all), but for UTF-16 input. See [`decode_utf16_lossy`] for
+    /// how the input is decoded.
+    #[inline]
+    pub fn find_all_utf16(&self, search_str: &[u16]) -> Vec<&V> {
+        self.find_all(decode_utf16_lossy(search_str))
+    }
+
+    /// Finds the value of the longest key that matches at each position in the search string.
+    pub fn find_longest<S: AsRef<str>>(&self, search_str: S) -> Vec<&V> {
+        let tokens = self.tokenizer.tokenize_ref(search_str.as_ref());
+        (0..tokens.len())
+            .filter_map(|i| self.longest_at(&tokens[i..]))
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    /// Finds the leftmost-longest matches, skipping over the tokens of each match before looking
+    /// for the next one. This way `mister bobby` will suppress `bobby` instead of both matching.
+    pub fn find_non_overlapping<S: AsRef<str>>(&self, search_str: S) -> Vec<&V> {
+        let tokens = self.tokenizer.tokenize_ref(search_str.as_ref());
+        self.non_overlapping(&tokens)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    /// Gets the token range and value of each leftmost-longest match.
+    pub(crate) fn non_overlapping<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<(Range<usize>, &V)> {
+        self.non_overlapping_by(tokens, |tokens| self.longest_at(tokens))
+    }
+
+    /// Like [`non_overlapping`](Self::non_overlapping), but `longest_at` finds the longest match
+    /// at the start of the tokens, so that it can return more than just the value.
+    fn non_overlapping_by<S, M, F>(&self, tokens: &[S], mut longest_at: F) -> Vec<(Range<usize>, M)>
+    where
+        S: AsRef<str>,
+        F: FnMut(&[S]) -> Option<(usize, M)>,
+    {
+        let mut found = Vec::new();
+        let mut i = 0;
+        while i < tokens.len() {
+            if let Some((depth, found_at)) = longest_at(&tokens[i..]) {
+                found.push((i..i + depth, found_at));
+                i += depth;
+            } else {
+                i += 1;
+            }
+        }
+        found
+    }
+
+    /// Gets the value stored under exactly the given key. All of the key's tokens must be
+    /// consumed, so neither shorter stored keys nor keys starting later in the string match.
+    pub fn get_exact<S: AsRef<str>>(&self, key: S) -> Option<&V> {
+        let tokens = self.tokenizer.tokenize_ref(key.as_ref());
+        self.root.get_exact(&tokens)
+    }
+
+    #[inline]
+    pub fn contains_key<S: AsRef<str>>(&self, key: S) -> bool {
+        self.get_exact(key).is_some()
+    }
+
+    /// Gets the values of all keys that start with the given prefix, such as for autocompletion.
+    pub fn find_prefixed<S: AsRef<str>>(&self, prefix: S) -> Vec<&V> {
+        let tokens = self.tokenizer.tokenize_ref(prefix.as_ref());
+        self.root.get_prefixed(&tokens)
+    }
+
+    /// Like [`get_exact`](Self::get_exact), but with already tokenized input.
+    #[inline]
+    pub fn get_exact_tokens<S: AsRef<str>>(&self, tokens: &[S]) -> Option<&V> {
+        self.root.get_exact(tokens)
+    }
+
+    /// The number of values stored in the trie.
+    #[inline]
+    pub fn num_keys(&self) -> usize {
+        self.root.num_values()
+    }
+
+    /// The number of nodes in the trie, including the root.
+    #[inline]
+    pub fn num_nodes(&self) -> usize {
+        1 + self.root.len_recursive()
+    }
+
+    /// Estimates how many bytes this trie uses, including all of its nodes, keys and compiled
+    /// patterns. This is only meant for capacity planning and is not exact.
+    #[inline]
+    pub fn approx_memory_bytes(&self) -> usize {
+        mem::size_of::<Self>() - mem::size_of::<N>() + self.root.approx_memory_bytes()
+    }
+
+    /// Gets all of the distinct tokens (or patterns) used by the keys in this trie.
+    pub fn vocabulary(&self) -> HashSet<&str> {
+        let mut vocabulary = HashSet::new();
+        let mut to_visit = vec![&self.root];
+        while let Some(node) = to_visit.pop() {
+            for (key, child) in node.children() {
+                vocabulary.insert(key);
+                vocabulary.extend(child.tail().iter().map(|token| token.as_ref()));
+                to_visit.push(child);
+            }
+        }
+        vocabulary
+    }
+
+    /// Gets every key stored in this trie as the tokens (or patterns) that make it up.
+    pub fn keys(&self) -> Vec<Vec<&str>> {
+        self.entries().into_iter().map(|(key, _)| key).collect()
+    }
+
+    /// Gets every key stored in this trie along with its value, like [`keys`](Self::keys).
+    pub fn entries(&self) -> Vec<(Vec<&str>, &V)> {
+        let mut entries = Vec::new();
+        collect_entries(&self.root, &mut Vec::new(), &mut entries);
+        entries
+    }
+
+    /// Makes this trie split compound tokens in search strings using its own
+    /// [vocabulary](Self::vocabulary). See [`CompoundSplitter`] for more details.
+    pub fn with_compound_splitting(self) -> ImmutableTrie<N, V, CompoundSplitter<T>> {
+        let vocabulary: Vec<String> = self.vocabulary().into_iter().map(String::from).collect();
+        ImmutableTrie {
+            tokenizer: CompoundSplitter::new(self.tokenizer, vocabulary),
+            root: self.root,
+            version: self.version,
+            max_gap: self.max_gap,
+            hints: self.hints,
+            has_params: self.has_params,
+            _spooky: PhantomData,
+        }
+    }
+
+    /// Like [`find_all`](Self::find_all), but each value is paired with the keys (or patterns)
+    /// that matched it. With a [max gap](Self::with_max_gap), the tokens that were skipped get
+    /// empty keys.
+    pub fn find_all_with_keys<S: AsRef<str>>(&self, search_str: S) -> Vec<(Vec<&str>, &V)> {
+        let tokens = self.tokenizer.tokenize_ref(search_str.as_ref());
         let mut found = Vec::new();
         for i in 0..tokens.len() {
-            found.extend(self.root.get_all(&tokens[i..]));
+            if self.may_match(&tokens[i..]) {
+                found.extend(
+                    self.matches_with_keys_at(&tokens[i..])
+                        .into_iter()
+                        .map(|(_, keys, value)| (keys, value)),
+                );
+            }
         }
         found
     }
@@ -180,6 +712,19 @@ where
     pub fn has_match<S: AsRef<str>>(&self, value: S) -> bool {
         *self.find_any(value).unwrap_or(&false)
     }
+
+    /// Like [`has_match`](Self::has_match), but with already tokenized input.
+    #[inline]
+    pub fn has_match_tokens<S: AsRef<str>>(&self, tokens: &[S]) -> bool {
+        *self.find_any_tokens(tokens).unwrap_or(&false)
+    }
+
+    /// Checks if the entire value is a key in this matcher. Unlike [`has_match`](Self::has_match),
+    /// the key can't just be somewhere within the value.
+    #[inline]
+    pub fn matches_entire<S: AsRef<str>>(&self, value: S) -> bool {
+        *self.get_exact(value).unwrap_or(&false)
+    }
 }
 
 impl<N, V, T> Default for ImmutableTrie<N, V, T>
@@ -191,17 +736,75 @@ where
         Self {
             tokenizer: Default::default(),
             root: Default::default(),
+            version: 0,
+            max_gap: 0,
+            hints: None,
+            has_params: false,
             _spooky: PhantomData,
         }
     }
 }
 
+/// Tries are equal if they have the same keys with the same values, even if they're made of
+/// different kinds of nodes. Their tokenizers and settings, such as their versions, aren't
+/// compared.
+impl<N, N2, V, T, T2> PartialEq<ImmutableTrie<N2, V, T2>> for ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    N2: ImmutableTrieNode<V>,
+    V: PartialEq,
+    T: Tokenizer,
+    T2: Tokenizer,
+{
+    #[inline]
+    fn eq(&self, other: &ImmutableTrie<N2, V, T2>) -> bool {
+        nodes::same_entries(&self.root, &other.root)
+    }
+}
+
+impl<N, V, T> Eq for ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    V: Eq,
+    T: Tokenizer,
+{
+}
+
 pub type StringTrie<V, T = BoundaryTokenizer> = ImmutableTrie<StringTrieNode<V>, V, T>;
 pub type StringTrieBuilder<V, T = WhitespaceTokenizer> =
-    ImmutableTrieBuilder<StringTrieNode<V>, V, T>;
+    ImmutableTrieBuilder<StringTrieNodeBuilder<V>, V, T>;
 pub type StringMatcher<T = BoundaryTokenizer> = StringTrie<bool, T>;
 pub type StringMatcherBuilder<T = WhitespaceTokenizer> =
-    ImmutableTrieBuilder<StringTrieNode<bool>, bool, T>;
+    ImmutableTrieBuilder<StringTrieNodeBuilder<bool>, bool, T>;
+
+/// A trie that counts how many times each key was added. See
+/// [`ImmutableTrieBuilder::increment`].
+pub type CountingTrie<T = BoundaryTokenizer> = StringTrie<u64, T>;
+pub type CountingTrieBuilder<T = WhitespaceTokenizer> = StringTrieBuilder<u64, T>;
+
+/// A classic trie with one character per edge, such as for prefix dictionaries and
+/// autocomplete. See [`CharTokenizer`].
+pub type CharTrie<V, T = CharTokenizer> = StringTrie<V, T>;
+pub type CharTrieBuilder<V, T = CharTokenizer> = StringTrieBuilder<V, T>;
+
+/// A trie whose children are always visited in sorted order. See [`OrderedTrieNode`].
+pub type OrderedTrie<V, T = BoundaryTokenizer> = ImmutableTrie<OrderedTrieNode<V>, V, T>;
+pub type OrderedTrieBuilder<V, T = WhitespaceTokenizer> =
+    ImmutableTrieBuilder<OrderedTrieNodeBuilder<V>, V, T>;
+
+/// A trie that new versions can be made from without copying it. See [`PersistentTrieNode`].
+pub type PersistentTrie<V, T = BoundaryTokenizer> = ImmutableTrie<PersistentTrieNode<V>, V, T>;
+pub type PersistentTrieBuilder<V, T = WhitespaceTokenizer> =
+    ImmutableTrieBuilder<PersistentTrieNodeBuilder<V>, V, T>;
+
+pub type AutoTrie<V, T = BoundaryTokenizer> = ImmutableTrie<AutoTrieNode<V>, V, T>;
+pub type AutoTrieBuilder<V, T = WhitespaceTokenizer> =
+    ImmutableTrieBuilder<AutoTrieNodeBuilder<V>, V, T>;
+
+pub type SmallKeyTrie<V, const N: usize = 4, T = BoundaryTokenizer> =
+    ImmutableTrie<SmallKeyTrieNode<V, N>, V, T>;
+pub type SmallKeyTrieBuilder<V, const N: usize = 4, T = WhitespaceTokenizer> =
+    ImmutableTrieBuilder<SmallKeyTrieNodeBuilder<V, N>, V, T>;
 
 #[cfg(feature = ("regex-filtered"))]
 pub type RegexTrie<V, T = BoundaryTokenizer> = ImmutableTrie<RegexFilteredTrieNode<V>, V, T>;
@@ -216,6 +819,8 @@ pub type RegexMatcherBuilder<T = WhitespaceTokenizer> = RegexTrieBuilder<bool, T
 //#[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tokenization::{CustomWhitespaceTokenizer, DynTokenizer, PathTokenizer};
+    use std::sync::Arc;
 
     #[test]
     fn test_string_matcher_has_match() {
@@ -234,62 +839,2071 @@ mod tests {
     }
 
     #[test]
-    fn test_regex_trie_conflict_1() {
-        let mut trie_builder: RegexTrieBuilder<&str> = Default::default();
+    fn test_string_trie_gaps() {
+        let mut trie_builder: StringTrieBuilder<&str> = Default::default();
+        trie_builder.add("mister **{0,2} bobby", "v1").unwrap();
+        trie_builder.add("mister bobby jr", "v2").unwrap();
+        assert!(matches!(
+            trie_builder.add("**{0,2} bobby", "v3"),
+            Err(Error::MisplacedGap { .. })
+        ));
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.find_all("mister bobby jr"), vec![&"v2", &"v1"]);
+        assert_eq!(trie.find_all("mister the bobby"), vec![&"v1"]);
+        assert_eq!(trie.find_all("mister the great bobby"), vec![&"v1"]);
+        assert!(trie.find_all("mister the great big bobby").is_empty());
+        assert_eq!(trie.find_any("hi mister a b bobby"), Some(&"v1"));
+        assert_eq!(
+            trie.find_iter("mister bobby jr").collect::<Vec<_>>(),
+            trie.find_all("mister bobby jr")
+        );
+        let mut matcher = trie.stream_matcher();
+        assert!(
+            matcher
+                .push_str("mister x", &WhitespaceTokenizer::new())
+                .is_empty()
+        );
+        assert_eq!(matcher.push_token("bobby"), vec![&"v1"]);
+    }
+
+    #[test]
+    fn test_stream_matcher_wide_gap() {
+        let mut trie_builder: StringTrieBuilder<&str> = Default::default();
         trie_builder
-            .add("(a|the|slumber|pool) party", "val 1")
+            .add("error **{0,1000} failed", "failure")
             .unwrap();
-        trie_builder.add("pool", "val 2").unwrap();
-        let trie: RegexTrie<&str> = trie_builder.build_default().unwrap();
-        let results = trie.find_unique("pool");
-        assert_eq!(results.len(), 1);
-        assert!(results.contains(&"val 2"));
-        let results = trie.find_unique("a pool");
-        assert_eq!(results.len(), 1);
-        assert!(results.contains(&"val 2"));
-        let results = trie.find_unique("a party");
-        assert_eq!(results.len(), 1);
-        assert!(results.contains(&"val 1"));
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let mut matcher = trie.stream_matcher();
+        assert!(matcher.push_token("error").is_empty());
+        for _ in 0..500 {
+            assert!(matcher.push_token("x").is_empty());
+            assert_eq!(matcher.num_active(), 1);
+        }
+        assert_eq!(matcher.push_token("failed"), vec![&"failure"]);
+        let mut matcher = trie.stream_matcher();
+        matcher.push_token("error");
+        for _ in 0..1001 {
+            matcher.push_token("x");
+        }
+        assert!(matcher.push_token("failed").is_empty());
     }
 
-    //FIXME
-    //#[test]
-    //fn test_regex_matcher_add_value() {
-    //    let mut builder = RegexMatcherBuilder::default();
-    //    builder.add("Test Name", true).unwrap();
-    //    let matcher: RegexMatcher<BoundaryTokenizer> = builder.build_default().unwrap();
-    //    assert_eq!(matcher.root.children.len(), 1);
-    //    let first_node = &matcher.root.children[0];
-    //    assert_eq!(first_node.key.as_str(), "^test$");
-    //    assert_eq!(first_node.children.len(), 1);
-    //    assert!(first_node.value.is_none());
-    //    let maybe_second_node = first_node.children.get("name");
-    //    assert!(maybe_second_node.is_some());
-    //    let second_node = maybe_second_node.unwrap();
-    //    assert_eq!(second_node.key.as_str(), "^name$");
-    //    assert_eq!(second_node.children.len(), 0);
-    //    assert!(matches!(second_node.value, Some(true)));
-    //}
+    #[test]
+    fn test_string_trie_max_gap() {
+        let mut trie_builder: StringTrieBuilder<&str> = Default::default();
+        trie_builder.add("mister bobby", "v1").unwrap();
+        trie_builder.add("mister bobby jr", "v2").unwrap();
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.max_gap(), 0);
+        assert!(trie.find_all("mister the bobby").is_empty());
+        let trie = trie.with_max_gap(2);
+        assert_eq!(trie.find_all("mister the bobby"), vec![&"v1"]);
+        assert_eq!(
+            trie.find_all("mister the the bobby the jr"),
+            vec![&"v1", &"v2"]
+        );
+        assert!(trie.find_all("mister the the the bobby").is_empty());
+        assert_eq!(trie.find_any("mister a bobby"), Some(&"v1"));
+        assert_eq!(trie.find_longest("mister a bobby jr"), vec![&"v2"]);
+        assert_eq!(trie.find_all("mister bobby bobby"), vec![&"v1"]);
+    }
 
     #[test]
-    fn test_string_trie_find() {
+    fn test_string_trie_max_gap_search_paths() {
+        let mut trie_builder: StringTrieBuilder<&str> = Default::default();
+        trie_builder.add("mister bobby", "v1").unwrap();
+        trie_builder.add("mister bobby jr", "v2").unwrap();
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let trie = trie.with_max_gap(1);
+        let search_str = "mister the bobby a jr";
+        assert_eq!(
+            trie.find_iter(search_str).collect::<Vec<_>>(),
+            vec![&"v1", &"v2"]
+        );
+        assert_eq!(trie.find_first_n(search_str, 1), vec![&"v1"]);
+        assert_eq!(
+            trie.find_until(search_str, |value| if *value == "v2" {
+                std::ops::ControlFlow::Break(*value)
+            } else {
+                std::ops::ControlFlow::Continue(())
+            }),
+            Some("v2")
+        );
+        assert_eq!(
+            trie.find_all_with_keys("mister the bobby"),
+            vec![(vec!["mister", "", "bobby"], &"v1")]
+        );
+
+        let query = trie.compile_query("mister {} bobby");
+        assert_eq!(query.find_all(&["the"]).unwrap(), vec![&"v1"]);
+        assert!(query.find_all(&["the big"]).unwrap().is_empty());
+
+        let mut matcher = trie.stream_matcher();
+        assert!(matcher.push_token("mister").is_empty());
+        assert!(matcher.push_token("the").is_empty());
+        assert_eq!(matcher.push_token("bobby"), vec![&"v1"]);
+        assert!(matcher.push_token("a").is_empty());
+        assert_eq!(matcher.push_token("jr"), vec![&"v2"]);
+        assert!(matcher.push_token("bobby").is_empty());
+
+        let found = trie.find_all_corrected("mistr the bobby");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].value, &"v1");
+        assert!(found[0].corrected);
+
+        let mut trie_builder = StringTrieBuilder::<&str>::default().with_parameters();
+        trie_builder.add("hello :name bye", "greeting").unwrap();
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let trie = trie.with_max_gap(1);
+        let found = trie.find_matches("hello bobby well bye");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].tokens, 0..4);
+        assert_eq!(found[0].param("name"), Some("bobby"));
+    }
+
+    #[test]
+    fn test_char_trie() {
+        let mut trie_builder: CharTrieBuilder<&str> = CharTrieBuilder::default();
+        trie_builder.add("car", "car").unwrap();
+        trie_builder.add("cart", "cart").unwrap();
+        trie_builder.add("cat", "cat").unwrap();
+        let trie: CharTrie<&str> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.get_exact("Cart"), Some(&"cart"));
+        let mut completions = trie.find_prefixed("ca");
+        completions.sort();
+        assert_eq!(completions, vec![&"car", &"cart", &"cat"]);
+        assert_eq!(trie.find_all("a cart"), vec![&"car", &"cart"]);
+    }
+
+    #[test]
+    fn test_find_all_with_tokenizer() {
         let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
-        trie_builder.add("test value", "v1").unwrap();
-        trie_builder.add("another test value", "v2").unwrap();
-        trie_builder.add("something else", "v3").unwrap();
-        trie_builder.add("another something else", "v3").unwrap();
+        trie_builder.add("bobby hill", "bobby").unwrap();
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert!(trie.find_all("users/bobby/hill").is_empty());
+        assert_eq!(
+            trie.find_all_with_tokenizer("users/bobby/hill", &PathTokenizer::new()),
+            vec![&"bobby"]
+        );
+    }
+
+    #[test]
+    fn test_find_tokens() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("mister bobby", "mister bobby").unwrap();
+        trie_builder.add("bobby", "bobby").unwrap();
         let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
-        let all = trie.find_all("this is a test value");
-        assert_eq!(all, vec![&"v1"]);
-        let mut all = trie.find_all("this is a another test value");
-        all.sort();
-        assert_eq!(all, vec![&"v1", &"v2"]);
-        let all = trie.find_all("another something else");
-        assert_eq!(all, vec![&"v3", &"v3"]);
-        let all = trie.find_all("nothing");
-        assert!(all.is_empty());
-        let all = trie.find_all("");
-        assert!(all.is_empty());
+        let tokens = ["mister", "bobby", "hill"];
+        assert_eq!(
+            trie.find_all_tokens(&tokens),
+            vec![&"mister bobby", &"bobby"]
+        );
+        assert_eq!(trie.find_any_tokens(&tokens), Some(&"mister bobby"));
+        assert_eq!(trie.find_any_tokens(&tokens[1..]), Some(&"bobby"));
+        assert_eq!(trie.find_any_tokens(&["Bobby"]), None);
+        let trie = trie.with_max_gap(1);
+        assert_eq!(
+            trie.find_all_tokens(&[
+                String::from("mister"),
+                String::from("the"),
+                String::from("bobby")
+            ]),
+            vec![&"mister bobby", &"bobby"]
+        );
+    }
+
+    #[test]
+    fn test_dyn_tokenizer_trie() {
+        let tokenizer_for = |name: &str| -> Arc<dyn DynTokenizer> {
+            match name {
+                "path" => Arc::new(PathTokenizer::new()),
+                _ => Arc::new(WhitespaceTokenizer::new()),
+            }
+        };
+        let tokenizer = tokenizer_for("path");
+        let mut trie_builder: StringTrieBuilder<&str, Arc<dyn DynTokenizer>> =
+            StringTrieBuilder::with_tokenizer(tokenizer.clone());
+        trie_builder.add("users/posts", "posts").unwrap();
+        let trie = trie_builder.build(tokenizer).unwrap();
+        assert_eq!(trie.find_any("/users/posts/"), Some(&"posts"));
+    }
+
+    #[test]
+    fn test_auto_trie_gaps() {
+        let mut trie_builder: AutoTrieBuilder<&str> = Default::default();
+        trie_builder.add("out **{1,1} memory", "oom").unwrap();
+        let trie: AutoTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.find_all("out of memory"), vec![&"oom"]);
+        assert!(trie.find_all("out memory").is_empty());
+        assert_eq!(trie.get_exact("out **{1,1} memory"), Some(&"oom"));
+        assert_eq!(
+            trie.find_all_with_keys("out of memory"),
+            vec![(vec!["out", "**{1,1}", "memory"], &"oom")]
+        );
+    }
+
+    #[test]
+    fn test_auto_trie_params() {
+        let mut trie_builder = AutoTrieBuilder::<&str>::default().with_parameters();
+        trie_builder.add("users :id posts", "posts").unwrap();
+        trie_builder.add("users :id", "user").unwrap();
+        let trie: AutoTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let found = trie.find_matches("get users Bob posts");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].value, &"user");
+        assert_eq!(found[0].param("id"), Some("Bob"));
+        assert_eq!(found[1].value, &"posts");
+        assert_eq!(found[1].params(), &[("id", String::from("Bob"))]);
+        assert!(trie.find_matches("users").is_empty());
+        let found = trie.find_matches_non_overlapping("users Ann posts users Bob");
+        let params: Vec<_> = found.iter().map(|found| found.param("id")).collect();
+        assert_eq!(params, vec![Some("Ann"), Some("Bob")]);
+        let score = trie.score("users Ann posts", |found| found.params().len() as f64);
+        assert_eq!(score, 2.0);
+    }
+
+    #[test]
+    fn test_params_are_opt_in() {
+        let mut trie_builder: StringTrieBuilder<&str> = Default::default();
+        trie_builder.add("hello :)", "smile").unwrap();
+        trie_builder.add(":wave: hi", "wave").unwrap();
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.find_all("well hello :) there"), vec![&"smile"]);
+        assert!(trie.find_all("hello :(").is_empty());
+        assert_eq!(trie.find_all(":wave: hi"), vec![&"wave"]);
+        assert!(trie.find_all("bye hi").is_empty());
+        assert!(trie.find_matches("hello :)")[0].params().is_empty());
+
+        let mut trie_builder: AutoTrieBuilder<&str> = Default::default();
+        trie_builder.add("hello :wave:", "wave").unwrap();
+        let trie: AutoTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.find_all("hello :wave:"), vec![&"wave"]);
+        assert!(trie.find_all("hello there").is_empty());
+
+        let mut trie_builder = StringTrieBuilder::<&str>::default().with_parameters();
+        trie_builder.add("hello :)", "smile").unwrap();
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.find_all("hello :("), vec![&"smile"]);
+    }
+
+    #[test]
+    fn test_string_trie_get_exact() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("bobby", "v1").unwrap();
+        trie_builder.add("mister bobby", "v2").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.get_exact("bobby"), Some(&"v1"));
+        assert_eq!(trie.get_exact("Mister Bobby"), Some(&"v2"));
+        assert!(trie.get_exact("mister").is_none());
+        assert!(trie.get_exact("the mister bobby").is_none());
+        assert!(trie.get_exact("bobby bobby").is_none());
+        assert!(trie.contains_key("mister bobby"));
+        assert!(!trie.contains_key("mister bobby jr"));
+        assert!(!trie.contains_key(""));
+    }
+
+    #[test]
+    fn test_string_trie_query() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("bobby", "v1").unwrap();
+        trie_builder.add("mister bobby", "v2").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap().with_version(3);
+        let response = trie.query(&QueryRequest::new("hi mister bobby"));
+        assert_eq!(response.schema_version, QUERY_SCHEMA_VERSION);
+        assert_eq!(response.trie_version, 3);
+        assert_eq!(
+            response.matches,
+            vec![
+                FlatMatch {
+                    token_start: 1,
+                    token_end: 3,
+                    value: "v2"
+                },
+                FlatMatch {
+                    token_start: 2,
+                    token_end: 3,
+                    value: "v1"
+                },
+            ]
+        );
+        let request =
+            QueryRequest::new("hi mister bobby").with_options(QueryOptions { limit: 1 });
+        assert_eq!(trie.query(&request).matches.len(), 1);
+
+        let view = request.as_view();
+        assert_eq!(view.text().unwrap(), "hi mister bobby");
+        assert_eq!(view.to_request().unwrap(), request);
+        let bytes = [b'h', 0xff];
+        let view = unsafe { QueryRequestView::from_raw_parts(bytes.as_ptr(), 2, view.options()) };
+        assert!(matches!(view.to_request(), Err(Error::InvalidUtf8 { .. })));
+        let view = response.as_view();
+        assert_eq!(view.trie_version(), 3);
+        assert_eq!(view.matches(), response.matches.as_slice());
+        assert_eq!(view.to_response(), response);
+    }
+
+    #[test]
+    fn test_string_trie_counts() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("bobby", "v1").unwrap();
+        trie_builder.add("mister bobby", "v2").unwrap();
+        trie_builder.add("mister", "v3").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.num_keys(), 3);
+        assert_eq!(trie.num_nodes(), 4);
+        let trie: StringTrie<&str> = Default::default();
+        assert_eq!(trie.num_keys(), 0);
+        assert_eq!(trie.num_nodes(), 1);
+    }
+
+    #[test]
+    fn test_string_trie_map_merge() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("mister bobby", "person").unwrap();
+        trie_builder.add("ice cream", "food").unwrap();
+        trie_builder.add("mister", "title").unwrap();
+        let categories: StringTrie<&str> = trie_builder.build_default().unwrap();
+        let mut trie_builder: StringTrieBuilder<u8> = StringTrieBuilder::default();
+        trie_builder.add("mister bobby", 1).unwrap();
+        trie_builder.add("ice cream", 3).unwrap();
+        trie_builder.add("ice", 2).unwrap();
+        let severities: StringTrie<u8> = trie_builder.build_default().unwrap();
+        let merged: StringTrie<(&str, u8)> = categories
+            .map_merge(&severities, StringTrieNodeBuilder::default(), |c, s| {
+                (*c, *s)
+            })
+            .unwrap();
+        assert_eq!(merged.num_keys(), 2);
+        assert_eq!(merged.get_exact("mister bobby"), Some(&("person", 1)));
+        assert_eq!(merged.get_exact("ice cream"), Some(&("food", 3)));
+        assert!(merged.get_exact("mister").is_none());
+        assert!(merged.get_exact("ice").is_none());
+    }
+
+    #[test]
+    fn test_string_trie_map_merge_with_parameters() {
+        let mut trie_builder: StringTrieBuilder<&str> =
+            StringTrieBuilder::default().with_parameters();
+        trie_builder.add("user :id", "user").unwrap();
+        let names: StringTrie<&str> = trie_builder
+            .build_default()
+            .unwrap()
+            .with_version(2)
+            .with_max_gap(1);
+        let mut trie_builder: StringTrieBuilder<u8> =
+            StringTrieBuilder::default().with_parameters();
+        trie_builder.add("user :id", 1).unwrap();
+        let ids: StringTrie<u8> = trie_builder.build_default().unwrap();
+        let merged: StringTrie<(&str, u8)> = names
+            .map_merge(&ids, StringTrieNodeBuilder::default(), |n, i| (*n, *i))
+            .unwrap();
+        assert_eq!(merged.version(), 2);
+        assert_eq!(merged.max_gap(), 1);
+        assert_eq!(merged.find_all("hi user 42"), vec![&("user", 1)]);
+        assert_eq!(merged.find_all("user the 42"), vec![&("user", 1)]);
+        let found = merged.find_matches("user 42");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].param("id"), Some("42"));
+    }
+
+    #[test]
+    fn test_string_trie_approx_memory_bytes() {
+        let empty: StringTrie<&str> = Default::default();
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("mister bobby", "v1").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        assert!(empty.approx_memory_bytes() >= mem::size_of::<StringTrie<&str>>());
+        assert!(trie.approx_memory_bytes() > empty.approx_memory_bytes());
+    }
+
+    #[test]
+    // With compact_str, short tokens are stored inline instead of being shared.
+    #[cfg(not(feature = "compact_str"))]
+    fn test_string_trie_interns_tokens() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("the cat", "cat").unwrap();
+        trie_builder.add("on the mat", "mat").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        let key_of = |node: &StringTrieNode<&str>| {
+            node.children()
+                .into_iter()
+                .find(|(key, _)| *key == "the")
+                .map(|(key, _)| key.as_ptr())
+        };
+        // `on the mat` is a chain, so `the` is in the tail of `on`.
+        let on = trie.root.get_child("on").unwrap();
+        assert_eq!(
+            key_of(&trie.root),
+            on.tail().first().map(|key| key.as_ref().as_ptr())
+        );
+        assert_eq!(trie.find_all("sat on the mat"), vec![&"mat"]);
+    }
+
+    #[test]
+    #[cfg(feature = "compact_str")]
+    fn test_string_trie_copies_tokens_with_compact_str() {
+        let long = "pneumonoultramicroscopicsilicovolcanoconiosis";
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add(format!("the {long}"), "short").unwrap();
+        trie_builder
+            .add(format!("{long} on the mat"), "long")
+            .unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        let key_of = |node: &StringTrieNode<&str>, token: &str| {
+            node.children()
+                .into_iter()
+                .find(|(key, _)| *key == token)
+                .map(|(key, _)| key.as_ptr())
+                .unwrap()
+        };
+        // Every node has its own copy of its key, whether it's stored inline or not.
+        let the = trie.root.get_child("the").unwrap();
+        let tail = trie.root.get_child(long).unwrap().tail();
+        assert_ne!(key_of(&trie.root, "the"), tail[1].as_ref().as_ptr());
+        assert_ne!(key_of(&trie.root, long), the.tail()[0].as_ref().as_ptr());
+        assert_eq!(
+            trie.find_all(format!("the {long} on the mat")),
+            vec![&"short", &"long"]
+        );
+    }
+
+    #[test]
+    fn test_string_trie_merges_chains() {
+        let mut trie_builder: StringTrieBuilder<u32> = StringTrieBuilder::default();
+        trie_builder
+            .add_all([
+                ("department of motor vehicles", 1),
+                ("department of labor", 2),
+                ("new york city", 3),
+                ("big **{0,2} deal", 4),
+            ])
+            .unwrap();
+        let trie: StringTrie<u32, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let tail = |node: &StringTrieNode<u32>| -> Vec<String> {
+            node.tail()
+                .iter()
+                .map(|token| token.as_ref().to_owned())
+                .collect()
+        };
+        let department = trie.root.get_child("department").unwrap();
+        assert_eq!(tail(department), vec!["of"]);
+        assert_eq!(
+            tail(department.get_child("motor").unwrap()),
+            vec!["vehicles"]
+        );
+        assert_eq!(
+            tail(trie.root.get_child("new").unwrap()),
+            vec!["york", "city"]
+        );
+        // Gaps are never merged into a chain.
+        assert!(tail(trie.root.get_child("big").unwrap()).is_empty());
+        assert_eq!(trie.num_nodes(), 8);
+
+        assert_eq!(trie.find_all("the department of motor vehicles"), vec![&1]);
+        assert_eq!(
+            trie.find_iter("new york city").collect::<Vec<_>>(),
+            vec![&3]
+        );
+        assert!(trie.find_all("department of new york").is_empty());
+        assert_eq!(trie.find_all("a big old deal"), vec![&4]);
+        assert_eq!(trie.get_exact("new york city"), Some(&3));
+        assert_eq!(trie.get_exact("new york"), None);
+        assert_eq!(trie.find_prefixed("department"), vec![&2, &1]);
+        assert_eq!(trie.find_prefixed("new york"), vec![&3]);
+        assert!(trie.find_prefixed("new jersey").is_empty());
+        assert!(trie.keys().contains(&vec!["new", "york", "city"]));
+        assert!(trie.vocabulary().contains("vehicles"));
+        assert!(
+            trie.display_tree()
+                .to_string()
+                .contains("new york city => 3\n")
+        );
+
+        let mut matcher = trie.stream_matcher();
+        assert!(matcher.push_token("new").is_empty());
+        assert!(matcher.push_token("york").is_empty());
+        assert_eq!(matcher.push_token("city"), vec![&3]);
+
+        let trie = trie.with_max_gap(1);
+        assert_eq!(trie.find_all("new york the city"), vec![&3]);
+        assert!(trie.find_all("new york the big city").is_empty());
+    }
+
+    #[test]
+    fn test_ordered_trie() {
+        let mut trie_builder: OrderedTrieBuilder<&str> = OrderedTrieBuilder::default();
+        for key in ["zebra", "apple", "mango", "apple pie"] {
+            trie_builder.add(key, key).unwrap();
+        }
+        let trie: OrderedTrie<&str> = trie_builder.build_default().unwrap();
+        let keys: Vec<_> = trie
+            .root
+            .children()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, vec!["apple", "mango", "zebra"]);
+        assert_eq!(trie.find_prefixed("apple"), vec![&"apple", &"apple pie"]);
+        assert_eq!(trie.get_exact("Mango"), Some(&"mango"));
+    }
+
+    #[test]
+    fn test_string_trie_sorted_children() {
+        let mut trie_builder: StringTrieBuilder<usize> = StringTrieBuilder::default();
+        for (i, key) in ["delta", "alpha", "charlie", "bravo", "echo"]
+            .iter()
+            .enumerate()
+        {
+            trie_builder.add(key, i).unwrap();
+        }
+        let trie: StringTrie<usize> = trie_builder.build_default().unwrap();
+        let keys: Vec<_> = trie
+            .root
+            .children()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, vec!["alpha", "bravo", "charlie", "delta", "echo"]);
+        assert_eq!(trie.get_exact("charlie"), Some(&2));
+        assert_eq!(trie.get_exact("foxtrot"), None);
+    }
+
+    #[test]
+    fn test_string_trie_flatten() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("mister bobby", "mister bobby").unwrap();
+        trie_builder.add("bobby", "bobby").unwrap();
+        trie_builder.add("bobby hill", "bobby hill").unwrap();
+        trie_builder.add("ice cream", "ice cream").unwrap();
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let flat = trie.flatten().unwrap();
+        assert_eq!(flat.num_keys(), 4);
+        for search_str in ["mister bobby hill", "no ice cream", "nothing", ""] {
+            assert_eq!(flat.find_all(search_str), trie.find_all(search_str));
+            assert_eq!(flat.find_any(search_str), trie.find_any(search_str));
+        }
+        assert_eq!(flat.get_exact("bobby hill"), Some(&"bobby hill"));
+        assert_eq!(flat.get_exact("mister"), None);
+        assert_eq!(flat.find_prefixed("bobby"), vec![&"bobby", &"bobby hill"]);
+
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder
+            .add("department of the interior", "doi")
+            .unwrap();
+        trie_builder.add("department of labor", "dol").unwrap();
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let flat = trie.flatten().unwrap();
+        assert_eq!(flat.find_all("the department of labor"), vec![&"dol"]);
+        assert_eq!(flat.get_exact("department of the interior"), Some(&"doi"));
+        assert_eq!(flat.get_exact("department of the"), None);
+        assert_eq!(flat.find_prefixed("department of"), vec![&"dol", &"doi"]);
+        assert_eq!(flat.find_prefixed("department of the"), vec![&"doi"]);
+        assert!(flat.find_prefixed("department of the state").is_empty());
+
+        let mut trie_builder: CharTrieBuilder<&str> = CharTrieBuilder::default();
+        for (key, value) in [
+            ("walk", "verb"),
+            ("walks", "verb"),
+            ("walked", "verb"),
+            ("talk", "verb"),
+            ("talks", "verb"),
+            ("talked", "verb"),
+            ("talkative", "adjective"),
+        ] {
+            trie_builder.add(key, value).unwrap();
+        }
+        let trie: CharTrie<&str> = trie_builder.build_default().unwrap();
+        let flat = trie.flatten().unwrap();
+        let minimized = trie.flatten_minimized().unwrap();
+        assert_eq!(minimized.num_keys(), 7);
+        assert!(minimized.approx_memory_bytes() < flat.approx_memory_bytes());
+        for key in ["walked", "talked", "talkative", "walkative", "talk", "wal"] {
+            assert_eq!(minimized.get_exact(key), trie.get_exact(key));
+        }
+        assert_eq!(minimized.get_exact("talked"), Some(&"verb"));
+        assert_eq!(minimized.find_prefixed("walk").len(), 3);
+        assert_eq!(minimized.find_prefixed("talk").len(), 4);
+
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("out **{1,1} memory", "oom").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        assert!(matches!(
+            trie.flatten(),
+            Err(Error::GapNotSupported { token }) if token == "**{1,1}"
+        ));
+
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("mister bobby", "v1").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        let trie = trie.with_max_gap(1);
+        assert!(matches!(
+            trie.flatten(),
+            Err(Error::MaxGapNotSupported { max_gap: 1 })
+        ));
+        assert!(matches!(
+            trie.flatten_minimized(),
+            Err(Error::MaxGapNotSupported { max_gap: 1 })
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "aho-corasick")]
+    fn test_aho_corasick_trie() {
+        let keys = [
+            ("mister bobby", "mister bobby"),
+            ("bobby", "bobby"),
+            ("bobby hill", "bobby hill"),
+            ("ice cream", "ice cream"),
+        ];
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        let mut aho_builder: AhoCorasickTrieBuilder<&str> = AhoCorasickTrieBuilder::default();
+        for (key, value) in keys {
+            trie_builder.add(key, value).unwrap();
+            aho_builder.add(key, value).unwrap();
+        }
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let aho: AhoCorasickTrie<&str> = aho_builder.build_default().unwrap();
+        assert_eq!(aho.num_keys(), 4);
+        for search_str in [
+            "mister bobby hill",
+            "bobby hill",
+            "bobbys ice cream",
+            "icecream",
+            "",
+        ] {
+            assert_eq!(aho.find_all(search_str), trie.find_all(search_str));
+            assert_eq!(aho.find_any(search_str), trie.find_any(search_str));
+        }
+        assert_eq!(aho.find_any("the bobby hill"), Some(&"bobby"));
+        assert!(
+            aho.find_any_tokens(&["ice\u{1F}cream", "ice", "cream"])
+                .is_some()
+        );
+        assert!(aho.find_any_tokens(&["ice\u{1F}cream"]).is_none());
+        assert!(aho.find_any_tokens(&["bobby\u{1F}", "hill"]).is_none());
+        let mut aho_builder: AhoCorasickTrieBuilder<&str> = AhoCorasickTrieBuilder::default();
+        assert!(aho_builder.add("out **{1,1} memory", "oom").is_err());
+        assert!(matches!(
+            aho_builder.add_tokens(&["ice\u{1F}cream"], "ice cream"),
+            Err(Error::ReservedCharacter { .. })
+        ));
+        assert!(matches!(
+            aho_builder.add(" ", "nothing"),
+            Err(Error::EmptyKey { .. })
+        ));
+        assert!(matches!(
+            aho_builder.add_tokens::<&str>(&[], "nothing"),
+            Err(Error::EmptyKey { .. })
+        ));
+    }
+
+    #[test]
+    fn test_string_trie_find_all_annotated() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("bobby", "name").unwrap();
+        trie_builder.add("run", "verb").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        let tokens = vec![
+            Token::new("bobby", 0, 5).with_pos_tag("PROPN"),
+            Token::new("went", 6, 10).with_pos_tag("VERB"),
+            Token::new("on", 11, 13).with_pos_tag("ADP"),
+            Token::new("a", 14, 15).with_pos_tag("DET"),
+            Token::new("run", 16, 19).with_pos_tag("NOUN"),
+        ];
+        let found = trie.find_all_annotated(&tokens);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].value, &"name");
+        assert_eq!(found[0].span(), 0..5);
+        assert_eq!(found[1].value, &"verb");
+        assert_eq!(found[1].span(), 16..19);
+        let found = trie.find_all_annotated_with(&tokens, |m| {
+            *m.value != "verb" || m.pos_tags().all(|tag| tag == Some("VERB"))
+        });
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].value, &"name");
+    }
+
+    #[test]
+    fn test_string_trie_find_all_in_context() {
+        let mut trie_builder: StringTrieBuilder<Contextual<&str>> = StringTrieBuilder::default();
+        trie_builder
+            .add_with_context(
+                "house",
+                "doctor",
+                vec![ContextRule::preceded_by(3, ["dr", "doctor"])],
+            )
+            .unwrap();
+        trie_builder.add("cuddy", "dean".into()).unwrap();
+        let trie: StringTrie<Contextual<&str>> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.find_all_in_context("dr gregory house"), vec![&"doctor"]);
+        assert_eq!(trie.find_all_in_context("doctor a b house"), vec![&"doctor"]);
+        assert!(trie.find_all_in_context("doctor a b c house").is_empty());
+        assert!(trie.find_all_in_context("a big house").is_empty());
+        assert_eq!(trie.find_all_in_context("cuddy"), vec![&"dean"]);
+    }
+
+    #[cfg(feature = "regex-filtered")]
+    #[test]
+    fn test_regex_trie_find_grouped() {
+        let mut trie_builder: RegexTrieBuilder<&str> = Default::default();
+        trie_builder.add("paris", "city").unwrap();
+        trie_builder.add("par.s", "person").unwrap();
+        trie_builder.add("hilton", "hotel").unwrap();
+        let trie: RegexTrie<&str> = trie_builder.build_default().unwrap();
+        let found = trie.find_grouped("paris hilton");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].tokens, 0..1);
+        assert!(found[0].is_ambiguous());
+        assert_eq!(found[0].values.len(), 2);
+        assert_eq!(found[0].confidence(), 0.5);
+        assert_eq!(found[1].tokens, 1..2);
+        assert!(!found[1].is_ambiguous());
+        assert_eq!(found[1].values, vec![&"hotel"]);
+    }
+
+    #[test]
+    fn test_string_trie_display_tree() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("bobby", "v1").unwrap();
+        trie_builder.add("mister bobby", "v2").unwrap();
+        trie_builder.add("mister mark", "v3").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        assert_eq!(
+            trie.display_tree().to_string(),
+            "bobby => \"v1\"\nmister\n  bobby => \"v2\"\n  mark => \"v3\"\n"
+        );
+    }
+
+    #[test]
+    fn test_string_trie_add_with_acronym() {
+        let mut trie_builder: StringTrieBuilder<Derivable<u8>> = StringTrieBuilder::default();
+        trie_builder
+            .add_with_acronym("north atlantic treaty organization", 1)
+            .unwrap();
+        trie_builder.add_with_acronym("nations", 2).unwrap();
+        let trie: StringTrie<Derivable<u8>> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.find_all("nato"), vec![&Derivable::derived(1)]);
+        assert_eq!(
+            trie.find_all("the north atlantic treaty organization"),
+            vec![&Derivable::original(1)]
+        );
+        assert_eq!(trie.find_all("nations"), vec![&Derivable::original(2)]);
+        assert_eq!(trie.num_keys(), 3);
+    }
+
+    #[test]
+    fn test_acronyms_dont_replace_keys() {
+        let mut trie_builder: StringTrieBuilder<Derivable<u8>> =
+            StringTrieBuilder::default().with_duplicate_policy(DuplicatePolicy::Error);
+        trie_builder.add_with_acronym("ibm", 1).unwrap();
+        trie_builder
+            .add_with_acronym("international business machines", 2)
+            .unwrap();
+        trie_builder.add_with_acronym("united nations", 3).unwrap();
+        trie_builder.add_with_acronym("un", 4).unwrap();
+        let trie: StringTrie<Derivable<u8>> = trie_builder.build_default().unwrap();
+        // The real key was added first, so the acronym is skipped.
+        assert_eq!(trie.get_exact("ibm"), Some(&Derivable::original(1)));
+        // The real key was added after the acronym, so it replaces it.
+        assert_eq!(trie.get_exact("un"), Some(&Derivable::original(4)));
+        assert_eq!(
+            trie.get_exact("united nations"),
+            Some(&Derivable::original(3))
+        );
+    }
+
+    #[test]
+    fn test_string_trie_find_matches() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("bobby", "v1").unwrap();
+        trie_builder.add("mister bobby", "v2").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        let search_str = "Hi  Mister Bobby";
+        let found = trie.find_matches(search_str);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].value, &"v2");
+        assert_eq!(found[0].tokens, 1..3);
+        assert_eq!(found[0].as_str(search_str), "Mister Bobby");
+        assert_eq!(found[1].value, &"v1");
+        assert_eq!(found[1].tokens, 2..3);
+        assert_eq!(found[1].span, 11..16);
+    }
+
+    #[test]
+    fn test_string_trie_with_compound_splitting() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("credit card number", "pii").unwrap();
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert!(trie.find_all("my creditcardnumber").is_empty());
+        let trie = trie.with_compound_splitting();
+        assert_eq!(trie.find_all("my creditcardnumber"), vec![&"pii"]);
+        assert_eq!(trie.find_all("my credit cardnumber"), vec![&"pii"]);
+    }
+
+    #[test]
+    fn test_string_trie_find_all_with_keys() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("bobby", "v1").unwrap();
+        trie_builder.add("mister bobby", "v2").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        assert_eq!(
+            trie.find_all_with_keys("mister bobby"),
+            vec![(vec!["mister", "bobby"], &"v2"), (vec!["bobby"], &"v1")]
+        );
+    }
+
+    #[test]
+    fn test_string_trie_find_all_corrected() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("mister bobby", "v1").unwrap();
+        trie_builder.add("mark", "v2").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        let found = trie.find_all_corrected("mister bobby");
+        assert_eq!(found.len(), 1);
+        assert!(!found[0].corrected);
+        let found = trie.find_all_corrected("hi mistr bobby");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].value, &"v1");
+        assert!(found[0].corrected);
+        let found = trie.find_all_corrected("marks");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].value, &"v2");
+        assert!(found[0].corrected);
+        assert!(trie.find_all_corrected("mister bobyy").is_empty());
+        assert!(trie.find_all_corrected("marker").is_empty());
+    }
+
+    #[test]
+    fn test_string_trie_find_longest() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("mister", "v1").unwrap();
+        trie_builder.add("mister bobby", "v2").unwrap();
+        trie_builder.add("bobby", "v3").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.find_any("mister bobby"), Some(&"v1"));
+        assert_eq!(trie.find_longest("mister bobby"), vec![&"v2", &"v3"]);
+        assert_eq!(trie.find_longest("mister mark"), vec![&"v1"]);
+        assert!(trie.find_longest("mark").is_empty());
+    }
+
+    #[test]
+    fn test_string_trie_find_non_overlapping() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("bobby", "v1").unwrap();
+        trie_builder.add("mister bobby", "v2").unwrap();
+        trie_builder.add("mister", "v3").unwrap();
+        trie_builder.add("bobby jr", "v4").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.find_non_overlapping("mister bobby"), vec![&"v2"]);
+        assert_eq!(trie.find_non_overlapping("mister bobby jr"), vec![&"v2"]);
+        assert_eq!(
+            trie.find_non_overlapping("mister mister bobby jr"),
+            vec![&"v3", &"v2"]
+        );
+        assert_eq!(trie.find_non_overlapping("a bobby jr"), vec![&"v4"]);
+        assert!(trie.find_non_overlapping("nothing").is_empty());
+    }
+
+    #[test]
+    fn test_string_trie_find_all_utf16() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("mister bobby", "v1").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        let mut search_str = vec![0xFEFF];
+        search_str.extend("hi Mister Bobby".encode_utf16());
+        search_str.push(0);
+        assert_eq!(trie.find_all_utf16(&search_str), vec![&"v1"]);
+        assert_eq!(trie.find_any_utf16(&search_str), Some(&"v1"));
+        assert!(trie.find_all_utf16(&[0xD800]).is_empty());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex_set_build_is_deterministic() {
+        let keys = ["zed", "alpha", "m.*", "beta"];
+        let mut forwards = RegexSetTrieNodeBuilder::default();
+        let mut backwards = RegexSetTrieNodeBuilder::default();
+        for key in keys {
+            forwards.add([key].into_iter(), key).unwrap();
+        }
+        for key in keys.into_iter().rev() {
+            backwards.add([key].into_iter(), key).unwrap();
+        }
+        let forwards = forwards.build().unwrap();
+        let backwards = backwards.build().unwrap();
+        let forward_keys: Vec<_> = forwards.children().into_iter().map(|(k, _)| k).collect();
+        let backward_keys: Vec<_> = backwards.children().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(forward_keys, vec!["alpha", "beta", "m.*", "zed"]);
+        assert_eq!(forward_keys, backward_keys);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex_set_anchoring() {
+        let build = |anchoring: Anchoring| {
+            let mut trie_builder: ImmutableTrieBuilder<RegexSetTrieNodeBuilder<&str>, &str> =
+                ImmutableTrieBuilder::new(
+                    WhitespaceTokenizer::new(),
+                    RegexSetTrieNodeBuilder::default().with_anchoring(anchoring),
+                );
+            trie_builder.add("^err$ [0-9]+", "error").unwrap();
+            trie_builder.add("warn|info", "log").unwrap();
+            let trie: ImmutableTrie<_, _, WhitespaceTokenizer> =
+                trie_builder.build_default().unwrap();
+            trie
+        };
+
+        let trie = build(Anchoring::Full);
+        assert_eq!(trie.find_all("err 503"), vec![&"error"]);
+        assert!(trie.find_all("err 503x").is_empty());
+        // Every branch of an alternation has to match the whole token.
+        assert_eq!(trie.find_all("warn"), vec![&"log"]);
+        assert!(trie.find_all("warning").is_empty());
+        assert!(trie.find_all("preinfo").is_empty());
+        // A `$` after an escaped backslash is still an anchor, but an escaped `$` isn't.
+        assert_eq!(Anchoring::Full.anchor(r"dir\\$"), r"^(?:dir\\)$");
+        assert_eq!(Anchoring::Full.anchor(r"cost\$"), r"^(?:cost\$)$");
+        // The keys are reported as they were added, anchors and all.
+        let keys: Vec<_> = trie.root.children().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec!["^err$", "warn|info"]);
+        assert_eq!(
+            trie.keys(),
+            vec![vec!["^err$", "[0-9]+"], vec!["warn|info"]]
+        );
+
+        let trie = build(Anchoring::None);
+        assert_eq!(trie.find_all("err x503x"), vec![&"error"]);
+        assert_eq!(trie.find_all("prewarning"), vec![&"log"]);
+
+        let trie = build(Anchoring::WordBoundary);
+        assert_eq!(trie.find_all("info-level"), vec![&"log"]);
+        assert!(trie.find_all("information").is_empty());
+        let keys: Vec<_> = trie.root.children().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec!["^err$", "warn|info"]);
+
+        let trie = build(Anchoring::Custom {
+            prefix: "^(?:".into(),
+            suffix: ")".into(),
+        });
+        assert_eq!(trie.find_all("infos"), vec![&"log"]);
+        assert!(trie.find_all("preinfo").is_empty());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_invalid_pattern_error() {
+        let mut trie_builder: ImmutableTrieBuilder<RegexSetTrieNodeBuilder<&str>, &str> =
+            Default::default();
+        trie_builder.add("status [0-9]+", "status").unwrap();
+        let err = trie_builder.add("Order  #([0-9]+", "order").unwrap_err();
+        let Error::InvalidPattern {
+            key,
+            index,
+            pattern,
+            source,
+        } = err
+        else {
+            panic!("expected an invalid pattern error, got {err:?}");
+        };
+        assert_eq!(key, "Order  #([0-9]+");
+        assert_eq!(index, 1);
+        assert_eq!(pattern, "#([0-9]+");
+        assert!(matches!(*source, Error::PatternCompile { pattern, .. } if pattern == "#([0-9]+"));
+
+        let mut trie_builder: AutoTrieBuilder<&str> = Default::default();
+        trie_builder.add("a ** b", "gap").unwrap();
+        assert!(matches!(
+            trie_builder.add_tokens(["a", "b", "re:[c"], "bad"),
+            Err(Error::InvalidPattern { index: 2, key, .. }) if key == "a b re:[c"
+        ));
+        // Nothing from a key with an invalid pattern is added.
+        let trie: AutoTrie<&str> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.num_keys(), 1);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_add_all() {
+        let mut trie_builder: AutoTrieBuilder<&str> = Default::default();
+        let entries = [
+            ("status re:[0-9]+", "status"),
+            ("status re:[0-9", "bad range"),
+            ("status ok", "ok"),
+            ("  ", "no tokens"),
+        ];
+        let errors = trie_builder.add_all(entries).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(
+            errors[0],
+            (1, Error::InvalidPattern { index: 1, .. })
+        ));
+        assert!(matches!(errors[1], (3, Error::EmptyKey { .. })));
+        let trie: AutoTrie<&str> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.num_keys(), 2);
+        assert_eq!(
+            trie.find_all("status 200 status ok"),
+            vec![&"status", &"ok"]
+        );
+
+        let mut trie_builder: AutoTrieBuilder<&str> = Default::default();
+        trie_builder.add_all([("a", "a"), ("b", "b")]).unwrap();
+
+        let mut trie_builder: StringTrieBuilder<u32> =
+            StringTrieBuilder::default().with_duplicate_policy(DuplicatePolicy::Error);
+        let errors = trie_builder
+            .add_all([("key", 1), ("key", 2), ("other", 3)])
+            .unwrap_err();
+        assert!(matches!(errors[..], [(1, Error::DuplicateKey { .. })]));
+        let trie: StringTrie<u32> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.get_exact("key"), Some(&1));
+        assert_eq!(trie.num_keys(), 2);
+    }
+
+    #[test]
+    fn test_from_wordlist() {
+        let wordlist = "# Greetings\n  hello there \n\nhi\n   # indented comment\ngood morning\n";
+        let matcher_builder =
+            StringMatcherBuilder::<WhitespaceTokenizer>::from_wordlist(wordlist.as_bytes())
+                .unwrap();
+        let matcher: StringMatcher<WhitespaceTokenizer> = matcher_builder.build_default().unwrap();
+        assert_eq!(matcher.num_keys(), 3);
+        assert!(matcher.has_match("well hello there"));
+        assert!(matcher.has_match("good morning all"));
+        assert!(!matcher.has_match("greetings"));
+        assert!(!matcher.has_match("comment"));
+
+        let mut matcher_builder: StringMatcherBuilder = Default::default();
+        assert!(matches!(
+            matcher_builder.add_wordlist("ok\n**{0,2} trailing\n".as_bytes()),
+            Err(Error::InvalidLine { line: 2, .. })
+        ));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_round_trip() {
+        let json = r#"{"new york": 3, "york": 1, "boston": 2}"#;
+        let trie_builder: StringTrieBuilder<u32> =
+            StringTrieBuilder::from_json(json.as_bytes()).unwrap();
+        let trie: StringTrie<u32, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.find_all("new york to boston"), vec![&3, &1, &2]);
+
+        let mut exported = Vec::new();
+        trie.to_json(&mut exported).unwrap();
+        let trie_builder: StringTrieBuilder<u32> =
+            StringTrieBuilder::from_json(exported.as_slice()).unwrap();
+        let reimported: StringTrie<u32, WhitespaceTokenizer> =
+            trie_builder.build_default().unwrap();
+        assert_eq!(reimported, trie);
+
+        assert!(matches!(
+            StringTrieBuilder::<u32>::from_json(r#"{"york": "one"}"#.as_bytes()),
+            Err(Error::Json { .. })
+        ));
+
+        // Repeated keys go through the duplicate policy in the order they're written.
+        let json = r#"{"york": 2, "boston": 3, "York": 1}"#;
+        let trie_builder: StringTrieBuilder<u32> =
+            StringTrieBuilder::from_json(json.as_bytes()).unwrap();
+        let trie: StringTrie<u32, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.get_exact("york"), Some(&1));
+        let mut trie_builder: StringTrieBuilder<u32> =
+            StringTrieBuilder::default().with_duplicate_policy(DuplicatePolicy::Error);
+        assert!(matches!(
+            trie_builder.add_json(r#"{"york": 1, "york": 2}"#.as_bytes()),
+            Err(Error::DuplicateKey { key }) if key == "york"
+        ));
+    }
+
+    #[cfg(feature = "csv")]
+    #[test]
+    fn test_csv_rules() {
+        let rules = "key,value,weight,tokenizer
+# Cities
+new york, 1, 5
+york,2
+/api/users,3,,path
+";
+        let trie_builder: StringTrieBuilder<Weighted<u32>> =
+            StringTrieBuilder::from_weighted_csv(rules.as_bytes()).unwrap();
+        let trie: StringTrie<Weighted<u32>, WhitespaceTokenizer> =
+            trie_builder.build_default().unwrap();
+        assert_eq!(trie.num_keys(), 3);
+        assert_eq!(trie.find_best("new york").unwrap(), &Weighted::new(1, 5));
+        assert_eq!(
+            trie.get_exact_tokens(&["api", "users"]),
+            Some(&Weighted::new(3, 0))
+        );
+
+        // Searches are split with the trie's tokenizer, so the path is only found when that
+        // splits paths the same way.
+        assert!(trie.find_all("/api/users").is_empty());
+        let trie_builder: StringTrieBuilder<Weighted<u32>> =
+            StringTrieBuilder::from_weighted_csv(rules.as_bytes()).unwrap();
+        let trie: StringTrie<Weighted<u32>, PathTokenizer> =
+            trie_builder.build(PathTokenizer::default()).unwrap();
+        assert_eq!(trie.find_all("/api/users"), vec![&Weighted::new(3, 0)]);
+
+        let trie_builder: StringTrieBuilder<u32> =
+            StringTrieBuilder::from_csv(rules.as_bytes()).unwrap();
+        let trie: StringTrie<u32, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.find_all("new york"), vec![&1, &2]);
+
+        assert!(matches!(
+            StringTrieBuilder::<u32>::from_csv("key,value\nok,1\nbad,one\n".as_bytes()),
+            Err(Error::InvalidLine { line: 3, source })
+                if matches!(*source, Error::InvalidField { field: "value", .. })
+        ));
+        assert!(matches!(
+            StringTrieBuilder::<u32>::from_csv("key,value\nok,1,,shouting\n".as_bytes()),
+            Err(Error::InvalidLine { line: 2, source })
+                if matches!(*source, Error::UnknownTokenizer { .. })
+        ));
+    }
+
+    #[test]
+    fn test_reserve() {
+        let mut trie_builder: StringTrieBuilder<&str> =
+            ImmutableTrieBuilder::with_builder(StringTrieNodeBuilder::with_capacity(16));
+        trie_builder.reserve(1000);
+        trie_builder
+            .add_all([("mister bobby", "v1"), ("mark", "v2")])
+            .unwrap();
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.find_all("mark and mister bobby"), vec![&"v2", &"v1"]);
+    }
+
+    #[test]
+    fn test_trie_equality() {
+        let entries = [("new york", 1), ("york", 2), ("new jersey", 3)];
+        let mut trie_builder: StringTrieBuilder<u32> = StringTrieBuilder::default();
+        trie_builder.add_all(entries).unwrap();
+        let string_trie: StringTrie<u32> = trie_builder.build_default().unwrap();
+        let mut trie_builder: AutoTrieBuilder<u32> = AutoTrieBuilder::default();
+        trie_builder.add_all(entries.into_iter().rev()).unwrap();
+        let auto_trie: AutoTrie<u32, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert!(string_trie == auto_trie);
+        assert_eq!(string_trie.clone().with_version(2), string_trie);
+
+        let mut trie_builder: StringTrieBuilder<u32> = StringTrieBuilder::default();
+        trie_builder
+            .add_all([("new york", 1), ("york", 4), ("new jersey", 3)])
+            .unwrap();
+        let changed: StringTrie<u32> = trie_builder.build_default().unwrap();
+        assert_ne!(changed, string_trie);
+        let mut trie_builder: StringTrieBuilder<u32> = StringTrieBuilder::default();
+        trie_builder
+            .add_all([("new york", 1), ("york", 2)])
+            .unwrap();
+        let missing: StringTrie<u32> = trie_builder.build_default().unwrap();
+        assert_ne!(missing, string_trie);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_structured_errors() {
+        use std::error::Error as _;
+
+        let mut trie_builder: ImmutableTrieBuilder<RegexSetTrieNodeBuilder<&str>, &str> =
+            Default::default();
+        let err = trie_builder.add("a (b", "bad").unwrap_err();
+        let source = err.source().unwrap();
+        assert!(source.to_string().contains("\"(b\""));
+        assert!(source.source().is_some());
+
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("", "empty").unwrap();
+        assert!(matches!(
+            trie_builder.add(" \t ", "blank"),
+            Err(Error::EmptyKey { key }) if key == " \t "
+        ));
+
+        let mut trie_builder: ImmutableTrieBuilder<RegexSetTrieNodeBuilder<&str>, &str> =
+            Default::default();
+        trie_builder.add("status ok", "ok").unwrap();
+        trie_builder.add("status ^ok$", "anchored ok").unwrap();
+        let built: Result<ImmutableTrie<_, _, WhitespaceTokenizer>> = trie_builder.build_default();
+        assert!(matches!(
+            built,
+            Err(Error::BuildConflict { first, second, pattern })
+                if first == "^ok$" && second == "ok" && pattern == "^(?:ok)$"
+        ));
+    }
+
+    #[test]
+    fn test_string_trie_manifest() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("mister bobby", "v1").unwrap();
+        trie_builder.add("mark", "v2").unwrap();
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let manifest = trie.manifest();
+        assert_eq!(manifest.entry_count, 2);
+        assert_eq!(manifest.crate_version, env!("CARGO_PKG_VERSION"));
+        trie.verify_manifest(&manifest).unwrap();
+
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("mark", "v2").unwrap();
+        trie_builder.add("mister bobby", "v3").unwrap();
+        let other: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert_eq!(other.manifest(), manifest);
+
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("mister bob", "v1").unwrap();
+        trie_builder.add("mark", "v2").unwrap();
+        let other: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert!(matches!(
+            other.verify_manifest(&manifest),
+            Err(Error::ManifestMismatch { field: "keys_hash" })
+        ));
+
+        // Tokenizers that split strings the same way have the same hash.
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("mister bobby", "v1").unwrap();
+        trie_builder.add("mark", "v2").unwrap();
+        let other: StringTrie<&str, CustomWhitespaceTokenizer> = trie_builder
+            .build(CustomWhitespaceTokenizer::new())
+            .unwrap();
+        other.verify_manifest(&manifest).unwrap();
+
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("mister bobby", "v1").unwrap();
+        trie_builder.add("mark", "v2").unwrap();
+        let tokenizer = CustomWhitespaceTokenizer::new().with_case_sensitive(true);
+        let other: StringTrie<&str, CustomWhitespaceTokenizer> =
+            trie_builder.build(tokenizer).unwrap();
+        assert!(matches!(
+            other.verify_manifest(&manifest),
+            Err(Error::ManifestMismatch {
+                field: "tokenizer_hash"
+            })
+        ));
+    }
+
+    #[test]
+    fn test_shared_trie() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("mister bobby", "v1").unwrap();
+        trie_builder.add("mark", "v2").unwrap();
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let shared = trie.into_shared();
+        let workers: Vec<_> = (0..2)
+            .map(|_| {
+                let shared = shared.clone();
+                std::thread::spawn(move || shared.find_all("mark and mister bobby").len())
+            })
+            .collect();
+        for worker in workers {
+            assert_eq!(worker.join().unwrap(), 2);
+        }
+
+        let clone = shared.clone();
+        assert!(SharedTrie::ptr_eq(&shared, &clone));
+        let shared = SharedTrie::try_unwrap(shared).unwrap_err();
+        drop(clone);
+        let trie = SharedTrie::try_unwrap(shared).unwrap();
+        assert_eq!(trie.find_any("mark"), Some(&"v2"));
+    }
+
+    #[test]
+    fn test_persistent_trie() {
+        let mut trie_builder: PersistentTrieBuilder<u32> = PersistentTrieBuilder::default();
+        trie_builder
+            .add_all([("new york", 1), ("boston", 2)])
+            .unwrap();
+        let v1: PersistentTrie<u32, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let v2 = v1.insert("new jersey", 3).unwrap();
+        let v3 = v2.remove("new york").unwrap();
+
+        assert_eq!(v1.find_all("new york new jersey boston"), vec![&1, &2]);
+        assert_eq!(v2.find_all("new york new jersey boston"), vec![&1, &3, &2]);
+        assert_eq!(v3.find_all("new york new jersey boston"), vec![&3, &2]);
+        assert!(PersistentTrieNode::ptr_eq(
+            v1.root().get_child("boston").unwrap(),
+            v2.root().get_child("boston").unwrap()
+        ));
+        assert!(PersistentTrieNode::ptr_eq(
+            v2.root()
+                .get_child("new")
+                .unwrap()
+                .get_child("jersey")
+                .unwrap(),
+            v3.root()
+                .get_child("new")
+                .unwrap()
+                .get_child("jersey")
+                .unwrap()
+        ));
+        let with_gap = v3.insert("new **{0,2} city", 4).unwrap();
+        assert_eq!(with_gap.find_all("new big apple city"), vec![&4]);
+        let without_gap = with_gap.remove("new **{0,2} city").unwrap();
+        assert!(without_gap.find_all("new big apple city").is_empty());
+        assert!(v3.remove("new york").is_none());
+        assert_eq!(v3.remove("new jersey").unwrap().root().len_recursive(), 1);
+        assert!(matches!(
+            v1.insert("new **{0,2}", 4),
+            Err(Error::MisplacedGap { .. })
+        ));
+    }
+
+    #[test]
+    fn test_content_hash() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("mister bobby", "v1").unwrap();
+        trie_builder.add("mark", "v2").unwrap();
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+
+        let mut trie_builder: OrderedTrieBuilder<&str> = OrderedTrieBuilder::default();
+        trie_builder.add("mark", "v2").unwrap();
+        trie_builder.add("mister bobby", "v1").unwrap();
+        let other: OrderedTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert_eq!(other.content_hash(), trie.content_hash());
+
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("mister bobby", "v3").unwrap();
+        trie_builder.add("mark", "v2").unwrap();
+        let other: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert_ne!(other.content_hash(), trie.content_hash());
+
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("mister", "v1").unwrap();
+        trie_builder.add("bobby mark", "v2").unwrap();
+        let other: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert_ne!(other.content_hash(), trie.content_hash());
+    }
+
+    #[test]
+    fn test_string_matcher_matches_entire() {
+        let mut trie_builder: StringMatcherBuilder = Default::default();
+        trie_builder.add("bobby", true).unwrap();
+        trie_builder.add("mister bobby", true).unwrap();
+        trie_builder.add("mister mark", false).unwrap();
+        let trie: StringMatcher = trie_builder.build_default().unwrap();
+        assert!(trie.matches_entire("bobby"));
+        assert!(trie.matches_entire("Mister Bobby"));
+        assert!(trie.has_match("the mister bobby"));
+        assert!(!trie.matches_entire("the mister bobby"));
+        assert!(!trie.matches_entire("mister bobby the"));
+        assert!(!trie.matches_entire("mister"));
+        assert!(!trie.matches_entire("mister mark"));
+        assert_eq!(trie.get_exact_tokens(&["mister", "mark"]), Some(&false));
+    }
+
+    #[test]
+    fn test_string_trie_replay_diff() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("bobby", "name").unwrap();
+        trie_builder.add("mark", "name").unwrap();
+        let old: StringTrie<&str> = trie_builder.build_default().unwrap();
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("bobby", "name").unwrap();
+        trie_builder.add("mister", "title").unwrap();
+        let new: StringTrie<&str> = trie_builder.build_default().unwrap();
+        let diffs = StringTrie::replay_diff(
+            &old,
+            &new,
+            ["bobby", "mister bobby", "mark and mark", "nothing"],
+        );
+        assert_eq!(diffs.len(), 2);
+        assert_eq!(diffs[0].index, 1);
+        assert_eq!(diffs[0].added, vec![&"title"]);
+        assert!(diffs[0].removed.is_empty());
+        assert_eq!(diffs[1].index, 2);
+        assert!(diffs[1].added.is_empty());
+        assert_eq!(diffs[1].removed, vec![&"name", &"name"]);
+    }
+
+    #[test]
+    fn test_replay_diff_tokenizers() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("users", "users").unwrap();
+        let old: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("users", "users").unwrap();
+        let new: StringTrie<&str, PathTokenizer> = trie_builder.build_default().unwrap();
+        let diffs = StringTrie::replay_diff(&old, &new, ["users", "/api/users"]);
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].index, 1);
+        assert_eq!(diffs[0].added, vec![&"users"]);
+        let diffs = StringTrie::replay_diff(&new, &old, ["/api/users"]);
+        assert_eq!(diffs[0].removed, vec![&"users"]);
+    }
+
+    #[test]
+    fn test_string_trie_find_prefixed() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("git commit", "commit").unwrap();
+        trie_builder.add("git commit amend", "amend").unwrap();
+        trie_builder.add("git push", "push").unwrap();
+        trie_builder.add("cargo build", "build").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        let mut found = trie.find_prefixed("git");
+        found.sort();
+        assert_eq!(found, vec![&"amend", &"commit", &"push"]);
+        let mut found = trie.find_prefixed("git commit");
+        found.sort();
+        assert_eq!(found, vec![&"amend", &"commit"]);
+        assert!(trie.find_prefixed("commit").is_empty());
+        assert_eq!(trie.find_prefixed("").len(), 4);
+    }
+
+    #[test]
+    fn test_string_trie_find_with() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("bobby", "v1").unwrap();
+        trie_builder.add("mister bobby", "v2").unwrap();
+        trie_builder.add("mister", "v3").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        let options = FindOptions::default();
+        assert_eq!(
+            trie.find_with("mister bobby", &options),
+            vec![&"v3", &"v2", &"v1"]
+        );
+        let options = FindOptions::default().with_max_results(2);
+        assert_eq!(trie.find_with("mister bobby", &options), vec![&"v3", &"v2"]);
+        let options = FindOptions::default().with_overlap(OverlapPolicy::LeftmostLongest);
+        assert_eq!(trie.find_with("mister bobby", &options), vec![&"v2"]);
+        let options = FindOptions::default().with_skip_leading_tokens(false);
+        assert_eq!(trie.find_with("bobby mister", &options), vec![&"v1"]);
+        let options = FindOptions::default().with_anchor_end(true);
+        assert_eq!(trie.find_with("mister bobby", &options), vec![&"v2", &"v1"]);
+        let options = FindOptions::default().anchored();
+        assert_eq!(trie.find_with("mister bobby", &options), vec![&"v2"]);
+        assert!(trie.find_with("the mister bobby", &options).is_empty());
+    }
+
+    #[test]
+    fn test_auto_trie() {
+        let mut trie_builder: AutoTrieBuilder<usize> = Default::default();
+        for i in 0..100 {
+            trie_builder.add(format!("key{i}"), i).unwrap();
+        }
+        trie_builder.add("mister bobby", 100).unwrap();
+        trie_builder.add("mister mark", 101).unwrap();
+        for i in 0..10 {
+            trie_builder.add(format!("many key{i}"), 102 + i).unwrap();
+        }
+        let trie: AutoTrie<usize, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.num_keys(), 112);
+        assert_eq!(trie.find_all("key42 key99"), vec![&42, &99]);
+        assert_eq!(trie.find_all("mister mark"), vec![&101]);
+        assert_eq!(trie.find_all("many key7"), vec![&109, &7]);
+        assert!(trie.find_all("key100").is_empty());
+        // The root has enough children to be hashed, but they still come out in order.
+        assert!(trie.keys().is_sorted());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_auto_trie_with_patterns() {
+        let mut trie_builder: AutoTrieBuilder<&str> = Default::default();
+        trie_builder.add("status re:[0-9]+", "status").unwrap();
+        trie_builder.add("status ok", "ok").unwrap();
+        trie_builder.add("status a.b", "literal").unwrap();
+        trie_builder.add("c++ tips", "c++").unwrap();
+        let trie: AutoTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.find_all("status 503"), vec![&"status"]);
+        assert_eq!(trie.find_all("status ok"), vec![&"ok"]);
+        assert!(trie.find_all("status bad").is_empty());
+        // Literal keys next to patterns are still matched exactly.
+        assert_eq!(trie.find_all("status a.b"), vec![&"literal"]);
+        assert!(trie.find_all("status axb").is_empty());
+        assert_eq!(trie.find_all("c++ tips"), vec![&"c++"]);
+        assert!(trie.find_all("cc tips").is_empty());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_auto_trie_case_sensitive() {
+        let tokenizer = WhitespaceTokenizer::new().with_case_sensitive(true);
+        let mut trie_builder: AutoTrieBuilder<&str, CustomWhitespaceTokenizer> =
+            ImmutableTrieBuilder::new(
+                tokenizer,
+                AutoTrieNodeBuilder::default().with_case_sensitive(true),
+            );
+        trie_builder.add("Status re:[A-Z]+", "status").unwrap();
+        trie_builder.add("OK", "ok").unwrap();
+        let trie: AutoTrie<&str, CustomWhitespaceTokenizer> =
+            trie_builder.build(tokenizer).unwrap();
+        assert_eq!(trie.find_all("Status FAILED"), vec![&"status"]);
+        assert!(trie.find_all("status FAILED").is_empty());
+        assert!(trie.find_all("Status failed").is_empty());
+        assert_eq!(trie.find_all("OK ok"), vec![&"ok"]);
+    }
+
+    #[test]
+    fn test_small_key_trie() {
+        let mut trie_builder: SmallKeyTrieBuilder<&str, 3> = Default::default();
+        trie_builder.add("bobby", "v1").unwrap();
+        trie_builder.add("mister bobby", "v2").unwrap();
+        trie_builder.add("mister bobby jr", "v3").unwrap();
+        trie_builder.add("mister", "v4").unwrap();
+        trie_builder.add("bobby", "v5").unwrap();
+        assert!(matches!(
+            trie_builder.add("mister bobby jr jr", "v6"),
+            Err(Error::KeyTooLong { max: 3 })
+        ));
+        let trie: SmallKeyTrie<&str, 3> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.num_keys(), 4);
+        assert_eq!(
+            trie.find_all("hi mister bobby jr"),
+            vec![&"v4", &"v2", &"v3", &"v5"]
+        );
+        assert_eq!(trie.find_any("mister bobby"), Some(&"v4"));
+        assert_eq!(trie.get_exact("mister bobby jr"), Some(&"v3"));
+        assert!(trie.find_all("jr").is_empty());
+    }
+
+    #[test]
+    fn test_regex_trie_conflict_1() {
+        let mut trie_builder: RegexTrieBuilder<&str> = Default::default();
+        trie_builder
+            .add("(a|the|slumber|pool) party", "val 1")
+            .unwrap();
+        trie_builder.add("pool", "val 2").unwrap();
+        let trie: RegexTrie<&str> = trie_builder.build_default().unwrap();
+        let results = trie.find_unique("pool");
+        assert_eq!(results.len(), 1);
+        assert!(results.contains(&"val 2"));
+        let results = trie.find_unique("a pool");
+        assert_eq!(results.len(), 1);
+        assert!(results.contains(&"val 2"));
+        let results = trie.find_unique("a party");
+        assert_eq!(results.len(), 1);
+        assert!(results.contains(&"val 1"));
+    }
+
+    //FIXME
+    //#[test]
+    //fn test_regex_matcher_add_value() {
+    //    let mut builder = RegexMatcherBuilder::default();
+    //    builder.add("Test Name", true).unwrap();
+    //    let matcher: RegexMatcher<BoundaryTokenizer> = builder.build_default().unwrap();
+    //    assert_eq!(matcher.root.children.len(), 1);
+    //    let first_node = &matcher.root.children[0];
+    //    assert_eq!(first_node.key.as_str(), "^test$");
+    //    assert_eq!(first_node.children.len(), 1);
+    //    assert!(first_node.value.is_none());
+    //    let maybe_second_node = first_node.children.get("name");
+    //    assert!(maybe_second_node.is_some());
+    //    let second_node = maybe_second_node.unwrap();
+    //    assert_eq!(second_node.key.as_str(), "^name$");
+    //    assert_eq!(second_node.children.len(), 0);
+    //    assert!(matches!(second_node.value, Some(true)));
+    //}
+
+    #[test]
+    fn test_string_trie_find() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("test value", "v1").unwrap();
+        trie_builder.add("another test value", "v2").unwrap();
+        trie_builder.add("something else", "v3").unwrap();
+        trie_builder.add("another something else", "v3").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        let all = trie.find_all("this is a test value");
+        assert_eq!(all, vec![&"v1"]);
+        let mut all = trie.find_all("this is a another test value");
+        all.sort();
+        assert_eq!(all, vec![&"v1", &"v2"]);
+        let all = trie.find_all("another something else");
+        assert_eq!(all, vec![&"v3", &"v3"]);
+        let all = trie.find_all("nothing");
+        assert!(all.is_empty());
+        let all = trie.find_all("");
+        assert!(all.is_empty());
+    }
+
+    #[test]
+    fn test_string_trie_find_iter() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("test value", "v1").unwrap();
+        trie_builder.add("another test value", "v2").unwrap();
+        trie_builder.add("another", "v3").unwrap();
+        trie_builder.add("value", "v4").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        for search_str in ["this is another test value", "value value", "nothing", ""] {
+            let found: Vec<_> = trie.find_iter(search_str).collect();
+            assert_eq!(found, trie.find_all(search_str));
+        }
+        assert_eq!(trie.find_iter("another test value").next(), Some(&"v3"));
+    }
+
+    #[test]
+    fn test_string_trie_find_until() {
+        let mut trie_builder: StringTrieBuilder<&str> = StringTrieBuilder::default();
+        trie_builder.add("bobby", "name").unwrap();
+        trie_builder.add("mister", "title").unwrap();
+        trie_builder.add("crash", "error").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        let mut seen = Vec::new();
+        let found = trie.find_until("mister bobby crash bobby", |value| {
+            seen.push(*value);
+            if *value == "name" {
+                ControlFlow::Break(*value)
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert_eq!(found, Some("name"));
+        assert_eq!(seen, vec!["title", "name"]);
+        let found: Option<()> = trie.find_until("mister", |_| ControlFlow::Continue(()));
+        assert!(found.is_none());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_string_trie_find_all_batch() {
+        let mut trie_builder: StringTrieBuilder<usize> = Default::default();
+        trie_builder.add("one", 1).unwrap();
+        trie_builder.add("two", 2).unwrap();
+        let trie: StringTrie<usize, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let documents: Vec<String> = (0..100)
+            .map(|i| if i % 2 == 0 { "one two" } else { "two" }.to_string())
+            .collect();
+        let found = trie.find_all_batch(&documents);
+        assert_eq!(found.len(), documents.len());
+        for (document, found) in documents.iter().zip(found) {
+            assert_eq!(found, trie.find_all(document));
+        }
+    }
+
+    #[test]
+    fn test_string_trie_find_in_reader() {
+        let mut trie_builder: StringTrieBuilder<&str> = Default::default();
+        trie_builder.add("timed out", "timeout").unwrap();
+        trie_builder.add("refused", "refused").unwrap();
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let log = "connection refused\nok\n\nrequest timed\nout\nrequest timed out refused";
+        let found: Vec<_> = trie
+            .find_in_reader(log.as_bytes())
+            .map(|found| found.map(|found| (found.line, found.tokens, *found.value)))
+            .collect::<std::io::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            found,
+            vec![
+                (1, 1..2, "refused"),
+                (6, 1..3, "timeout"),
+                (6, 3..4, "refused"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_in_reader_stops_at_error() {
+        let mut trie_builder: StringTrieBuilder<&str> = Default::default();
+        trie_builder.add("refused", "refused").unwrap();
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let log: &[u8] = b"refused\n\xff\nrefused\n";
+        let mut found = trie.find_in_reader(log);
+        assert_eq!(found.next().unwrap().unwrap().line, 1);
+        assert!(found.next().unwrap().is_err());
+        assert!(found.next().is_none());
+    }
+
+    #[test]
+    fn test_string_trie_stream_matcher() {
+        let mut trie_builder: StringTrieBuilder<&str> = Default::default();
+        trie_builder.add("out of memory", "oom").unwrap();
+        trie_builder.add("memory", "mem").unwrap();
+        trie_builder.add("out of disk", "ood").unwrap();
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let mut matcher = trie.stream_matcher();
+        assert!(matcher.push_token("out").is_empty());
+        assert!(matcher.push_token("of").is_empty());
+        assert_eq!(matcher.num_active(), 1);
+        assert_eq!(matcher.push_token("memory"), vec![&"oom", &"mem"]);
+        assert_eq!(matcher.num_active(), 0);
+        assert!(matcher.push_token("out").is_empty());
+        matcher.reset();
+        assert!(matcher.push_token("of").is_empty());
+        assert_eq!(
+            matcher.push_str("out of disk", &WhitespaceTokenizer::new()),
+            vec![&"ood"]
+        );
+    }
+
+    #[test]
+    fn test_string_matcher_split_on_matches() {
+        let mut matcher_builder: StringMatcherBuilder = Default::default();
+        matcher_builder.add("request id", true).unwrap();
+        matcher_builder.add("took", true).unwrap();
+        let matcher: StringMatcher<WhitespaceTokenizer> = matcher_builder.build_default().unwrap();
+        assert_eq!(
+            matcher.split_on_matches("GET /users request id 42 took 13ms"),
+            vec!["GET /users ", " 42 ", " 13ms"]
+        );
+        assert_eq!(matcher.split_on_matches("took"), vec!["", ""]);
+        assert_eq!(matcher.split_on_matches("no matches"), vec!["no matches"]);
+    }
+
+    #[test]
+    fn test_string_trie_annotate() {
+        let mut trie_builder: StringTrieBuilder<&str> = Default::default();
+        trie_builder.add("ada lovelace", "person").unwrap();
+        trie_builder.add("london", "place").unwrap();
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let search_str = "Ada Lovelace was born in London";
+        let segments = trie.annotate(search_str);
+        assert_eq!(
+            segments
+                .iter()
+                .map(|segment| (segment.text(), segment.value()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Ada Lovelace", Some(&"person")),
+                (" was born in ", None),
+                ("London", Some(&"place")),
+            ]
+        );
+        assert_eq!(
+            segments.iter().map(Segment::text).collect::<String>(),
+            search_str
+        );
+        assert_eq!(trie.annotate(""), vec![]);
+    }
+
+    #[test]
+    fn test_string_matcher_redact() {
+        let mut matcher_builder: StringMatcherBuilder = Default::default();
+        matcher_builder.add("john smith", true).unwrap();
+        matcher_builder.add("smith jr", true).unwrap();
+        matcher_builder.add("555-1234", true).unwrap();
+        let matcher: StringMatcher<WhitespaceTokenizer> = matcher_builder.build_default().unwrap();
+        assert_eq!(
+            matcher.redact("Call John  Smith Jr at 555-1234 today", '*'),
+            "Call ************** at ******** today"
+        );
+        assert_eq!(matcher.redact("Jöhn Smith", '#'), "Jöhn Smith");
+    }
+
+    #[test]
+    fn test_string_trie_replace_all() {
+        let mut trie_builder: StringTrieBuilder<&str> = Default::default();
+        trie_builder.add("darn", "d**n").unwrap();
+        trie_builder.add("heck", "h**k").unwrap();
+        trie_builder.add("what the heck", "wth").unwrap();
+        let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert_eq!(
+            trie.replace_all("Oh  Darn  what the  heck", |m| m.value.to_string()),
+            "Oh  d**n  wth"
+        );
+        assert_eq!(
+            trie.replace_all("heck", |m| format!("[{}]", m.tokens.len())),
+            "[1]"
+        );
+        assert_eq!(
+            trie.replace_all("nothing here", |_| unreachable!()),
+            "nothing here"
+        );
+    }
+
+    #[test]
+    fn test_string_trie_compile_query() {
+        let mut trie_builder: StringTrieBuilder<&str> = Default::default();
+        trie_builder.add("error", "v1").unwrap();
+        trie_builder.add("disk error", "v2").unwrap();
+        trie_builder.add("error in", "v3").unwrap();
+        trie_builder.add("in kernel", "v4").unwrap();
+        trie_builder.add("disk", "v5").unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        let query = trie.compile_query("{} error in {}");
+        assert_eq!(query.num_fields(), 2);
+        assert_eq!(
+            query.find_all(&["disk", "kernel"]).unwrap(),
+            trie.find_all("disk error in kernel")
+        );
+        assert_eq!(
+            query.find_all(&["the", "user space"]).unwrap(),
+            vec![&"v1", &"v3"]
+        );
+        assert!(matches!(
+            query.find_all(&["disk"]),
+            Err(Error::FieldCountMismatch {
+                expected: 2,
+                found: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn test_string_trie_duplicate_policy() {
+        let build = |policy| -> Result<Vec<u32>> {
+            let mut trie_builder: StringTrieBuilder<u32> =
+                StringTrieBuilder::default().with_duplicate_policy(policy);
+            trie_builder.add("key", 1)?;
+            trie_builder.add("other", 2)?;
+            trie_builder.add_tokens(["key"], 3)?;
+            let trie: StringTrie<u32> = trie_builder.build_default()?;
+            Ok(trie.find_all("key other").into_iter().copied().collect())
+        };
+        assert_eq!(build(DuplicatePolicy::Overwrite).unwrap(), vec![3, 2]);
+        assert_eq!(build(DuplicatePolicy::KeepFirst).unwrap(), vec![1, 2]);
+        assert_eq!(
+            build(DuplicatePolicy::Merge(|a, b| a + b)).unwrap(),
+            vec![4, 2]
+        );
+        assert!(matches!(
+            build(DuplicatePolicy::Error),
+            Err(Error::DuplicateKey { key }) if key == "key"
+        ));
+    }
+
+    #[test]
+    fn test_rejected_duplicate_keeps_value() {
+        let mut trie_builder: StringTrieBuilder<u32> =
+            StringTrieBuilder::default().with_duplicate_policy(DuplicatePolicy::Error);
+        trie_builder.add("key", 1).unwrap();
+        trie_builder.add("", 0).unwrap();
+        assert!(trie_builder.add("key", 2).is_err());
+        assert!(trie_builder.add("", 2).is_err());
+        let trie: StringTrie<u32> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.get_exact("key"), Some(&1));
+        assert_eq!(trie.num_keys(), 2);
+
+        let mut trie_builder: SmallKeyTrieBuilder<u32> =
+            SmallKeyTrieBuilder::default().with_duplicate_policy(DuplicatePolicy::Error);
+        trie_builder.add("new york", 1).unwrap();
+        assert!(trie_builder.add("new york", 2).is_err());
+        let trie: SmallKeyTrie<u32> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.get_exact("new york"), Some(&1));
+    }
+
+    #[test]
+    fn test_string_trie_add_or_merge() {
+        let mut trie_builder: StringTrieBuilder<HashSet<&str>> =
+            StringTrieBuilder::default().with_duplicate_policy(DuplicatePolicy::Error);
+        for (key, tag) in [
+            ("new york", "city"),
+            ("new york", "state"),
+            ("york", "city"),
+        ] {
+            trie_builder
+                .add_or_merge(key, HashSet::from([tag]), |mut tags, new| {
+                    tags.extend(new);
+                    tags
+                })
+                .unwrap();
+        }
+        let trie: StringTrie<HashSet<&str>> = trie_builder.build_default().unwrap();
+        assert_eq!(
+            trie.get_exact("new york"),
+            Some(&HashSet::from(["city", "state"]))
+        );
+        assert_eq!(trie.get_exact("york"), Some(&HashSet::from(["city"])));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex_set_trie_find_all_captures() {
+        let mut trie_builder: ImmutableTrieBuilder<RegexSetTrieNodeBuilder<&str>, &str> =
+            Default::default();
+        trie_builder.add(r"order #(?<id>[0-9]+)", "order").unwrap();
+        trie_builder
+            .add(r"(?<amount>[0-9]+)(?<currency>usd|eur)", "price")
+            .unwrap();
+        let trie: ImmutableTrie<_, _, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let found = trie.find_all_captures("Order #1234 cost 25EUR").unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].value, &"order");
+        assert_eq!(found[0].tokens, 0..2);
+        assert_eq!(found[0].get("id"), Some("1234"));
+        assert!(found[0].groups[0].is_empty());
+        assert_eq!(found[1].value, &"price");
+        assert_eq!(found[1].get("amount"), Some("25"));
+        assert_eq!(found[1].get("currency"), Some("eur"));
+        assert_eq!(found[1].get("id"), None);
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_auto_trie_find_all_captures() {
+        let mut trie_builder: AutoTrieBuilder<&str> = Default::default();
+        trie_builder.add("order re:(?<id>[0-9]+)", "order").unwrap();
+        let trie: AutoTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let found = trie.find_all_captures("order 42").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].tokens, 0..2);
+        assert!(found[0].groups[0].is_empty());
+        assert_eq!(found[0].get("id"), Some("42"));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex_set_trie_find_all_captures_unanchored() {
+        let mut trie_builder: ImmutableTrieBuilder<RegexSetTrieNodeBuilder<&str>, &str> =
+            ImmutableTrieBuilder::new(
+                WhitespaceTokenizer::new(),
+                RegexSetTrieNodeBuilder::default().with_anchoring(Anchoring::None),
+            );
+        trie_builder.add("(?<n>[0-9]+)", "number").unwrap();
+        let trie: ImmutableTrie<_, _, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let found = trie.find_all_captures("abc123").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].get("n"), Some("123"));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex_set_trie_find_all_captures_case_sensitive() {
+        let tokenizer = WhitespaceTokenizer::new().with_case_sensitive(true);
+        let mut trie_builder: ImmutableTrieBuilder<
+            RegexSetTrieNodeBuilder<&str>,
+            &str,
+            CustomWhitespaceTokenizer,
+        > = ImmutableTrieBuilder::new(
+            tokenizer,
+            RegexSetTrieNodeBuilder::default().with_case_sensitive(true),
+        );
+        trie_builder
+            .add("(?<lower>[a-z]*)(?<upper>[A-Z]+)", "mixed")
+            .unwrap();
+        let trie: ImmutableTrie<_, _, CustomWhitespaceTokenizer> =
+            trie_builder.build(tokenizer).unwrap();
+        let found = trie.find_all_captures("abcDEF").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].get("lower"), Some("abc"));
+        assert_eq!(found[0].get("upper"), Some("DEF"));
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex_set_trie_find_all_captures_with_max_gap() {
+        let mut trie_builder: ImmutableTrieBuilder<RegexSetTrieNodeBuilder<&str>, &str> =
+            Default::default();
+        trie_builder.add(r"order #(?<id>[0-9]+)", "order").unwrap();
+        let trie: ImmutableTrie<_, _, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let trie = trie.with_max_gap(1);
+        let found = trie.find_all_captures("order number #1234").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].tokens, 0..3);
+        assert_eq!(found[0].groups.len(), 3);
+        assert_eq!(found[0].get("id"), Some("1234"));
+    }
+
+    #[test]
+    fn test_counting_trie() {
+        let mut trie_builder: CountingTrieBuilder = Default::default();
+        for phrase in ["new york", "york", "new york", "boston", "new york"] {
+            trie_builder.increment(phrase).unwrap();
+        }
+        trie_builder.increment_by("boston", 10).unwrap();
+        let trie: CountingTrie<WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.frequency("new york"), 3);
+        assert_eq!(trie.frequency("boston"), 11);
+        assert_eq!(trie.frequency("chicago"), 0);
+        assert_eq!(trie.total_frequency(), 15);
+        assert_eq!(
+            trie.term_frequencies("from new york to boston"),
+            HashMap::from([
+                (String::from("new york"), 3),
+                (String::from("york"), 1),
+                (String::from("boston"), 11),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_string_trie_score() {
+        let mut trie_builder: StringTrieBuilder<f64> = Default::default();
+        trie_builder.add("rust", 1.0).unwrap();
+        trie_builder.add("rust compiler", 2.0).unwrap();
+        trie_builder.add("borrow checker", 3.0).unwrap();
+        let trie: StringTrie<f64, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+        let search_str = "the rust compiler has a borrow checker";
+        assert_eq!(trie.score(search_str, |m| *m.value), 6.0);
+        assert_eq!(
+            trie.score(search_str, |m| m.value * m.tokens.len() as f64),
+            11.0
+        );
+        assert_eq!(trie.score(search_str, |m| m.span.len() as f64), 31.0);
+        assert_eq!(trie.score("nothing", |_| 1.0), 0.0);
+    }
+
+    #[test]
+    fn test_string_trie_find_best() {
+        let mut trie_builder: StringTrieBuilder<Weighted<&str>> = Default::default();
+        trie_builder.add_weighted("apple", "fruit", 1).unwrap();
+        trie_builder.add_weighted("apple", "company", 2).unwrap();
+        trie_builder
+            .add_weighted("apple pie", "dessert", 2)
+            .unwrap();
+        trie_builder.add_weighted("pie", "dessert", 2).unwrap();
+        trie_builder.add_weighted("cherry", "fruit", 1).unwrap();
+        let trie: StringTrie<Weighted<&str>> = trie_builder.build_default().unwrap();
+        assert_eq!(
+            trie.find_best("apple pie"),
+            Some(&Weighted::new("dessert", 2))
+        );
+        assert_eq!(
+            trie.find_best("apple cherry"),
+            Some(&Weighted::new("company", 2))
+        );
+        assert_eq!(trie.find_best("banana"), None);
+    }
+
+    #[test]
+    fn test_string_trie_multiple_values() {
+        let mut trie_builder: StringTrieBuilder<Vec<u32>> = Default::default();
+        trie_builder.add_value("heart attack", 1).unwrap();
+        trie_builder.add_value("heart attack", 2).unwrap();
+        trie_builder.add_value("attack", 3).unwrap();
+        trie_builder.add("stroke", vec![4, 5]).unwrap();
+        trie_builder.add_value("stroke", 6).unwrap();
+        let trie: StringTrie<Vec<u32>> = trie_builder.build_default().unwrap();
+        assert_eq!(
+            trie.find_all("heart attack and stroke"),
+            vec![&vec![1, 2], &vec![3], &vec![4, 5, 6]]
+        );
+    }
+
+    #[test]
+    fn test_small_key_trie_multiple_values() {
+        let mut trie_builder: SmallKeyTrieBuilder<Vec<u32>> = Default::default();
+        trie_builder.add_value("a b", 1).unwrap();
+        trie_builder.add_value("a", 2).unwrap();
+        trie_builder.add_value("a b", 3).unwrap();
+        trie_builder.add("a", vec![4]).unwrap();
+        let trie: SmallKeyTrie<Vec<u32>> = trie_builder.build_default().unwrap();
+        assert_eq!(trie.find_all("a b"), vec![&vec![4], &vec![1, 3]]);
+    }
+
+    #[test]
+    fn test_string_trie_find_first_n() {
+        let mut trie_builder: StringTrieBuilder<usize> = Default::default();
+        trie_builder.add("a", 1).unwrap();
+        trie_builder.add("a a", 2).unwrap();
+        trie_builder.add("a a a", 3).unwrap();
+        let trie: StringTrie<usize> = trie_builder.build_default().unwrap();
+        let search_str = vec!["a"; 1000].join(" ");
+        assert_eq!(trie.find_first_n(&search_str, 4), vec![&1, &2, &3, &1]);
+        assert!(trie.find_first_n(&search_str, 0).is_empty());
+        assert_eq!(trie.find_first_n("a a", 10), trie.find_all("a a"));
+    }
+
+    #[test]
+    fn test_string_trie_pruning_hints() {
+        let mut trie_builder: StringTrieBuilder<&str> =
+            StringTrieBuilder::default().with_pruning_hints();
+        trie_builder.add("bobby", "v1").unwrap();
+        trie_builder.add("mister bobby", "v2").unwrap();
+        trie_builder
+            .add_tokens(["mister", "mark", "jr"], "v3")
+            .unwrap();
+        let trie: StringTrie<&str> = trie_builder.build_default().unwrap();
+        let hints = trie.hints.as_ref().unwrap();
+        assert!(hints.may_match(&["bobby"]));
+        assert!(hints.may_match(&["mister", "mark"]));
+        assert!(!hints.may_match(&["mister", "the"]));
+        assert!(!hints.may_match(&["mister"]));
+        assert!(!hints.may_match(&["mark"]));
+        assert_eq!(trie.find_all("the mister bobby"), vec![&"v2", &"v1"]);
+        assert_eq!(trie.find_all("mister mark jr"), vec![&"v3"]);
+        assert_eq!(trie.find_any("mister the bobby"), Some(&"v1"));
+        assert!(trie.find_all("mister the mark").is_empty());
+        assert_eq!(
+            trie.find_iter("the mister bobby").collect::<Vec<_>>(),
+            vec![&"v2", &"v1"]
+        );
     }
 
     #[test]
@@ -0,0 +1,325 @@
+use super::{
+    Anchoring, ChildHasher, ImmutableTrieNode, ImmutableTrieNodeBuilder, KeyToken, gap_of,
+    merge_value, same_entries,
+};
+use crate::{Error, Result, error::pattern_compile, sealed::Sealed};
+#[cfg(feature = "regex")]
+use regex::{RegexBuilder, RegexSet, RegexSetBuilder};
+use std::{
+    collections::{BTreeMap, HashMap},
+    ops::RangeInclusive,
+};
+
+/// The prefix that marks a token as a regex pattern in an [`AutoTrieNode`], such as
+/// `re:[0-9]+`. Every other token is matched literally.
+pub const PATTERN_PREFIX: &str = "re:";
+
+/// Gets the regex of a [pattern token](PATTERN_PREFIX), such as `[0-9]+` for `re:[0-9]+`.
+pub fn pattern_of(token: &str) -> Option<&str> {
+    token
+        .strip_prefix(PATTERN_PREFIX)
+        .filter(|pattern| !pattern.is_empty())
+}
+
+/// Nodes with at most this many children scan them linearly.
+const INLINE_MAX_CHILDREN: usize = 4;
+/// Nodes with at most this many children binary search them. Anything larger uses a hash map.
+const SORTED_MAX_CHILDREN: usize = 32;
+
+#[derive(Clone, Debug)]
+enum AutoChildren<V> {
+    Inline(Box<[(KeyToken, AutoTrieNode<V>)]>),
+    Sorted(Box<[(KeyToken, AutoTrieNode<V>)]>),
+    Hashed(HashMap<KeyToken, AutoTrieNode<V>, ChildHasher>),
+}
+
+/// The children whose keys are [pattern tokens](PATTERN_PREFIX), compiled into a single set.
+#[cfg(feature = "regex")]
+#[derive(Clone, Debug)]
+struct PatternChildren<V> {
+    set: RegexSet,
+    /// The keys the patterns were compiled from, as they were added.
+    keys: Box<[KeyToken]>,
+    children: Box<[AutoTrieNode<V>]>,
+    case_sensitive: bool,
+}
+
+/// A trie node that picks how to store its children when it's built, based on how many children
+/// it has and whether any of their keys are regex patterns:
+///
+/// - A few literal keys are scanned linearly.
+/// - A moderate number of literal keys are binary searched.
+/// - Many literal keys are put in a hash map.
+/// - Keys that start with [`re:`](PATTERN_PREFIX) are compiled into a single
+///   [`RegexSet`](regex::RegexSet), if the `regex` feature is enabled. Without it, they're
+///   matched literally like every other key.
+///
+/// Literal keys are always compared exactly, even if they contain regex syntax and there are
+/// pattern keys next to them. [Gaps](super::gap_of) are kept separately from the other children.
+#[derive(Clone, Debug)]
+pub struct AutoTrieNode<V> {
+    value: Option<V>,
+    children: AutoChildren<V>,
+    #[cfg(feature = "regex")]
+    patterns: Option<PatternChildren<V>>,
+    gaps: Box<[GapChild<V>]>,
+}
+
+/// A child that's reached by skipping a range of tokens, along with its gap token.
+type GapChild<V> = (KeyToken, RangeInclusive<usize>, AutoTrieNode<V>);
+
+impl<V> Default for AutoTrieNode<V> {
+    fn default() -> Self {
+        Self {
+            value: None,
+            children: AutoChildren::Inline(Box::default()),
+            #[cfg(feature = "regex")]
+            patterns: None,
+            gaps: Box::default(),
+        }
+    }
+}
+
+impl<V: PartialEq> PartialEq for AutoTrieNode<V> {
+    fn eq(&self, other: &Self) -> bool {
+        same_entries(self, other)
+    }
+}
+
+impl<V: Eq> Eq for AutoTrieNode<V> {}
+
+impl<V> Sealed for AutoTrieNode<V> {}
+
+impl<V> ImmutableTrieNode<V> for AutoTrieNode<V> {
+    fn value(&self) -> Option<&V> {
+        self.value.as_ref()
+    }
+
+    fn get_children<S: AsRef<str>>(&self, token: S) -> Vec<&Self> {
+        let token = token.as_ref();
+        let literal = match &self.children {
+            AutoChildren::Inline(children) => children
+                .iter()
+                .find(|(key, _)| **key == *token)
+                .map(|(_, child)| child),
+            AutoChildren::Sorted(children) => children
+                .binary_search_by(|(key, _)| str::cmp(key, token))
+                .ok()
+                .map(|idx| &children[idx].1),
+            AutoChildren::Hashed(children) => children.get(token),
+        };
+        let found = literal.into_iter();
+        #[cfg(feature = "regex")]
+        let found = found.chain(self.patterns.iter().flat_map(|patterns| {
+            patterns
+                .set
+                .matches(token)
+                .into_iter()
+                .map(|idx| &patterns.children[idx])
+        }));
+        found.collect()
+    }
+
+    fn children(&self) -> Vec<(&str, &Self)> {
+        let gaps = self
+            .gaps
+            .iter()
+            .map(|(key, _, child)| (key.as_ref(), child));
+        let children: Vec<_> = match &self.children {
+            AutoChildren::Inline(children) | AutoChildren::Sorted(children) => children
+                .iter()
+                .map(|(key, child)| (key.as_ref(), child))
+                .collect(),
+            AutoChildren::Hashed(children) => {
+                // Hash map order changes from run to run, so it's sorted to keep walks and
+                // serialized tries the same for the same keys.
+                let mut children: Vec<_> = children
+                    .iter()
+                    .map(|(key, child)| (key.as_ref(), child))
+                    .collect();
+                children.sort_unstable_by_key(|(key, _)| *key);
+                children
+            }
+        };
+        let children = children.into_iter();
+        #[cfg(feature = "regex")]
+        let children = children.chain(self.patterns.iter().flat_map(|patterns| {
+            patterns
+                .keys
+                .iter()
+                .map(|key| key.as_ref())
+                .zip(patterns.children.iter())
+        }));
+        children.chain(gaps).collect()
+    }
+
+    #[cfg(feature = "regex")]
+    fn key_pattern(&self, key: &str) -> Option<(&str, bool)> {
+        let patterns = self.patterns.as_ref()?;
+        let idx = patterns
+            .keys
+            .iter()
+            .position(|other| other.as_ref() == key)?;
+        Some((&patterns.set.patterns()[idx], patterns.case_sensitive))
+    }
+
+    fn gaps(&self) -> impl DoubleEndedIterator<Item = (RangeInclusive<usize>, &Self)> {
+        self.gaps
+            .iter()
+            .map(|(_, skips, child)| (skips.clone(), child))
+    }
+
+    fn len(&self) -> usize {
+        let len = match &self.children {
+            AutoChildren::Inline(children) | AutoChildren::Sorted(children) => children.len(),
+            AutoChildren::Hashed(children) => children.len(),
+        };
+        #[cfg(feature = "regex")]
+        let len = len + self.patterns.as_ref().map_or(0, |p| p.children.len());
+        len + self.gaps.len()
+    }
+
+    fn len_recursive(&self) -> usize {
+        self.len()
+            + self
+                .children()
+                .into_iter()
+                .map(|(_, n)| n.len_recursive())
+                .sum::<usize>()
+    }
+}
+
+pub struct AutoTrieNodeBuilder<V> {
+    value: Option<V>,
+    children: BTreeMap<String, AutoTrieNodeBuilder<V>>,
+    case_sensitive: bool,
+    anchoring: Anchoring,
+    parameters: bool,
+}
+
+impl<V> AutoTrieNodeBuilder<V> {
+    /// Sets whether keys that are compiled into regexes are matched case-sensitively. Other
+    /// keys are always compared exactly. This is applied to every descendant when the node is
+    /// built.
+    #[inline]
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Sets how keys that are compiled into regexes are anchored. By default, they have to match
+    /// the whole token. This is applied to every descendant when the node is built.
+    #[inline]
+    pub fn with_anchoring(mut self, anchoring: Anchoring) -> Self {
+        self.anchoring = anchoring;
+        self
+    }
+}
+
+impl<V> Default for AutoTrieNodeBuilder<V> {
+    fn default() -> Self {
+        Self {
+            value: None,
+            children: BTreeMap::new(),
+            case_sensitive: false,
+            anchoring: Anchoring::default(),
+            parameters: false,
+        }
+    }
+}
+
+impl<V> Sealed for AutoTrieNodeBuilder<V> {}
+
+impl<V> ImmutableTrieNodeBuilder<V> for AutoTrieNodeBuilder<V> {
+    type Node = AutoTrieNode<V>;
+
+    fn add_with<S, I, F>(&mut self, mut items_iter: I, value: V, on_duplicate: F) -> Result<()>
+    where
+        S: AsRef<str>,
+        I: Iterator<Item = S>,
+        F: FnOnce(V, V) -> Result<V, (V, Error)>,
+    {
+        let key = if let Some(part) = items_iter.next() {
+            String::from(part.as_ref())
+        } else {
+            return merge_value(&mut self.value, value, on_duplicate);
+        };
+        self.children
+            .entry(key)
+            .or_default()
+            .add_with(items_iter, value, on_duplicate)
+    }
+
+    #[inline]
+    fn enable_parameters(&mut self) {
+        self.parameters = true;
+    }
+
+    /// Compiles [pattern tokens](PATTERN_PREFIX) on their own, since a [`RegexSet`] doesn't say
+    /// which of its patterns is invalid.
+    #[cfg(feature = "regex")]
+    fn validate_token(&self, token: &str) -> Result<()> {
+        if let Some(pattern) = pattern_of(token) {
+            RegexBuilder::new(&self.anchoring.anchor(pattern))
+                .unicode(true)
+                .case_insensitive(!self.case_sensitive)
+                .build()
+                .map_err(pattern_compile(pattern))?;
+        }
+        Ok(())
+    }
+
+    fn build(self) -> Result<Self::Node> {
+        let mut children = Vec::with_capacity(self.children.len());
+        #[cfg(feature = "regex")]
+        let mut pattern_children = Vec::new();
+        let mut gaps = Vec::new();
+        for (key, mut child) in self.children {
+            child.case_sensitive = self.case_sensitive;
+            child.anchoring = self.anchoring.clone();
+            child.parameters = self.parameters;
+            if let Some(skips) = gap_of(&key, self.parameters) {
+                gaps.push((KeyToken::from(key), skips, child.build()?));
+                continue;
+            }
+            #[cfg(feature = "regex")]
+            if pattern_of(&key).is_some() {
+                pattern_children.push((KeyToken::from(key), child.build()?));
+                continue;
+            }
+            children.push((KeyToken::from(key), child.build()?));
+        }
+        #[cfg(feature = "regex")]
+        let patterns = if pattern_children.is_empty() {
+            None
+        } else {
+            let (keys, children): (Vec<_>, Vec<_>) = pattern_children.into_iter().unzip();
+            let sources = keys.iter().filter_map(|key| pattern_of(key));
+            let set = RegexSetBuilder::new(self.anchoring.anchor_all(sources)?)
+                .unicode(true)
+                .case_insensitive(!self.case_sensitive)
+                .build()?;
+            Some(PatternChildren {
+                set,
+                keys: keys.into_boxed_slice(),
+                children: children.into_boxed_slice(),
+                case_sensitive: self.case_sensitive,
+            })
+        };
+        // The children came out of a BTreeMap, so they're already sorted.
+        let children = if children.len() <= INLINE_MAX_CHILDREN {
+            AutoChildren::Inline(children.into_boxed_slice())
+        } else if children.len() <= SORTED_MAX_CHILDREN {
+            AutoChildren::Sorted(children.into_boxed_slice())
+        } else {
+            AutoChildren::Hashed(children.into_iter().collect())
+        };
+        Ok(AutoTrieNode {
+            value: self.value,
+            children,
+            #[cfg(feature = "regex")]
+            patterns,
+            gaps: gaps.into_boxed_slice(),
+        })
+    }
+}
@@ -1,34 +1,249 @@
-use crate::Result;
+use crate::{Error, Result, error::BuildConflictSnafu, sealed::Sealed};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    iter, mem,
+    ops::RangeInclusive,
+    ptr,
+};
 
+mod auto;
+mod ordered;
+mod persistent;
 #[cfg(feature = ("regex"))]
 mod regex;
 #[cfg(feature = ("regex-filtered"))]
 mod regex_filtered;
+mod small;
+mod string;
 
+pub use auto::*;
+pub use ordered::*;
+pub use persistent::*;
 #[cfg(feature = ("regex"))]
 pub use regex::*;
 #[cfg(feature = ("regex-filtered"))]
 pub use regex_filtered::*;
+pub use small::*;
+pub use string::*;
 
-pub trait ImmutableTrieNodeBuilder<V> {
+/// How nodes store the tokens of their children's keys. With the `compact_str` feature, tokens
+/// of up to 24 bytes are stored inline instead of in their own allocation.
+#[cfg(not(feature = "compact_str"))]
+pub(crate) type KeyToken = Box<str>;
+#[cfg(feature = "compact_str")]
+pub(crate) type KeyToken = compact_str::CompactString;
+
+/// The hasher used by nodes that keep their children in hash maps. With the `ahash` feature,
+/// this is [aHash](ahash::RandomState), which is much faster than the default SipHash for short
+/// tokens.
+#[cfg(not(feature = "ahash"))]
+pub(crate) type ChildHasher = std::hash::RandomState;
+#[cfg(feature = "ahash")]
+pub(crate) type ChildHasher = ahash::RandomState;
+
+/// Builds an [`ImmutableTrieNode`] out of keys that have already been tokenized.
+///
+/// This is sealed, so it's only implemented by the node builders in this crate. Searches are
+/// customized with a [`Tokenizer`](crate::Tokenizer) and [`TokenStage`](crate::TokenStage)s
+/// instead, which are meant to be implemented outside of it.
+pub trait ImmutableTrieNodeBuilder<V>: Sealed {
     type Node: ImmutableTrieNode<V>;
 
+    /// Adds a key, replacing its value if it already has one.
+    #[inline]
     fn add<S, I>(&mut self, items_iter: I, value: V) -> Result<()>
     where
         S: AsRef<str>,
-        I: Iterator<Item = S>;
+        I: Iterator<Item = S>,
+    {
+        self.add_with(items_iter, value, |_, new| Ok(new))
+    }
+
+    /// Adds a key. If the key already has a value, `on_duplicate` is called with the existing
+    /// value and the new one, and whatever it returns is stored instead. If `on_duplicate` fails,
+    /// it hands the existing value back along with the error, and the key keeps that value.
+    fn add_with<S, I, F>(&mut self, items_iter: I, value: V, on_duplicate: F) -> Result<()>
+    where
+        S: AsRef<str>,
+        I: Iterator<Item = S>,
+        F: FnOnce(V, V) -> Result<V, (V, Error)>;
+
+    /// Reserves room for at least `additional` more keys, so that loading many keys doesn't
+    /// repeatedly grow the builder's maps. Builders that don't keep their children in hash maps
+    /// ignore this.
+    #[inline]
+    fn reserve(&mut self, additional: usize) {}
+
+    /// Makes tokens like `:id` [parameters](param_name) instead of literal tokens. This is applied
+    /// to every descendant when the node is built. Builders whose nodes don't support gaps
+    /// ignore this.
+    #[inline]
+    fn enable_parameters(&mut self) {}
+
+    /// Checks that a token can be part of a key. The trie builders call this for every token of
+    /// a key before adding it, so that an invalid pattern can be traced back to the key it's in.
+    #[inline]
+    fn validate_token(&self, token: &str) -> Result<()> {
+        Ok(())
+    }
 
     fn build(self) -> Result<Self::Node>;
 }
 
-pub trait ImmutableTrieNode<V> {
+/// Stores `value` in `slot`, using `on_duplicate` to combine it with the value that's already
+/// there, if any. If `on_duplicate` fails, the value that was already there is put back.
+#[inline]
+pub(crate) fn merge_value<V, F>(slot: &mut Option<V>, value: V, on_duplicate: F) -> Result<()>
+where
+    F: FnOnce(V, V) -> Result<V, (V, Error)>,
+{
+    let (value, result) = match slot.take() {
+        Some(existing) => match on_duplicate(existing, value) {
+            Ok(merged) => (merged, Ok(())),
+            Err((existing, err)) => (existing, Err(err)),
+        },
+        None => (value, Ok(())),
+    };
+    *slot = Some(value);
+    result
+}
+
+/// Checks if two nodes have the same keys with the same values, no matter how either of them
+/// stores its children. This is what the nodes' `PartialEq` implementations use.
+pub(crate) fn same_entries<V, L, R>(left: &L, right: &R) -> bool
+where
+    V: PartialEq,
+    L: ImmutableTrieNode<V>,
+    R: ImmutableTrieNode<V>,
+{
+    let (mut left_entries, mut right_entries) = (Vec::new(), Vec::new());
+    collect_entries(left, &mut Vec::new(), &mut left_entries);
+    collect_entries(right, &mut Vec::new(), &mut right_entries);
+    left_entries.sort_unstable_by(|(left, _), (right, _)| left.cmp(right));
+    right_entries.sort_unstable_by(|(left, _), (right, _)| left.cmp(right));
+    left_entries == right_entries
+}
+
+/// Collects every key stored under `node` along with its value. Each key is `path` followed by
+/// the tokens (or patterns) that lead to it, including the [tails](ImmutableTrieNode::tail) of
+/// the nodes along the way.
+pub(crate) fn collect_entries<'a, V, N>(
+    node: &'a N,
+    path: &mut Vec<&'a str>,
+    entries: &mut Vec<(Vec<&'a str>, &'a V)>,
+) where
+    N: ImmutableTrieNode<V>,
+{
+    if let Some(value) = node.value() {
+        entries.push((path.clone(), value));
+    }
+    for (key, child) in node.children() {
+        let len = path.len();
+        path.push(key);
+        path.extend(child.tail().iter().map(|token| token.as_ref()));
+        collect_entries(child, path, entries);
+        path.truncate(len);
+    }
+}
+
+/// Strips a child's [tail](ImmutableTrieNode::tail) off the front of `tokens`, which are the
+/// tokens after the one the child was reached with. Returns `None` if they don't start with it.
+pub(crate) fn strip_tail<'t, V, N, S>(child: &N, tokens: &'t [S]) -> Option<&'t [S]>
+where
+    N: ImmutableTrieNode<V> + ?Sized,
+    S: AsRef<str>,
+{
+    let tail = child.tail();
+    let matches = tokens.len() >= tail.len()
+        && tail
+            .iter()
+            .zip(tokens)
+            .all(|(key, token)| key.as_ref() == token.as_ref());
+    matches.then(|| &tokens[tail.len()..])
+}
+
+/// A node of an [`ImmutableTrie`](crate::immutable::ImmutableTrie).
+///
+/// Like [`ImmutableTrieNodeBuilder`], this is sealed, so that methods can be added to it without
+/// breaking anything downstream.
+pub trait ImmutableTrieNode<V>: Sealed {
     fn value(&self) -> Option<&V>;
 
     fn get_children<S: AsRef<str>>(&self, token: S) -> Vec<&Self>;
 
+    /// Gets all of this node's children along with the key (or pattern) that was used to add
+    /// them.
+    fn children(&self) -> Vec<(&str, &Self)>;
+
+    /// The tokens that have to come right after the one this node is reached with before its
+    /// value and children apply. Nodes that merge chains of nodes with a single child and no
+    /// value into one node, such as [`StringTrieNode`], keep the keys of the rest of the chain
+    /// here; the [`children`](Self::children) are only keyed by the chain's first token. Children
+    /// that are [gaps](Self::gaps) never have a tail.
+    #[inline]
+    fn tail(&self) -> &[impl AsRef<str>] {
+        &[] as &[&str]
+    }
+
+    /// Gets the children that are reached by skipping over some number of tokens, along with the
+    /// range of how many tokens can be skipped. These are added with [gap tokens](parse_gap)
+    /// such as `**{0,3}`, and are also included in [`children`](Self::children). Nodes that
+    /// don't support gaps treat gap tokens like any other key.
+    fn gaps(&self) -> impl DoubleEndedIterator<Item = (RangeInclusive<usize>, &Self)> {
+        std::iter::empty()
+    }
+
+    /// Like [`get_children`](Self::get_children), but also returns the key (or pattern) of each
+    /// child.
+    fn get_keyed_children<S: AsRef<str>>(&self, token: S) -> Vec<(&str, &Self)> {
+        let children = self.children();
+        self.get_children(token)
+            .into_iter()
+            .filter_map(|child| {
+                children
+                    .iter()
+                    .find(|(_, other)| ptr::eq(*other, child))
+                    .copied()
+            })
+            .collect()
+    }
+
+    /// Gets the pattern that the child with the given key was compiled to, after it was
+    /// [anchored](Anchoring), along with whether it's case sensitive. This is `None` for keys
+    /// that are matched literally.
+    #[inline]
+    fn key_pattern(&self, key: &str) -> Option<(&str, bool)> {
+        None
+    }
+
     fn len(&self) -> usize;
     fn len_recursive(&self) -> usize;
 
+    /// Estimates the number of bytes used by this node and all of its descendants. The default
+    /// implementation only accounts for the nodes themselves and their keys.
+    fn approx_memory_bytes(&self) -> usize
+    where
+        Self: Sized,
+    {
+        mem::size_of::<Self>()
+            + self
+                .children()
+                .into_iter()
+                .map(|(key, child)| key.len() + child.approx_memory_bytes())
+                .sum::<usize>()
+    }
+
+    /// The number of values stored in this node and all of its descendants.
+    fn num_values(&self) -> usize {
+        usize::from(self.value().is_some())
+            + self
+                .children()
+                .into_iter()
+                .map(|(_, child)| child.num_values())
+                .sum::<usize>()
+    }
+
     #[inline]
     fn is_empty(&self) -> bool {
         self.len() == 0
@@ -40,30 +255,488 @@ pub trait ImmutableTrieNode<V> {
     }
 
     fn get_any<S: AsRef<str>>(&self, tokens: &[S]) -> Option<&V> {
-        let mut child = self;
-        for token in tokens {
-            if let Some(value) = child.value() {
-                return Some(value);
-            //FIXME should use get children?
-            } else if let Some(next_child) = child.get_child(token) {
-                child = next_child;
-            } else {
-                return None;
-            }
+        if let Some(value) = self.value() {
+            return Some(value);
+        }
+        //FIXME should use get children?
+        let found = tokens.split_first().and_then(|(token, rest)| {
+            let child = self.get_child(token)?;
+            child.get_any(strip_tail(child, rest)?)
+        });
+        found.or_else(|| {
+            self.gaps().find_map(|(skips, child)| {
+                skips
+                    .take_while(|skip| *skip <= tokens.len())
+                    .find_map(|skip| child.get_any(&tokens[skip..]))
+            })
+        })
+    }
+
+    /// Gets the value stored under exactly the given tokens. Unlike [`get_any`](Self::get_any),
+    /// every token must be consumed and only the value of the final node is returned.
+    fn get_exact<S: AsRef<str>>(&self, tokens: &[S]) -> Option<&V> {
+        let found = match tokens.split_first() {
+            Some((token, rest)) => self
+                .get_children(token)
+                .into_iter()
+                .find_map(|child| child.get_exact(strip_tail(child, rest)?)),
+            None => self.value(),
+        };
+        found.or_else(|| {
+            self.gaps().find_map(|(skips, child)| {
+                skips
+                    .take_while(|skip| *skip <= tokens.len())
+                    .find_map(|skip| child.get_exact(&tokens[skip..]))
+            })
+        })
+    }
+
+    /// Gets the values stored in this node and all of its descendants.
+    fn values_recursive(&self) -> Vec<&V> {
+        let mut values: Vec<&V> = self.value().into_iter().collect();
+        for (_, child) in self.children() {
+            values.extend(child.values_recursive());
+        }
+        values
+    }
+
+    /// Gets the values of all keys that start with the given tokens.
+    fn get_prefixed<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<&V> {
+        match tokens.split_first() {
+            Some((token, rest)) => self
+                .get_children(token)
+                .into_iter()
+                .flat_map(|child| match strip_tail(child, rest) {
+                    Some(rest) => child.get_prefixed(rest),
+                    // The tokens run out partway through the child's tail.
+                    None if rest.len() < child.tail().len()
+                        && child
+                            .tail()
+                            .iter()
+                            .zip(rest)
+                            .all(|(key, token)| key.as_ref() == token.as_ref()) =>
+                    {
+                        child.values_recursive()
+                    }
+                    None => Vec::new(),
+                })
+                .collect(),
+            None => self.values_recursive(),
         }
-        child.value()
     }
 
     fn get_all<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<&V> {
+        self.get_all_with_depth(tokens)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect()
+    }
+
+    /// Gets the value of the deepest node that can be reached with the tokens, along with the
+    /// number of tokens consumed to reach it. Unlike [`get_any`](Self::get_any), this keeps going
+    /// past nodes with values in case a longer key also matches.
+    fn get_longest<S: AsRef<str>>(&self, tokens: &[S]) -> Option<(usize, &V)> {
+        self.get_all_with_depth(tokens)
+            .into_iter()
+            .reduce(|longest, next| if next.0 > longest.0 { next } else { longest })
+    }
+
+    /// Like [`get_all`](Self::get_all), but each value is paired with the keys (or patterns) of
+    /// the nodes that led to it.
+    fn get_all_with_keys<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<(Vec<&str>, &V)> {
+        let mut values = Vec::new();
+        if let Some(token) = tokens.first() {
+            for (key, child) in self.get_keyed_children(token) {
+                let Some(rest) = strip_tail(child, &tokens[1..]) else {
+                    continue;
+                };
+                let mut path = vec![key];
+                path.extend(child.tail().iter().map(|token| token.as_ref()));
+                if let Some(value) = child.value() {
+                    values.push((path.clone(), value))
+                }
+                values.extend(
+                    child
+                        .get_all_with_keys(rest)
+                        .into_iter()
+                        .map(|(keys, value)| {
+                            let mut keys_with_path = path.clone();
+                            keys_with_path.extend(keys);
+                            (keys_with_path, value)
+                        }),
+                );
+            }
+        }
+        // The gap's key is repeated for each skipped token so that there's still one key per
+        // token.
+        let mut gap_keys = self.gaps().peekable();
+        if gap_keys.peek().is_some() {
+            let children = self.children();
+            for (skips, child) in gap_keys {
+                let Some((key, _)) = children.iter().find(|(_, other)| ptr::eq(*other, child))
+                else {
+                    continue;
+                };
+                for skip in skips.take_while(|skip| *skip <= tokens.len()) {
+                    if let Some(value) = child.value().filter(|_| skip > 0) {
+                        values.push((vec![*key; skip], value));
+                    }
+                    values.extend(child.get_all_with_keys(&tokens[skip..]).into_iter().map(
+                        |(keys, value)| {
+                            let mut gap_keys = vec![*key; skip];
+                            gap_keys.extend(keys);
+                            (gap_keys, value)
+                        },
+                    ));
+                }
+            }
+        }
+        values
+    }
+
+    /// Like [`get_all_with_depth`](Self::get_all_with_depth), but up to `max_gap` unmatched tokens
+    /// can be skipped between each of a key's tokens. If a key can be matched in more than one
+    /// way, only the first match found is returned.
+    fn get_all_with_max_gap<S: AsRef<str>>(
+        &self,
+        tokens: &[S],
+        max_gap: usize,
+    ) -> Vec<(usize, &V)> {
+        let mut collector = MaxGapCollector::new(max_gap, false);
+        collector.collect(self, tokens, 0);
+        collector
+            .values
+            .into_iter()
+            .map(|(depth, _, value)| (depth, value))
+            .collect()
+    }
+
+    /// Like [`get_all_with_max_gap`](Self::get_all_with_max_gap), but each value is also paired
+    /// with the keys (or patterns) of the nodes that led to it, like with
+    /// [`get_all_with_keys`](Self::get_all_with_keys). The tokens that were skipped get empty
+    /// keys, so there's still one key for each matched token.
+    fn get_all_with_keys_and_max_gap<S: AsRef<str>>(
+        &self,
+        tokens: &[S],
+        max_gap: usize,
+    ) -> Vec<(usize, Vec<&str>, &V)> {
+        let mut collector = MaxGapCollector::new(max_gap, true);
+        collector.collect(self, tokens, 0);
+        collector.values
+    }
+
+    /// Like [`get_all`](Self::get_all), but each value is paired with the number of tokens that
+    /// were consumed to reach it.
+    fn get_all_with_depth<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<(usize, &V)> {
         let mut values = Vec::new();
         if let Some(token) = tokens.first() {
             for child in self.get_children(token) {
+                let Some(rest) = strip_tail(child, &tokens[1..]) else {
+                    continue;
+                };
+                let consumed = tokens.len() - rest.len();
                 if let Some(value) = child.value() {
-                    values.push(value)
+                    values.push((consumed, value))
+                }
+                values.extend(
+                    child
+                        .get_all_with_depth(rest)
+                        .into_iter()
+                        .map(|(depth, value)| (depth + consumed, value)),
+                );
+            }
+        }
+        for (skips, child) in self.gaps() {
+            for skip in skips.take_while(|skip| *skip <= tokens.len()) {
+                if let Some(value) = child.value().filter(|_| skip > 0) {
+                    values.push((skip, value));
                 }
-                values.extend(child.get_all(&tokens[1..]));
+                values.extend(
+                    child
+                        .get_all_with_depth(&tokens[skip..])
+                        .into_iter()
+                        .map(|(depth, value)| (depth + skip, value)),
+                );
             }
         }
         values
     }
 }
+
+/// Walks a node for [`get_all_with_max_gap`](ImmutableTrieNode::get_all_with_max_gap), keeping
+/// track of the keys that led to each node if `keyed` is set.
+struct MaxGapCollector<'a, V> {
+    max_gap: usize,
+    keyed: bool,
+    /// The keys of the nodes on the way to the current one.
+    path: Vec<&'a str>,
+    values: Vec<(usize, Vec<&'a str>, &'a V)>,
+    seen: HashSet<*const V>,
+}
+
+impl<'a, V> MaxGapCollector<'a, V> {
+    fn new(max_gap: usize, keyed: bool) -> Self {
+        Self {
+            max_gap,
+            keyed,
+            path: Vec::new(),
+            values: Vec::new(),
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Records the value of a node that was reached after `depth` tokens, unless it was already
+    /// reached some other way.
+    fn found<N>(&mut self, depth: usize, node: &'a N)
+    where
+        N: ImmutableTrieNode<V> + ?Sized,
+    {
+        if let Some(value) = node.value()
+            && self.seen.insert(ptr::from_ref(value))
+        {
+            self.values.push((depth, self.path.clone(), value));
+        }
+    }
+
+    fn collect<N, S>(&mut self, node: &'a N, tokens: &[S], depth: usize)
+    where
+        N: ImmutableTrieNode<V> + ?Sized,
+        S: AsRef<str>,
+    {
+        let Some(token) = tokens.first() else {
+            return;
+        };
+        let children = if self.keyed {
+            node.get_keyed_children(token)
+        } else {
+            node.get_children(token)
+                .into_iter()
+                .map(|child| ("", child))
+                .collect()
+        };
+        for (key, child) in children {
+            self.reached(key, child, tokens, depth);
+        }
+        let mut gaps = node.gaps().peekable();
+        if gaps.peek().is_none() {
+            return;
+        }
+        let children = if self.keyed {
+            node.children()
+        } else {
+            Vec::new()
+        };
+        for (skips, child) in gaps {
+            // Like with `get_all_with_keys`, the gap's key is repeated for each skipped token.
+            let key = children
+                .iter()
+                .find(|(_, other)| ptr::eq(*other, child))
+                .map_or("", |(key, _)| *key);
+            for skip in skips.take_while(|skip| *skip <= tokens.len()) {
+                let path_len = self.path.len();
+                if self.keyed {
+                    self.path.extend(iter::repeat_n(key, skip));
+                }
+                if skip > 0 {
+                    self.found(depth + skip, child);
+                }
+                // The gap stands in for a key token, so the max gap applies after it as well.
+                for slack in 0..=self.max_gap.min(tokens.len() - skip) {
+                    let skipped_len = self.path.len();
+                    if self.keyed {
+                        self.path.extend(iter::repeat_n("", slack));
+                    }
+                    self.collect(child, &tokens[skip + slack..], depth + skip + slack);
+                    self.path.truncate(skipped_len);
+                }
+                self.path.truncate(path_len);
+            }
+        }
+    }
+
+    /// Follows a `child` that was reached with the first of the tokens, which had `key`.
+    fn reached<N, S>(&mut self, key: &'a str, child: &'a N, tokens: &[S], depth: usize)
+    where
+        N: ImmutableTrieNode<V> + ?Sized,
+        S: AsRef<str>,
+    {
+        for (consumed, matched) in tail_ends_with_max_gap(child, &tokens[1..], self.max_gap) {
+            let path_len = self.path.len();
+            if self.keyed {
+                // The skipped tokens get empty keys, so that there's still one for each token.
+                let mut tail = child.tail().iter();
+                self.path.push(key);
+                self.path.extend((0..consumed).map(|pos| {
+                    if matched.contains(&pos) {
+                        tail.next().map_or("", |token| token.as_ref())
+                    } else {
+                        ""
+                    }
+                }));
+            }
+            let consumed = consumed + 1;
+            self.found(depth + consumed, child);
+            for skip in 0..=self.max_gap.min(tokens.len() - consumed) {
+                let skipped_len = self.path.len();
+                if self.keyed {
+                    self.path.extend(iter::repeat_n("", skip));
+                }
+                self.collect(child, &tokens[consumed + skip..], depth + consumed + skip);
+                self.path.truncate(skipped_len);
+            }
+            self.path.truncate(path_len);
+        }
+    }
+}
+
+/// Like [`ImmutableTrieNode::get_all_with_max_gap`], but starts from a `child` that was reached
+/// with the first of the tokens. The child's own value is included if its tail matches.
+pub(crate) fn get_all_from_child_with_max_gap<'a, N, V, S>(
+    child: &'a N,
+    tokens: &[S],
+    max_gap: usize,
+) -> Vec<&'a V>
+where
+    N: ImmutableTrieNode<V>,
+    S: AsRef<str>,
+{
+    let mut collector = MaxGapCollector::new(max_gap, false);
+    collector.reached("", child, tokens, 0);
+    collector
+        .values
+        .into_iter()
+        .map(|(_, _, value)| value)
+        .collect()
+}
+
+/// Finds every way a child's [tail](ImmutableTrieNode::tail) can be matched by `tokens` (the
+/// tokens after the one the child was reached with) when up to `max_gap` tokens can be skipped
+/// before each of the tail's tokens. Returns how many tokens each of them consumes, along with
+/// the positions of the tokens that matched the tail.
+fn tail_ends_with_max_gap<V, N, S>(
+    child: &N,
+    tokens: &[S],
+    max_gap: usize,
+) -> Vec<(usize, Vec<usize>)>
+where
+    N: ImmutableTrieNode<V> + ?Sized,
+    S: AsRef<str>,
+{
+    let mut ends = vec![(0, Vec::new())];
+    for key in child.tail() {
+        let mut next: Vec<(usize, Vec<usize>)> = ends
+            .into_iter()
+            .flat_map(|(end, matched)| (end..=end + max_gap).map(move |pos| (pos, matched.clone())))
+            .filter(|(pos, _)| tokens.get(*pos).is_some_and(|t| t.as_ref() == key.as_ref()))
+            .map(|(pos, mut matched)| {
+                matched.push(pos);
+                (pos + 1, matched)
+            })
+            .collect();
+        next.sort_by_key(|(end, _)| *end);
+        next.dedup_by_key(|(end, _)| *end);
+        ends = next;
+    }
+    ends
+}
+
+/// Parses a gap token, which matches any number of arbitrary tokens within a range. Gap tokens
+/// look like `**{min,max}`, such as `**{0,3}`. Gaps have to be between two other tokens, since a
+/// gap at the start or end of a key would never change what it matches.
+///
+/// [Parameters](param_name) aren't parsed as gaps here, since they're only gaps in tries that
+/// enable them.
+pub fn parse_gap(token: &str) -> Option<RangeInclusive<usize>> {
+    let (min, max) = token
+        .strip_prefix("**{")?
+        .strip_suffix('}')?
+        .split_once(',')?;
+    let (min, max) = (min.trim().parse().ok()?, max.trim().parse().ok()?);
+    (min <= max).then_some(min..=max)
+}
+
+/// Gets the name of a parameter token, such as `:id`. Parameters match any single token, and the
+/// text they match is returned with each [`Match`](crate::Match). Unlike other gaps, parameters
+/// can start or end a key.
+///
+/// Parameters have to be enabled with
+/// [`ImmutableTrieBuilder::with_parameters`](crate::ImmutableTrieBuilder::with_parameters).
+/// Otherwise, tokens like `:)` are matched literally.
+pub fn param_name(token: &str) -> Option<&str> {
+    token.strip_prefix(':').filter(|name| !name.is_empty())
+}
+
+/// Like [`parse_gap`], but if `parameters` is set, [parameters](param_name) are parsed as gaps of
+/// exactly one token.
+pub(crate) fn gap_of(token: &str, parameters: bool) -> Option<RangeInclusive<usize>> {
+    if parameters && param_name(token).is_some() {
+        Some(1..=1)
+    } else {
+        parse_gap(token)
+    }
+}
+
+/// How the regex node builders anchor each part of a key before compiling it. This is applied to
+/// every descendant when a node is built, like case sensitivity. The built nodes still report
+/// each key as it was added, not as the pattern it was compiled to.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum Anchoring {
+    /// Parts have to match the whole token (`^(?:part)$`), so every branch of an alternation is
+    /// anchored. A leading `^` and a trailing `$` that parts already have are dropped first, so
+    /// that `foo` and `^foo$` compile to the same pattern.
+    #[default]
+    Full,
+    /// Parts are compiled as-is, so they match if they match anywhere in the token.
+    None,
+    /// Parts have to match whole words within the token (`\b(?:part)\b`).
+    WordBoundary,
+    /// Parts are wrapped in the given prefix and suffix.
+    Custom {
+        prefix: Cow<'static, str>,
+        suffix: Cow<'static, str>,
+    },
+}
+
+impl Anchoring {
+    /// Anchors a key part, returning the pattern that's compiled.
+    pub fn anchor(&self, part: &str) -> String {
+        match self {
+            Self::Full => {
+                let part = part.strip_prefix('^').unwrap_or(part);
+                let part = match part.strip_suffix('$') {
+                    // The `$` is only an anchor if it isn't escaped, which it is if it follows an
+                    // odd number of backslashes.
+                    Some(rest) if (rest.len() - rest.trim_end_matches('\\').len()) % 2 == 0 => rest,
+                    _ => part,
+                };
+                format!("^(?:{part})$")
+            }
+            Self::None => part.to_owned(),
+            Self::WordBoundary => format!(r"\b(?:{part})\b"),
+            Self::Custom { prefix, suffix } => format!("{prefix}{part}{suffix}"),
+        }
+    }
+
+    /// Anchors each of a node's keys, failing if two of them are anchored to the same pattern,
+    /// such as `foo` and `^foo$`.
+    pub(crate) fn anchor_all<'k, I>(&self, keys: I) -> Result<Vec<String>>
+    where
+        I: IntoIterator<Item = &'k str>,
+    {
+        let mut sources: HashMap<String, &str> = HashMap::new();
+        keys.into_iter()
+            .map(|key| {
+                let pattern = self.anchor(key);
+                match sources.insert(pattern.clone(), key) {
+                    Some(first) => BuildConflictSnafu {
+                        first,
+                        second: key,
+                        pattern,
+                    }
+                    .fail(),
+                    None => Ok(pattern),
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,152 @@
+use super::{
+    ChildKey, ImmutableTrieNode, ImmutableTrieNodeBuilder, TokenInterner, gap_of, merge_value,
+    same_entries,
+};
+use crate::{Error, Result, sealed::Sealed};
+use std::{collections::BTreeMap, ops::RangeInclusive};
+
+/// Like a [`StringTrieNode`](super::StringTrieNode), but the children are kept in a
+/// [`BTreeMap`], so they're always visited in the same (sorted) order. This makes iteration,
+/// `Debug` output and the order of multiple matches deterministic across runs, at the cost of
+/// somewhat slower lookups.
+#[derive(Clone, Debug)]
+pub struct OrderedTrieNode<V> {
+    value: Option<V>,
+    children: BTreeMap<ChildKey, OrderedTrieNode<V>>,
+    /// The keys of the children that are [gaps](gap_of), found when the node is built.
+    gaps: Box<[(RangeInclusive<usize>, ChildKey)]>,
+}
+
+impl<V> Default for OrderedTrieNode<V> {
+    fn default() -> Self {
+        Self {
+            value: None,
+            children: BTreeMap::new(),
+            gaps: Box::default(),
+        }
+    }
+}
+
+impl<V> OrderedTrieNode<V> {
+    /// Finds the gaps among the children of this node and all of its descendants.
+    fn find_gaps(&mut self, parameters: bool) {
+        self.gaps = self
+            .children
+            .keys()
+            .filter_map(|key| gap_of(key, parameters).map(|skips| (skips, key.clone())))
+            .collect();
+        self.children
+            .values_mut()
+            .for_each(|child| child.find_gaps(parameters));
+    }
+}
+
+impl<V: PartialEq> PartialEq for OrderedTrieNode<V> {
+    fn eq(&self, other: &Self) -> bool {
+        same_entries(self, other)
+    }
+}
+
+impl<V: Eq> Eq for OrderedTrieNode<V> {}
+
+impl<V> Sealed for OrderedTrieNode<V> {}
+
+impl<V> ImmutableTrieNode<V> for OrderedTrieNode<V> {
+    #[inline]
+    fn value(&self) -> Option<&V> {
+        self.value.as_ref()
+    }
+
+    fn get_children<S: AsRef<str>>(&self, token: S) -> Vec<&Self> {
+        self.children.get(token.as_ref()).into_iter().collect()
+    }
+
+    #[inline]
+    fn get_child<S: AsRef<str>>(&self, token: S) -> Option<&Self> {
+        self.children.get(token.as_ref())
+    }
+
+    fn children(&self) -> Vec<(&str, &Self)> {
+        self.children
+            .iter()
+            .map(|(key, child)| (key.as_ref(), child))
+            .collect()
+    }
+
+    fn gaps(&self) -> impl DoubleEndedIterator<Item = (RangeInclusive<usize>, &Self)> {
+        self.gaps
+            .iter()
+            .map(|(skips, key)| (skips.clone(), &self.children[key]))
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    fn len_recursive(&self) -> usize {
+        self.len()
+            + self
+                .children
+                .values()
+                .map(|n| n.len_recursive())
+                .sum::<usize>()
+    }
+}
+
+/// Builds an [`OrderedTrieNode`], interning every token that's added.
+#[derive(Clone, Debug)]
+pub struct OrderedTrieNodeBuilder<V> {
+    root: OrderedTrieNode<V>,
+    interner: TokenInterner,
+    parameters: bool,
+}
+
+impl<V> Default for OrderedTrieNodeBuilder<V> {
+    fn default() -> Self {
+        Self {
+            root: OrderedTrieNode::default(),
+            interner: TokenInterner::default(),
+            parameters: false,
+        }
+    }
+}
+
+impl<V> Sealed for OrderedTrieNodeBuilder<V> {}
+
+impl<V> ImmutableTrieNodeBuilder<V> for OrderedTrieNodeBuilder<V> {
+    type Node = OrderedTrieNode<V>;
+
+    fn add_with<S, I, F>(&mut self, items_iter: I, value: V, on_duplicate: F) -> Result<()>
+    where
+        S: AsRef<str>,
+        I: Iterator<Item = S>,
+        F: FnOnce(V, V) -> Result<V, (V, Error)>,
+    {
+        let mut node = &mut self.root;
+        for part in items_iter {
+            node = node
+                .children
+                .entry(self.interner.intern(part.as_ref()))
+                .or_default();
+        }
+        merge_value(&mut node.value, value, on_duplicate)
+    }
+
+    /// Only the interner is reserved, since the children are kept in B-trees.
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        self.interner.reserve(additional);
+    }
+
+    #[inline]
+    fn enable_parameters(&mut self) {
+        self.parameters = true;
+    }
+
+    #[inline]
+    fn build(mut self) -> Result<Self::Node> {
+        self.root.find_gaps(self.parameters);
+        Ok(self.root)
+    }
+}
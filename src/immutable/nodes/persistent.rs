@@ -0,0 +1,289 @@
+use super::{
+    ChildKey, ImmutableTrieNode, ImmutableTrieNodeBuilder, TokenInterner, gap_of, merge_value,
+    same_entries,
+};
+use crate::{Error, Result, sealed::Sealed};
+use std::{collections::BTreeMap, ops::RangeInclusive};
+use triomphe::Arc;
+
+#[derive(Clone, Debug)]
+struct PersistentNodeData<V> {
+    value: Option<V>,
+    children: BTreeMap<ChildKey, PersistentTrieNode<V>>,
+    /// The keys of the children that are [gaps](gap_of), kept up to date as keys are
+    /// inserted and removed.
+    gaps: Box<[(RangeInclusive<usize>, ChildKey)]>,
+    /// Whether parameters count as gaps. New nodes inherit this from their parents.
+    parameters: bool,
+}
+
+impl<V> PersistentNodeData<V> {
+    fn new(
+        value: Option<V>,
+        children: BTreeMap<ChildKey, PersistentTrieNode<V>>,
+        parameters: bool,
+    ) -> Self {
+        let gaps = children
+            .keys()
+            .filter_map(|key| gap_of(key, parameters).map(|skips| (skips, key.clone())))
+            .collect();
+        Self {
+            value,
+            children,
+            gaps,
+            parameters,
+        }
+    }
+}
+
+/// A trie node that's shared instead of copied. Cloning a node is O(1), and
+/// [inserting](Self::insert) or [removing](Self::remove) a key returns a new node that only
+/// copies the nodes along that key's path, sharing every other subtree with the old one. This
+/// lets many versions of the same trie be kept around at once. Like an
+/// [`OrderedTrieNode`](super::OrderedTrieNode), the children are kept in sorted order.
+#[derive(Debug)]
+pub struct PersistentTrieNode<V> {
+    data: Arc<PersistentNodeData<V>>,
+}
+
+impl<V> Clone for PersistentTrieNode<V> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+        }
+    }
+}
+
+impl<V> Default for PersistentTrieNode<V> {
+    fn default() -> Self {
+        Self::from(PersistentNodeData::new(None, BTreeMap::new(), false))
+    }
+}
+
+impl<V> From<PersistentNodeData<V>> for PersistentTrieNode<V> {
+    #[inline]
+    fn from(data: PersistentNodeData<V>) -> Self {
+        Self {
+            data: Arc::new(data),
+        }
+    }
+}
+
+impl<V> PersistentTrieNode<V> {
+    /// Checks if two nodes are the same shared node, rather than just having the same contents.
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.data, &other.data)
+    }
+
+    /// Whether [parameters](super::param_name) were enabled when this node was built.
+    #[inline]
+    pub(crate) fn parameters(&self) -> bool {
+        self.data.parameters
+    }
+}
+
+impl<V: Clone> PersistentTrieNode<V> {
+    /// Creates a copy of this node with the key set to `value`, replacing its old value if it
+    /// had one.
+    pub fn insert<S: AsRef<str>>(&self, tokens: &[S], value: V) -> Self {
+        let mut data = PersistentNodeData::clone(&self.data);
+        match tokens.split_first() {
+            None => data.value = Some(value),
+            Some((first, rest)) => {
+                let (key, child) = match self.data.children.get_key_value(first.as_ref()) {
+                    Some((key, child)) => (key.clone(), child.insert(rest, value)),
+                    None => {
+                        let key = ChildKey::from(first.as_ref());
+                        if let Some(skips) = gap_of(&key, data.parameters) {
+                            let mut gaps = data.gaps.into_vec();
+                            gaps.push((skips, key.clone()));
+                            data.gaps = gaps.into_boxed_slice();
+                        }
+                        let child = Self::from(PersistentNodeData::new(
+                            None,
+                            BTreeMap::new(),
+                            data.parameters,
+                        ));
+                        (key, child.insert(rest, value))
+                    }
+                };
+                data.children.insert(key, child);
+            }
+        }
+        Self::from(data)
+    }
+
+    /// Creates a copy of this node without the key, dropping any nodes that are left without
+    /// values or children. Returns `None` if the key doesn't have a value.
+    pub fn remove<S: AsRef<str>>(&self, tokens: &[S]) -> Option<Self> {
+        let data = match tokens.split_first() {
+            None => {
+                self.data.value.as_ref()?;
+                let mut data = PersistentNodeData::clone(&self.data);
+                data.value = None;
+                data
+            }
+            Some((first, rest)) => {
+                let (key, child) = self.data.children.get_key_value(first.as_ref())?;
+                let child = child.remove(rest)?;
+                let mut data = PersistentNodeData::clone(&self.data);
+                if child.data.value.is_none() && child.data.children.is_empty() {
+                    data.children.remove(key);
+                    if data.gaps.iter().any(|(_, gap)| gap == key) {
+                        data.gaps = data
+                            .gaps
+                            .iter()
+                            .filter(|(_, gap)| gap != key)
+                            .cloned()
+                            .collect();
+                    }
+                } else {
+                    data.children.insert(key.clone(), child);
+                }
+                data
+            }
+        };
+        Some(Self::from(data))
+    }
+}
+
+impl<V: PartialEq> PartialEq for PersistentTrieNode<V> {
+    fn eq(&self, other: &Self) -> bool {
+        Self::ptr_eq(self, other) || same_entries(self, other)
+    }
+}
+
+impl<V: Eq> Eq for PersistentTrieNode<V> {}
+
+impl<V> Sealed for PersistentTrieNode<V> {}
+
+impl<V> ImmutableTrieNode<V> for PersistentTrieNode<V> {
+    #[inline]
+    fn value(&self) -> Option<&V> {
+        self.data.value.as_ref()
+    }
+
+    fn get_children<S: AsRef<str>>(&self, token: S) -> Vec<&Self> {
+        self.data.children.get(token.as_ref()).into_iter().collect()
+    }
+
+    #[inline]
+    fn get_child<S: AsRef<str>>(&self, token: S) -> Option<&Self> {
+        self.data.children.get(token.as_ref())
+    }
+
+    fn children(&self) -> Vec<(&str, &Self)> {
+        self.data
+            .children
+            .iter()
+            .map(|(key, child)| (key.as_ref(), child))
+            .collect()
+    }
+
+    fn gaps(&self) -> impl DoubleEndedIterator<Item = (RangeInclusive<usize>, &Self)> {
+        self.data
+            .gaps
+            .iter()
+            .map(|(skips, key)| (skips.clone(), &self.data.children[key]))
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.data.children.len()
+    }
+
+    fn len_recursive(&self) -> usize {
+        self.len()
+            + self
+                .data
+                .children
+                .values()
+                .map(|n| n.len_recursive())
+                .sum::<usize>()
+    }
+}
+
+/// A node that's still being built, whose children can still be added to.
+#[derive(Debug)]
+struct PendingNode<V> {
+    value: Option<V>,
+    children: BTreeMap<ChildKey, PendingNode<V>>,
+}
+
+impl<V> Default for PendingNode<V> {
+    fn default() -> Self {
+        Self {
+            value: None,
+            children: BTreeMap::new(),
+        }
+    }
+}
+
+impl<V> PendingNode<V> {
+    fn build(self, parameters: bool) -> PersistentTrieNode<V> {
+        let children = self
+            .children
+            .into_iter()
+            .map(|(key, child)| (key, child.build(parameters)))
+            .collect();
+        PersistentTrieNode::from(PersistentNodeData::new(self.value, children, parameters))
+    }
+}
+
+/// Builds a [`PersistentTrieNode`], interning every token that's added. Keys added to the built
+/// node aren't interned.
+#[derive(Debug)]
+pub struct PersistentTrieNodeBuilder<V> {
+    root: PendingNode<V>,
+    interner: TokenInterner,
+    parameters: bool,
+}
+
+impl<V> Default for PersistentTrieNodeBuilder<V> {
+    fn default() -> Self {
+        Self {
+            root: PendingNode::default(),
+            interner: TokenInterner::default(),
+            parameters: false,
+        }
+    }
+}
+
+impl<V> Sealed for PersistentTrieNodeBuilder<V> {}
+
+impl<V> ImmutableTrieNodeBuilder<V> for PersistentTrieNodeBuilder<V> {
+    type Node = PersistentTrieNode<V>;
+
+    fn add_with<S, I, F>(&mut self, items_iter: I, value: V, on_duplicate: F) -> Result<()>
+    where
+        S: AsRef<str>,
+        I: Iterator<Item = S>,
+        F: FnOnce(V, V) -> Result<V, (V, Error)>,
+    {
+        let mut node = &mut self.root;
+        for part in items_iter {
+            node = node
+                .children
+                .entry(self.interner.intern(part.as_ref()))
+                .or_default();
+        }
+        merge_value(&mut node.value, value, on_duplicate)
+    }
+
+    #[inline]
+    fn reserve(&mut self, additional: usize) {
+        self.interner.reserve(additional);
+    }
+
+    #[inline]
+    fn enable_parameters(&mut self) {
+        self.parameters = true;
+    }
+
+    #[inline]
+    fn build(self) -> Result<Self::Node> {
+        Ok(self.root.build(self.parameters))
+    }
+}
@@ -1,12 +1,15 @@
-use super::{ImmutableTrieNode, ImmutableTrieNodeBuilder};
-use crate::Result;
+use super::{
+    Anchoring, ImmutableTrieNode, ImmutableTrieNodeBuilder, KeyToken, merge_value, same_entries,
+};
+use crate::{Error, Result, error::pattern_compile, sealed::Sealed};
 use educe::Educe;
 use regex::{Regex, RegexBuilder, RegexSet, RegexSetBuilder};
 use std::{
     borrow::BorrowMut,
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     fmt,
     marker::PhantomData,
+    mem,
     ops::Deref,
 };
 use triomphe::Arc;
@@ -15,9 +18,22 @@ use triomphe::Arc;
 pub struct RegexSetTrieNode<V> {
     value: Option<V>,
     patterns: RegexSet,
+    /// The keys the patterns were compiled from, as they were added.
+    keys: Box<[KeyToken]>,
     children: Vec<Box<RegexSetTrieNode<V>>>,
+    case_sensitive: bool,
+}
+
+impl<V: PartialEq> PartialEq for RegexSetTrieNode<V> {
+    fn eq(&self, other: &Self) -> bool {
+        same_entries(self, other)
+    }
 }
 
+impl<V: Eq> Eq for RegexSetTrieNode<V> {}
+
+impl<V> Sealed for RegexSetTrieNode<V> {}
+
 impl<V> ImmutableTrieNode<V> for RegexSetTrieNode<V> {
     fn get_child<S: AsRef<str>>(&self, token: S) -> Option<&Self> {
         self.patterns
@@ -40,10 +56,54 @@ impl<V> ImmutableTrieNode<V> for RegexSetTrieNode<V> {
         }
     }
 
+    fn get_keyed_children<S: AsRef<str>>(&self, token: S) -> Vec<(&str, &Self)> {
+        let mut matches: Vec<_> = self.patterns.matches(token.as_ref()).iter().collect();
+        if matches.is_empty() {
+            Vec::default()
+        } else {
+            matches.sort();
+            matches
+                .into_iter()
+                .map(|idx| (self.keys[idx].as_ref(), self.children[idx].as_ref()))
+                .collect()
+        }
+    }
+
+    fn children(&self) -> Vec<(&str, &Self)> {
+        self.keys
+            .iter()
+            .map(|key| key.as_ref())
+            .zip(self.children.iter().map(|n| n.as_ref()))
+            .collect()
+    }
+
     fn value(&self) -> Option<&V> {
         self.value.as_ref()
     }
 
+    fn key_pattern(&self, key: &str) -> Option<(&str, bool)> {
+        let idx = self.keys.iter().position(|other| other.as_ref() == key)?;
+        Some((&self.patterns.patterns()[idx], self.case_sensitive))
+    }
+
+    /// The regex crate doesn't expose how much memory a compiled [`RegexSet`] uses, so the
+    /// pattern sources are used as a lower bound for it.
+    fn approx_memory_bytes(&self) -> usize {
+        mem::size_of::<Self>()
+            + self.children.capacity() * mem::size_of::<Box<Self>>()
+            + self
+                .patterns
+                .patterns()
+                .iter()
+                .map(|p| mem::size_of::<String>() + p.len())
+                .sum::<usize>()
+            + self
+                .children
+                .iter()
+                .map(|n| n.approx_memory_bytes())
+                .sum::<usize>()
+    }
+
     #[inline]
     fn len(&self) -> usize {
         self.children.len()
@@ -61,31 +121,57 @@ impl<V> ImmutableTrieNode<V> for RegexSetTrieNode<V> {
 
 pub struct RegexSetTrieNodeBuilder<V> {
     value: Option<V>,
-    children: HashMap<String, Box<RegexSetTrieNodeBuilder<V>>>,
+    /// Kept sorted so that the built node's patterns are always in the same order, regardless
+    /// of the order keys were added in.
+    children: BTreeMap<String, Box<RegexSetTrieNodeBuilder<V>>>,
+    case_sensitive: bool,
+    anchoring: Anchoring,
+}
+
+impl<V> RegexSetTrieNodeBuilder<V> {
+    /// Sets whether patterns are matched case-sensitively. This is applied to every descendant
+    /// when the node is built.
+    #[inline]
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Sets how each key part is anchored before it's compiled. By default, parts have to match
+    /// the whole token. This is applied to every descendant when the node is built.
+    #[inline]
+    pub fn with_anchoring(mut self, anchoring: Anchoring) -> Self {
+        self.anchoring = anchoring;
+        self
+    }
 }
 
 impl<V> Default for RegexSetTrieNodeBuilder<V> {
     fn default() -> Self {
         Self {
             value: None,
-            children: HashMap::new(),
+            children: BTreeMap::new(),
+            case_sensitive: false,
+            anchoring: Anchoring::default(),
         }
     }
 }
 
+impl<V> Sealed for RegexSetTrieNodeBuilder<V> {}
+
 impl<V> ImmutableTrieNodeBuilder<V> for RegexSetTrieNodeBuilder<V> {
     type Node = RegexSetTrieNode<V>;
 
-    fn add<S, I>(&mut self, mut items_iter: I, value: V) -> Result<()>
+    fn add_with<S, I, F>(&mut self, mut items_iter: I, value: V, on_duplicate: F) -> Result<()>
     where
         S: AsRef<str>,
         I: Iterator<Item = S>,
+        F: FnOnce(V, V) -> Result<V, (V, Error)>,
     {
         let pattern = if let Some(part) = items_iter.next() {
-            format!("^{}$", part.as_ref())
+            String::from(part.as_ref())
         } else {
-            self.value = Some(value);
-            return Ok(());
+            return merge_value(&mut self.value, value, on_duplicate);
         };
         if !self.children.contains_key(&pattern) {
             let child = Self::default();
@@ -94,25 +180,46 @@ impl<V> ImmutableTrieNodeBuilder<V> for RegexSetTrieNodeBuilder<V> {
         self.children
             .get_mut(&pattern)
             .unwrap()
-            .add(items_iter, value)
+            .add_with(items_iter, value, on_duplicate)
+    }
+
+    /// Compiles the token on its own, since a [`RegexSet`] doesn't say which of its patterns is
+    /// invalid.
+    fn validate_token(&self, token: &str) -> Result<()> {
+        RegexBuilder::new(&self.anchoring.anchor(token))
+            .unicode(true)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+            .map_err(pattern_compile(token))?;
+        Ok(())
     }
 
     fn build(self) -> Result<Self::Node> {
         let mut children = Vec::with_capacity(self.children.len());
-        let mut patterns = Vec::with_capacity(self.children.len());
-        for (pattern, child) in self.children.into_iter() {
-            patterns.push(pattern);
+        let patterns = self
+            .anchoring
+            .anchor_all(self.children.keys().map(String::as_str))?;
+        let keys = self
+            .children
+            .keys()
+            .map(|key| KeyToken::from(key.as_str()))
+            .collect();
+        for mut child in self.children.into_values() {
+            child.case_sensitive = self.case_sensitive;
+            child.anchoring = self.anchoring.clone();
             let child = child.build()?;
             children.push(Box::new(child));
         }
         let regexes = RegexSetBuilder::new(patterns)
             .unicode(true)
-            .case_insensitive(true)
+            .case_insensitive(!self.case_sensitive)
             .build()?;
         Ok(RegexSetTrieNode {
             value: self.value,
             patterns: regexes,
+            keys,
             children,
+            case_sensitive: self.case_sensitive,
         })
     }
 }
@@ -1,12 +1,15 @@
-use super::{ImmutableTrieNode, ImmutableTrieNodeBuilder};
-use crate::Result;
+use super::{
+    Anchoring, ImmutableTrieNode, ImmutableTrieNodeBuilder, KeyToken, merge_value, same_entries,
+};
+use crate::{Error, Result, error::pattern_compile, sealed::Sealed};
 use educe::Educe;
 use regex_filtered::{Builder as RegexesBuilder, Options as RegexesOptions, Regexes};
 use std::{
     borrow::BorrowMut,
-    collections::{BTreeSet, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     fmt,
     marker::PhantomData,
+    mem,
     ops::Deref,
 };
 use triomphe::Arc;
@@ -15,16 +18,17 @@ use triomphe::Arc;
 pub struct RegexFilteredTrieNode<V> {
     value: Option<V>,
     patterns: Arc<Regexes>,
+    /// The keys the patterns were compiled from, as they were added.
+    keys: Box<[KeyToken]>,
     pub(crate) children: Vec<Box<RegexFilteredTrieNode<V>>>,
 }
 
 impl<V: fmt::Debug> fmt::Debug for RegexFilteredTrieNode<V> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let children: HashMap<&str, &Box<Self>> = self
-            .patterns
-            .regexes()
+        let children: BTreeMap<&str, &Box<Self>> = self
+            .keys
             .iter()
-            .map(|r| r.as_str())
+            .map(|key| key.as_ref())
             .zip(&self.children)
             .collect();
         f.debug_struct("RegexFilteredTrieNode")
@@ -34,6 +38,16 @@ impl<V: fmt::Debug> fmt::Debug for RegexFilteredTrieNode<V> {
     }
 }
 
+impl<V: PartialEq> PartialEq for RegexFilteredTrieNode<V> {
+    fn eq(&self, other: &Self) -> bool {
+        same_entries(self, other)
+    }
+}
+
+impl<V: Eq> Eq for RegexFilteredTrieNode<V> {}
+
+impl<V> Sealed for RegexFilteredTrieNode<V> {}
+
 impl<V> ImmutableTrieNode<V> for RegexFilteredTrieNode<V> {
     fn get_children<S: AsRef<str>>(&self, token: S) -> Vec<&Self> {
         let mut matches: Vec<_> = self.patterns.matching(token.as_ref()).collect();
@@ -48,10 +62,50 @@ impl<V> ImmutableTrieNode<V> for RegexFilteredTrieNode<V> {
         }
     }
 
+    fn get_keyed_children<S: AsRef<str>>(&self, token: S) -> Vec<(&str, &Self)> {
+        let mut matches: Vec<_> = self.patterns.matching(token.as_ref()).collect();
+        if matches.is_empty() {
+            Vec::default()
+        } else {
+            matches.sort_by(|(v1, _), (v2, _)| v1.cmp(v2));
+            matches
+                .into_iter()
+                .map(|(idx, _)| (self.keys[idx].as_ref(), self.children[idx].as_ref()))
+                .collect()
+        }
+    }
+
+    fn children(&self) -> Vec<(&str, &Self)> {
+        self.keys
+            .iter()
+            .map(|key| key.as_ref())
+            .zip(self.children.iter().map(|n| n.as_ref()))
+            .collect()
+    }
+
     fn value(&self) -> Option<&V> {
         self.value.as_ref()
     }
 
+    /// Neither the regex nor regex-filtered crates expose how much memory their compiled
+    /// automata use, so the pattern sources are used as a lower bound for them.
+    fn approx_memory_bytes(&self) -> usize {
+        mem::size_of::<Self>()
+            + mem::size_of::<Regexes>()
+            + self.children.capacity() * mem::size_of::<Box<Self>>()
+            + self
+                .patterns
+                .regexes()
+                .iter()
+                .map(|r| mem::size_of_val(r) + r.as_str().len())
+                .sum::<usize>()
+            + self
+                .children
+                .iter()
+                .map(|n| n.approx_memory_bytes())
+                .sum::<usize>()
+    }
+
     #[inline]
     fn len(&self) -> usize {
         self.children.len()
@@ -69,32 +123,47 @@ impl<V> ImmutableTrieNode<V> for RegexFilteredTrieNode<V> {
 
 pub struct RegexFilteredTrieNodeBuilder<V> {
     value: Option<V>,
-    children: HashMap<String, Box<RegexFilteredTrieNodeBuilder<V>>>,
+    /// Kept sorted so that the built node's patterns are always in the same order, regardless
+    /// of the order keys were added in.
+    children: BTreeMap<String, Box<RegexFilteredTrieNodeBuilder<V>>>,
+    anchoring: Anchoring,
+}
+
+impl<V> RegexFilteredTrieNodeBuilder<V> {
+    /// Sets how each key part is anchored before it's compiled. By default, parts have to match
+    /// the whole token. This is applied to every descendant when the node is built.
+    #[inline]
+    pub fn with_anchoring(mut self, anchoring: Anchoring) -> Self {
+        self.anchoring = anchoring;
+        self
+    }
 }
 
 impl<V> Default for RegexFilteredTrieNodeBuilder<V> {
     fn default() -> Self {
         Self {
             value: None,
-            children: HashMap::new(),
+            children: BTreeMap::new(),
+            anchoring: Anchoring::default(),
         }
     }
 }
 
+impl<V> Sealed for RegexFilteredTrieNodeBuilder<V> {}
+
 impl<V> ImmutableTrieNodeBuilder<V> for RegexFilteredTrieNodeBuilder<V> {
     type Node = RegexFilteredTrieNode<V>;
 
-    fn add<S, I>(&mut self, mut items_iter: I, value: V) -> Result<()>
+    fn add_with<S, I, F>(&mut self, mut items_iter: I, value: V, on_duplicate: F) -> Result<()>
     where
         S: AsRef<str>,
         I: Iterator<Item = S>,
+        F: FnOnce(V, V) -> Result<V, (V, Error)>,
     {
         let pattern = if let Some(part) = items_iter.next() {
-            //String::from(part.as_ref())
-            format!("^{}$", part.as_ref())
+            String::from(part.as_ref())
         } else {
-            self.value = Some(value);
-            return Ok(());
+            return merge_value(&mut self.value, value, on_duplicate);
         };
         if !self.children.contains_key(&pattern) {
             let child = Self::default();
@@ -103,20 +172,36 @@ impl<V> ImmutableTrieNodeBuilder<V> for RegexFilteredTrieNodeBuilder<V> {
         self.children
             .get_mut(&pattern)
             .unwrap()
-            .add(items_iter, value)
+            .add_with(items_iter, value, on_duplicate)
+    }
+
+    fn validate_token(&self, token: &str) -> Result<()> {
+        RegexesBuilder::new()
+            .push_opt(&self.anchoring.anchor(token), &OPTIMIZED_REGEX_OPTS)
+            .map_err(pattern_compile(token))?;
+        Ok(())
     }
 
     fn build(self) -> Result<Self::Node> {
         let mut children = Vec::with_capacity(self.children.len());
         let mut regexes_builder = RegexesBuilder::new();
-        for (pattern, child) in self.children.into_iter() {
-            regexes_builder = regexes_builder.push_opt(pattern.as_str(), &OPTIMIZED_REGEX_OPTS)?;
+        let patterns = self
+            .anchoring
+            .anchor_all(self.children.keys().map(String::as_str))?;
+        let mut keys = Vec::with_capacity(self.children.len());
+        for ((key, mut child), pattern) in self.children.into_iter().zip(patterns) {
+            regexes_builder = regexes_builder
+                .push_opt(&pattern, &OPTIMIZED_REGEX_OPTS)
+                .map_err(pattern_compile(&key))?;
+            keys.push(KeyToken::from(key));
+            child.anchoring = self.anchoring.clone();
             let child = child.build()?;
             children.push(Box::new(child));
         }
         Ok(RegexFilteredTrieNode {
             value: self.value,
             patterns: Arc::new(regexes_builder.build()?),
+            keys: keys.into_boxed_slice(),
             children,
         })
     }
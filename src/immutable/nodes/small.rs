@@ -0,0 +1,245 @@
+use super::{ImmutableTrieNode, ImmutableTrieNodeBuilder, KeyToken, merge_value, same_entries};
+use crate::{Error, Result, sealed::Sealed};
+use std::{array, cmp::Ordering, collections::BTreeMap, fmt};
+
+/// A key of at most `N` tokens, stored inline.
+#[derive(Clone)]
+struct SmallKey<const N: usize> {
+    len: usize,
+    tokens: [KeyToken; N],
+}
+
+impl<const N: usize> SmallKey<N> {
+    #[inline]
+    fn as_slice(&self) -> &[KeyToken] {
+        &self.tokens[..self.len]
+    }
+}
+
+impl<const N: usize> PartialEq for SmallKey<N> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<const N: usize> Eq for SmallKey<N> {}
+
+impl<const N: usize> PartialOrd for SmallKey<N> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize> Ord for SmallKey<N> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_slice().cmp(other.as_slice())
+    }
+}
+
+/// A trie node for dictionaries whose keys are all at most `N` tokens long. Children are kept in
+/// sorted slices instead of maps, and lookups never recurse more than `N` levels deep.
+#[derive(Clone)]
+pub struct SmallKeyTrieNode<V, const N: usize> {
+    value: Option<V>,
+    children: Box<[(KeyToken, SmallKeyTrieNode<V, N>)]>,
+}
+
+impl<V, const N: usize> Default for SmallKeyTrieNode<V, N> {
+    fn default() -> Self {
+        Self {
+            value: None,
+            children: Box::default(),
+        }
+    }
+}
+
+impl<V, const N: usize> SmallKeyTrieNode<V, N> {
+    fn collect_all<'a, S: AsRef<str>>(
+        &'a self,
+        tokens: &[S],
+        depth: usize,
+        values: &mut Vec<(usize, &'a V)>,
+    ) {
+        let Some(child) = tokens
+            .get(depth)
+            .and_then(|token| self.child(token.as_ref()))
+        else {
+            return;
+        };
+        if let Some(value) = child.value.as_ref() {
+            values.push((depth + 1, value));
+        }
+        child.collect_all(tokens, depth + 1, values);
+    }
+
+    #[inline]
+    fn child(&self, token: &str) -> Option<&Self> {
+        self.children
+            .binary_search_by(|(key, _)| str::cmp(key, token))
+            .ok()
+            .map(|idx| &self.children[idx].1)
+    }
+}
+
+impl<V: PartialEq, const N: usize> PartialEq for SmallKeyTrieNode<V, N> {
+    fn eq(&self, other: &Self) -> bool {
+        same_entries(self, other)
+    }
+}
+
+impl<V: Eq, const N: usize> Eq for SmallKeyTrieNode<V, N> {}
+
+impl<V, const N: usize> Sealed for SmallKeyTrieNode<V, N> {}
+
+impl<V, const N: usize> ImmutableTrieNode<V> for SmallKeyTrieNode<V, N> {
+    fn value(&self) -> Option<&V> {
+        self.value.as_ref()
+    }
+
+    fn get_children<S: AsRef<str>>(&self, token: S) -> Vec<&Self> {
+        self.child(token.as_ref()).into_iter().collect()
+    }
+
+    #[inline]
+    fn get_child<S: AsRef<str>>(&self, token: S) -> Option<&Self> {
+        self.child(token.as_ref())
+    }
+
+    fn children(&self) -> Vec<(&str, &Self)> {
+        self.children
+            .iter()
+            .map(|(key, child)| (key.as_ref(), child))
+            .collect()
+    }
+
+    fn get_all_with_depth<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<(usize, &V)> {
+        let mut values = Vec::new();
+        self.collect_all(&tokens[..tokens.len().min(N)], 0, &mut values);
+        values
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    fn len_recursive(&self) -> usize {
+        self.len()
+            + self
+                .children
+                .iter()
+                .map(|(_, n)| n.len_recursive())
+                .sum::<usize>()
+    }
+}
+
+impl<V: fmt::Debug, const N: usize> fmt::Debug for SmallKeyTrieNode<V, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let children: Vec<_> = self.children.iter().map(|(k, n)| (k, n)).collect();
+        f.debug_struct("SmallKeyTrieNode")
+            .field("value", &self.value)
+            .field("children", &children)
+            .finish()
+    }
+}
+
+/// Builds a [`SmallKeyTrieNode`]. Adding a key with more than `N` tokens fails with
+/// [`Error::KeyTooLong`].
+pub struct SmallKeyTrieNodeBuilder<V, const N: usize> {
+    value: Option<V>,
+    entries: BTreeMap<SmallKey<N>, V>,
+}
+
+impl<V, const N: usize> Default for SmallKeyTrieNodeBuilder<V, N> {
+    fn default() -> Self {
+        Self {
+            value: None,
+            entries: BTreeMap::new(),
+        }
+    }
+}
+
+impl<V, const N: usize> SmallKeyTrieNodeBuilder<V, N> {
+    /// Builds the children of the node reached by the first `depth` tokens of every entry. The
+    /// entries must be sorted and all share those tokens.
+    fn build_children(
+        mut entries: Vec<(SmallKey<N>, Option<V>)>,
+        depth: usize,
+    ) -> Box<[(KeyToken, SmallKeyTrieNode<V, N>)]> {
+        let mut children = Vec::new();
+        while !entries.is_empty() {
+            let token = entries[0].0.tokens[depth].clone();
+            let group_len = entries
+                .iter()
+                .take_while(|(key, _)| key.tokens[depth] == token)
+                .count();
+            let rest = entries.split_off(group_len);
+            let mut group = std::mem::replace(&mut entries, rest);
+            // Shorter keys sort first, so the ones ending here are at the start of the group.
+            let ends_here = group
+                .iter()
+                .take_while(|(key, _)| key.len == depth + 1)
+                .count();
+            let deeper = group.split_off(ends_here);
+            let value = group.into_iter().filter_map(|(_, value)| value).last();
+            children.push((
+                token,
+                SmallKeyTrieNode {
+                    value,
+                    children: Self::build_children(deeper, depth + 1),
+                },
+            ));
+        }
+        children.into_boxed_slice()
+    }
+}
+
+impl<V, const N: usize> Sealed for SmallKeyTrieNodeBuilder<V, N> {}
+
+impl<V, const N: usize> ImmutableTrieNodeBuilder<V> for SmallKeyTrieNodeBuilder<V, N> {
+    type Node = SmallKeyTrieNode<V, N>;
+
+    fn add_with<S, I, F>(&mut self, items_iter: I, value: V, on_duplicate: F) -> Result<()>
+    where
+        S: AsRef<str>,
+        I: Iterator<Item = S>,
+        F: FnOnce(V, V) -> Result<V, (V, Error)>,
+    {
+        let mut key = SmallKey {
+            len: 0,
+            tokens: array::from_fn(|_| KeyToken::default()),
+        };
+        for part in items_iter {
+            if key.len == N {
+                return Err(Error::KeyTooLong { max: N });
+            }
+            key.tokens[key.len] = KeyToken::from(part.as_ref());
+            key.len += 1;
+        }
+        if key.len == 0 {
+            return merge_value(&mut self.value, value, on_duplicate);
+        }
+        let mut slot = self.entries.remove(&key);
+        let result = merge_value(&mut slot, value, on_duplicate);
+        if let Some(value) = slot {
+            self.entries.insert(key, value);
+        }
+        result
+    }
+
+    fn build(self) -> Result<Self::Node> {
+        // The entries came out of a BTreeMap, so they're already sorted.
+        let entries = self
+            .entries
+            .into_iter()
+            .map(|(key, value)| (key, Some(value)))
+            .collect();
+        Ok(SmallKeyTrieNode {
+            value: self.value,
+            children: Self::build_children(entries, 0),
+        })
+    }
+}
@@ -0,0 +1,303 @@
+use super::{
+    ChildHasher, ImmutableTrieNode, ImmutableTrieNodeBuilder, gap_of, merge_value, same_entries,
+};
+use crate::{Error, Result, sealed::Sealed};
+use std::{
+    collections::{HashMap, HashSet},
+    mem,
+    ops::RangeInclusive,
+    sync::Arc,
+};
+
+/// The tokens that children are keyed by. These are normally shared through a
+/// [`TokenInterner`], but with the `compact_str` feature, short tokens are stored inline instead.
+/// That saves an allocation and a pointer chase per short token, but it also means nothing is
+/// shared anymore: the interner only dedupes its own set, and every node gets its own copy of its
+/// key, including long tokens that spill onto the heap. Dictionaries that repeat long tokens
+/// under many nodes can end up larger with the feature than without it.
+#[cfg(not(feature = "compact_str"))]
+pub(crate) type ChildKey = Arc<str>;
+#[cfg(feature = "compact_str")]
+pub(crate) type ChildKey = super::KeyToken;
+
+/// Hands out one shared copy of each distinct token, so tokens that show up under many nodes
+/// (such as `the` or `error`) are only stored once.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TokenInterner {
+    tokens: HashSet<ChildKey, ChildHasher>,
+}
+
+impl TokenInterner {
+    pub(crate) fn intern(&mut self, token: &str) -> ChildKey {
+        if let Some(interned) = self.tokens.get(token) {
+            return interned.clone();
+        }
+        let interned = ChildKey::from(token);
+        self.tokens.insert(interned.clone());
+        interned
+    }
+
+    /// The number of distinct tokens.
+    #[inline]
+    pub(crate) fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    #[inline]
+    pub(crate) fn reserve(&mut self, additional: usize) {
+        self.tokens.reserve(additional);
+    }
+}
+
+/// A trie node whose children are looked up by their exact token. The children are kept in a
+/// sorted slice and binary searched, since the trie never changes once it's built. The tokens
+/// are interned when the trie is built, so every node that has a child for the same token shares
+/// the same copy of it (unless the `compact_str` feature is enabled; see [`ChildKey`]).
+///
+/// Chains of nodes that have a single child and no value are merged into one node when the trie
+/// is built, with the keys after the first one kept as the node's [tail](ImmutableTrieNode::tail).
+/// This keeps long keys that don't share much with any others from costing a node per token.
+#[derive(Clone, Debug)]
+pub struct StringTrieNode<V> {
+    value: Option<V>,
+    tail: Box<[ChildKey]>,
+    children: Box<[(ChildKey, StringTrieNode<V>)]>,
+    /// The [gaps](gap_of) among the children, along with their indexes in `children`. These
+    /// are found when the node is built so that lookups don't have to parse every key.
+    gaps: Box<[(RangeInclusive<usize>, usize)]>,
+}
+
+impl<V> Default for StringTrieNode<V> {
+    fn default() -> Self {
+        Self {
+            value: None,
+            tail: Box::default(),
+            children: Box::default(),
+            gaps: Box::default(),
+        }
+    }
+}
+
+impl<V> StringTrieNode<V> {
+    #[inline]
+    fn child(&self, token: &str) -> Option<&Self> {
+        self.children
+            .binary_search_by(|(key, _)| str::cmp(key, token))
+            .ok()
+            .map(|idx| &self.children[idx].1)
+    }
+
+    /// Merges this node with its only child if it doesn't have a value, adding the child's key
+    /// to the front of its tail. The child's own chain has already been merged into it by then,
+    /// so this only ever has to be done once.
+    fn merge_chain(self) -> Self {
+        if self.value.is_some() || self.children.len() != 1 || !self.gaps.is_empty() {
+            return self;
+        }
+        let (key, child) = self.children.into_vec().remove(0);
+        let mut tail = vec![key];
+        tail.extend(child.tail.into_vec());
+        Self {
+            value: child.value,
+            tail: tail.into_boxed_slice(),
+            children: child.children,
+            gaps: child.gaps,
+        }
+    }
+
+    /// Adds up the size of this node and its descendants, and collects the distinct keys so
+    /// shared keys are only counted once.
+    fn node_bytes(&self, keys: &mut HashSet<(*const u8, usize)>) -> usize {
+        let mut bytes =
+            mem::size_of::<Self>() + mem::size_of_val(&*self.gaps) + mem::size_of_val(&*self.tail);
+        for key in &self.tail {
+            keys.insert((key.as_ptr(), key.len()));
+        }
+        for (key, child) in &self.children {
+            keys.insert((key.as_ptr(), key.len()));
+            bytes += mem::size_of::<ChildKey>() + child.node_bytes(keys);
+        }
+        bytes
+    }
+}
+
+impl<V: PartialEq> PartialEq for StringTrieNode<V> {
+    fn eq(&self, other: &Self) -> bool {
+        same_entries(self, other)
+    }
+}
+
+impl<V: Eq> Eq for StringTrieNode<V> {}
+
+impl<V> Sealed for StringTrieNode<V> {}
+
+impl<V> ImmutableTrieNode<V> for StringTrieNode<V> {
+    #[inline]
+    fn value(&self) -> Option<&V> {
+        self.value.as_ref()
+    }
+
+    fn get_children<S: AsRef<str>>(&self, token: S) -> Vec<&Self> {
+        self.child(token.as_ref()).into_iter().collect()
+    }
+
+    #[inline]
+    fn get_child<S: AsRef<str>>(&self, token: S) -> Option<&Self> {
+        self.child(token.as_ref())
+    }
+
+    fn children(&self) -> Vec<(&str, &Self)> {
+        self.children
+            .iter()
+            .map(|(key, child)| (key.as_ref(), child))
+            .collect()
+    }
+
+    #[inline]
+    fn tail(&self) -> &[impl AsRef<str>] {
+        &self.tail
+    }
+
+    fn gaps(&self) -> impl DoubleEndedIterator<Item = (RangeInclusive<usize>, &Self)> {
+        self.gaps
+            .iter()
+            .map(|(skips, idx)| (skips.clone(), &self.children[*idx].1))
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.children.len()
+    }
+
+    fn len_recursive(&self) -> usize {
+        self.len()
+            + self
+                .children
+                .iter()
+                .map(|(_, n)| n.len_recursive())
+                .sum::<usize>()
+    }
+
+    fn approx_memory_bytes(&self) -> usize {
+        let mut keys = HashSet::new();
+        self.node_bytes(&mut keys) + keys.iter().map(|(_, len)| len).sum::<usize>()
+    }
+}
+
+/// A node that's still being built, whose children can still be added to.
+#[derive(Clone, Debug)]
+struct PendingNode<V> {
+    value: Option<V>,
+    children: HashMap<ChildKey, PendingNode<V>, ChildHasher>,
+}
+
+impl<V> Default for PendingNode<V> {
+    fn default() -> Self {
+        Self {
+            value: None,
+            children: HashMap::default(),
+        }
+    }
+}
+
+impl<V> PendingNode<V> {
+    fn build(self, parameters: bool) -> StringTrieNode<V> {
+        let mut children: Vec<_> = self
+            .children
+            .into_iter()
+            .map(|(key, child)| {
+                let child = child.build(parameters);
+                // Gaps are matched by skipping tokens, so they can't be the start of a chain.
+                match gap_of(&key, parameters) {
+                    Some(_) => (key, child),
+                    None => (key, child.merge_chain()),
+                }
+            })
+            .collect();
+        children.sort_unstable_by(|(left, _), (right, _)| left.cmp(right));
+        let gaps = children
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, (key, _))| gap_of(key, parameters).map(|skips| (skips, idx)))
+            .collect();
+        StringTrieNode {
+            value: self.value,
+            tail: Box::default(),
+            children: children.into_boxed_slice(),
+            gaps,
+        }
+    }
+}
+
+/// Builds a [`StringTrieNode`], interning every token that's added.
+#[derive(Clone, Debug)]
+pub struct StringTrieNodeBuilder<V> {
+    root: PendingNode<V>,
+    interner: TokenInterner,
+    parameters: bool,
+}
+
+impl<V> Default for StringTrieNodeBuilder<V> {
+    fn default() -> Self {
+        Self {
+            root: PendingNode::default(),
+            interner: TokenInterner::default(),
+            parameters: false,
+        }
+    }
+}
+
+impl<V> StringTrieNodeBuilder<V> {
+    /// Creates a builder with room for at least `capacity` keys. See
+    /// [`reserve`](ImmutableTrieNodeBuilder::reserve).
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut builder = Self::default();
+        builder.reserve(capacity);
+        builder
+    }
+
+    /// The number of distinct tokens that have been added so far.
+    #[inline]
+    pub fn num_distinct_tokens(&self) -> usize {
+        self.interner.len()
+    }
+}
+
+impl<V> Sealed for StringTrieNodeBuilder<V> {}
+
+impl<V> ImmutableTrieNodeBuilder<V> for StringTrieNodeBuilder<V> {
+    type Node = StringTrieNode<V>;
+
+    fn add_with<S, I, F>(&mut self, items_iter: I, value: V, on_duplicate: F) -> Result<()>
+    where
+        S: AsRef<str>,
+        I: Iterator<Item = S>,
+        F: FnOnce(V, V) -> Result<V, (V, Error)>,
+    {
+        let mut node = &mut self.root;
+        for part in items_iter {
+            node = node
+                .children
+                .entry(self.interner.intern(part.as_ref()))
+                .or_default();
+        }
+        merge_value(&mut node.value, value, on_duplicate)
+    }
+
+    /// Reserves room in the root's children and in the interner. Deeper nodes are grown as
+    /// they're needed, since how keys are spread out below the root isn't known up front.
+    fn reserve(&mut self, additional: usize) {
+        self.root.children.reserve(additional);
+        self.interner.reserve(additional);
+    }
+
+    #[inline]
+    fn enable_parameters(&mut self) {
+        self.parameters = true;
+    }
+
+    #[inline]
+    fn build(self) -> Result<Self::Node> {
+        Ok(self.root.build(self.parameters))
+    }
+}
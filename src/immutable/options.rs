@@ -0,0 +1,116 @@
+use super::{ImmutableTrie, ImmutableTrieNode};
+use crate::tokenization::Tokenizer;
+
+/// How matches that share tokens are handled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OverlapPolicy {
+    /// Every match is returned, even if it overlaps with another one.
+    #[default]
+    All,
+    /// Only the leftmost-longest matches are returned. See
+    /// [`ImmutableTrie::find_non_overlapping`].
+    LeftmostLongest,
+}
+
+/// Options for [`ImmutableTrie::find_with`]. New options may be added in minor releases, so
+/// this should be created with [`Default`] and the `with_*` methods.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct FindOptions {
+    /// The maximum number of values to return.
+    pub max_results: Option<usize>,
+    pub overlap: OverlapPolicy,
+    /// If matches can start after the first token. When this is `false`, the search string has
+    /// to start with a key.
+    pub skip_leading_tokens: bool,
+    /// If matches have to end at the last token.
+    pub anchor_end: bool,
+}
+
+impl Default for FindOptions {
+    fn default() -> Self {
+        Self {
+            max_results: None,
+            overlap: OverlapPolicy::All,
+            skip_leading_tokens: true,
+            anchor_end: false,
+        }
+    }
+}
+
+impl FindOptions {
+    #[inline]
+    pub fn with_max_results(mut self, max_results: usize) -> Self {
+        self.max_results = Some(max_results);
+        self
+    }
+
+    #[inline]
+    pub fn with_overlap(mut self, overlap: OverlapPolicy) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    #[inline]
+    pub fn with_skip_leading_tokens(mut self, skip_leading_tokens: bool) -> Self {
+        self.skip_leading_tokens = skip_leading_tokens;
+        self
+    }
+
+    #[inline]
+    pub fn with_anchor_end(mut self, anchor_end: bool) -> Self {
+        self.anchor_end = anchor_end;
+        self
+    }
+
+    /// Requires matches to cover the entire search string.
+    #[inline]
+    pub fn anchored(self) -> Self {
+        self.with_skip_leading_tokens(false).with_anchor_end(true)
+    }
+}
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    pub fn find_with<S: AsRef<str>>(&self, search_str: S, options: &FindOptions) -> Vec<&V> {
+        let tokens = self.tokenizer.tokenize(search_str.as_ref());
+        let max_results = options.max_results.unwrap_or(usize::MAX);
+        let last_start = if options.skip_leading_tokens {
+            tokens.len()
+        } else {
+            tokens.len().min(1)
+        };
+        let mut found = Vec::new();
+        let mut i = 0;
+        while i < last_start && found.len() < max_results {
+            let mut candidates = self
+                .matches_at(&tokens[i..])
+                .into_iter()
+                .filter(|(depth, _)| !options.anchor_end || i + depth == tokens.len());
+            match options.overlap {
+                OverlapPolicy::All => {
+                    found.extend(candidates.map(|(_, value)| value));
+                    i += 1;
+                }
+                OverlapPolicy::LeftmostLongest => {
+                    let longest = candidates
+                        .reduce(|longest, next| if next.0 > longest.0 { next } else { longest });
+                    if let Some((depth, value)) = longest {
+                        found.push(value);
+                        i += depth;
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+        }
+        found.truncate(max_results);
+        found
+    }
+}
@@ -0,0 +1,63 @@
+use super::{
+    ImmutableTrie, check_gaps,
+    nodes::{PersistentTrieNode, param_name},
+};
+use crate::{Result, error::EmptyKeySnafu, tokenization::Tokenizer};
+
+impl<V, T> ImmutableTrie<PersistentTrieNode<V>, V, T>
+where
+    V: Clone,
+    T: Tokenizer + Clone,
+{
+    /// Creates a new version of this trie with the key set to `value`, leaving this one as it
+    /// is. Only the nodes along the key's path are copied; everything else is shared between the
+    /// two versions. The key is split with this trie's tokenizer, so use
+    /// [`insert_tokens`](Self::insert_tokens) if keys should be split differently from searches.
+    ///
+    /// [Pruning hints](super::PruningHints) aren't carried over to the new version, since they
+    /// can't be shared.
+    pub fn insert<S: AsRef<str>>(&self, key: S, value: V) -> Result<Self> {
+        let key = key.as_ref();
+        let tokens = self.tokenizer.tokenize(key);
+        if tokens.is_empty() && !key.is_empty() {
+            return EmptyKeySnafu { key }.fail();
+        }
+        self.insert_tokens(&tokens, value)
+    }
+
+    /// Like [`insert`](Self::insert), but with an already tokenized key.
+    pub fn insert_tokens<S: AsRef<str>>(&self, tokens: &[S], value: V) -> Result<Self> {
+        check_gaps(tokens)?;
+        let has_params = self.root.parameters()
+            && tokens
+                .iter()
+                .any(|token| param_name(token.as_ref()).is_some());
+        Ok(self.with_root(self.root.insert(tokens, value), has_params))
+    }
+
+    /// Creates a new version of this trie without the key, leaving this one as it is. Returns
+    /// `None` if the key doesn't have a value. Like [`insert`](Self::insert), everything but the
+    /// key's path is shared.
+    pub fn remove<S: AsRef<str>>(&self, key: S) -> Option<Self> {
+        let tokens = self.tokenizer.tokenize(key.as_ref());
+        self.remove_tokens(&tokens)
+    }
+
+    /// Like [`remove`](Self::remove), but with an already tokenized key.
+    pub fn remove_tokens<S: AsRef<str>>(&self, tokens: &[S]) -> Option<Self> {
+        let root = self.root.remove(tokens)?;
+        Some(self.with_root(root, false))
+    }
+
+    fn with_root(&self, root: PersistentTrieNode<V>, has_params: bool) -> Self {
+        Self {
+            tokenizer: self.tokenizer.clone(),
+            root,
+            version: self.version,
+            max_gap: self.max_gap,
+            hints: None,
+            has_params: self.has_params || has_params,
+            _spooky: self._spooky,
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use super::{ImmutableTrie, ImmutableTrieNode};
+use crate::{Result, error::FieldCountMismatchSnafu, tokenization::Tokenizer};
+use snafu::ensure;
+
+/// The placeholder that marks a variable field in a query template.
+pub const QUERY_FIELD: &str = "{}";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplatePart {
+    Literal(String),
+    Field,
+}
+
+/// What's known ahead of time about the matches that start at a literal token of the template.
+#[derive(Debug, Clone)]
+enum Start<'a, V> {
+    /// Every path through the trie ends before reaching a field, so the matches are always the
+    /// same.
+    Static(Vec<&'a V>),
+    /// At least one path reaches a field, so the trie has to be searched for each input.
+    Dynamic,
+}
+
+/// A query template whose literal tokens have already been tokenized and searched, so that it
+/// can be run against many sets of field values quickly. Created by
+/// [`ImmutableTrie::compile_query`].
+pub struct PreparedQuery<'a, N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    trie: &'a ImmutableTrie<N, V, T>,
+    parts: Vec<TemplatePart>,
+    /// One entry per part, `None` for fields.
+    starts: Vec<Option<Start<'a, V>>>,
+    num_fields: usize,
+}
+
+impl<'a, N, V, T> PreparedQuery<'a, N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    /// The number of fields in the template.
+    #[inline]
+    pub fn num_fields(&self) -> usize {
+        self.num_fields
+    }
+
+    /// Finds all of the values in the template with its fields filled in, in the same order as
+    /// [`ImmutableTrie::find_all`] would return them for the filled in string. Each field is
+    /// tokenized on its own.
+    pub fn find_all<S: AsRef<str>>(&self, fields: &[S]) -> Result<Vec<&'a V>> {
+        ensure!(
+            fields.len() == self.num_fields,
+            FieldCountMismatchSnafu {
+                expected: self.num_fields,
+                found: fields.len(),
+            }
+        );
+        let mut fields = fields.iter();
+        let mut tokens = Vec::new();
+        // The index of the first token of each part, followed by the total number of tokens.
+        let mut offsets = Vec::with_capacity(self.parts.len() + 1);
+        for part in &self.parts {
+            offsets.push(tokens.len());
+            match part {
+                TemplatePart::Literal(token) => tokens.push(token.clone()),
+                TemplatePart::Field => {
+                    let field = fields.next().expect("field count was checked");
+                    tokens.extend(self.trie.tokenizer.tokenize(field.as_ref()));
+                }
+            }
+        }
+        offsets.push(tokens.len());
+        let mut found = Vec::new();
+        for (idx, start) in self.starts.iter().enumerate() {
+            match start {
+                Some(Start::Static(values)) => found.extend(values.iter().copied()),
+                Some(Start::Dynamic) => found.extend(self.values_at(&tokens[offsets[idx]..])),
+                None => {
+                    for i in offsets[idx]..offsets[idx + 1] {
+                        if self.trie.may_match(&tokens[i..]) {
+                            found.extend(self.values_at(&tokens[i..]));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// Gets the values of the keys that match at the start of the tokens.
+    fn values_at(&self, tokens: &[String]) -> impl Iterator<Item = &'a V> {
+        let trie: &'a ImmutableTrie<N, V, T> = self.trie;
+        trie.matches_at(tokens).into_iter().map(|(_, value)| value)
+    }
+}
+
+/// Gets all of the values under `node` for the template parts, or `None` if any path reaches a
+/// field.
+fn static_values<'a, N, V>(node: &'a N, parts: &[TemplatePart]) -> Option<Vec<&'a V>>
+where
+    N: ImmutableTrieNode<V>,
+{
+    let mut values = Vec::new();
+    match parts.first() {
+        Some(TemplatePart::Literal(token)) => {
+            'children: for child in node.get_children(token) {
+                let mut rest = &parts[1..];
+                for key in child.tail() {
+                    match rest.split_first() {
+                        Some((TemplatePart::Literal(token), after)) if token == key.as_ref() => {
+                            rest = after;
+                        }
+                        Some((TemplatePart::Field, _)) => return None,
+                        _ => continue 'children,
+                    }
+                }
+                values.extend(child.value());
+                values.extend(static_values(child, rest)?);
+            }
+        }
+        Some(TemplatePart::Field) if node.len() > 0 => return None,
+        _ => {}
+    }
+    Some(values)
+}
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    /// Prepares a query template for repeated use. Each [`QUERY_FIELD`] in the template marks a
+    /// field whose value is supplied when the query is run; the rest of the template is
+    /// tokenized once, and the matches that can't reach a field are found up front.
+    ///
+    /// Since the template is split on its fields before being tokenized, a field always starts
+    /// a new token. With a [max gap](Self::with_max_gap), none of the matches are found up
+    /// front, since a gap can always skip ahead into a field.
+    pub fn compile_query<S: AsRef<str>>(&self, template: S) -> PreparedQuery<'_, N, V, T> {
+        let mut parts = Vec::new();
+        for (idx, literal) in template.as_ref().split(QUERY_FIELD).enumerate() {
+            if idx > 0 {
+                parts.push(TemplatePart::Field);
+            }
+            parts.extend(
+                self.tokenizer
+                    .tokenize(literal)
+                    .into_iter()
+                    .map(TemplatePart::Literal),
+            );
+        }
+        let starts = (0..parts.len())
+            .map(|idx| match &parts[idx] {
+                TemplatePart::Field => None,
+                TemplatePart::Literal(token) if !self.may_match(&[token]) => {
+                    Some(Start::Static(Vec::new()))
+                }
+                TemplatePart::Literal(_) if self.max_gap != 0 => Some(Start::Dynamic),
+                TemplatePart::Literal(_) => Some(
+                    static_values(&self.root, &parts[idx..]).map_or(Start::Dynamic, Start::Static),
+                ),
+            })
+            .collect();
+        PreparedQuery {
+            trie: self,
+            num_fields: parts
+                .iter()
+                .filter(|part| **part == TemplatePart::Field)
+                .count(),
+            parts,
+            starts,
+        }
+    }
+}
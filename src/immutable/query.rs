@@ -0,0 +1,267 @@
+//! A flat request/response layer for querying tries. These types only hold plain data so that
+//! they can be shared across language bindings and serialized as-is. [`QueryRequest`] and
+//! [`QueryResponse`] own their text and matches, so they aren't FFI-safe themselves; they're
+//! passed across an FFI boundary as a [`QueryRequestView`] or [`QueryResponseView`] instead.
+use super::{ImmutableTrie, ImmutableTrieNode};
+use crate::{
+    Result,
+    error::InvalidUtf8Snafu,
+    tokenization::{Tokenizer, TokenizerExt},
+};
+use snafu::ResultExt;
+use std::{marker::PhantomData, slice, str};
+
+/// The version of the [`QueryResponse`]/[`FlatMatch`] schema. This is bumped whenever the shape
+/// or meaning of those types changes so that stored responses can still be interpreted.
+pub const QUERY_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QueryRequest {
+    pub text: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub options: QueryOptions,
+}
+
+impl QueryRequest {
+    #[inline]
+    pub fn new<S: Into<String>>(text: S) -> Self {
+        Self {
+            text: text.into(),
+            options: Default::default(),
+        }
+    }
+
+    #[inline]
+    pub fn with_options(mut self, options: QueryOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+/// A `#[repr(C)]` view of a [`QueryRequest`], with its text as a pointer to UTF-8 bytes (which
+/// aren't nul-terminated) and a length.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct QueryRequestView<'a> {
+    text_ptr: *const u8,
+    text_len: usize,
+    options: QueryOptions,
+    _text: PhantomData<&'a [u8]>,
+}
+
+impl QueryRequest {
+    #[inline]
+    pub fn as_view(&self) -> QueryRequestView<'_> {
+        QueryRequestView {
+            text_ptr: self.text.as_ptr(),
+            text_len: self.text.len(),
+            options: self.options,
+            _text: PhantomData,
+        }
+    }
+}
+
+impl<'a> QueryRequestView<'a> {
+    /// Creates a view of a request that was made on the other side of an FFI boundary.
+    ///
+    /// # Safety
+    ///
+    /// Unless `text_len` is `0`, `text_ptr` has to point to `text_len` bytes that stay valid and
+    /// unchanged for `'a`.
+    #[inline]
+    pub unsafe fn from_raw_parts(
+        text_ptr: *const u8,
+        text_len: usize,
+        options: QueryOptions,
+    ) -> Self {
+        Self {
+            text_ptr,
+            text_len,
+            options,
+            _text: PhantomData,
+        }
+    }
+
+    /// Gets the text of the request, failing if it isn't valid UTF-8.
+    pub fn text(&self) -> Result<&'a str> {
+        if self.text_len == 0 {
+            return Ok("");
+        }
+        // SAFETY: Views are either made from a `QueryRequest` that outlives them, or with
+        // `from_raw_parts`, whose caller guarantees that the bytes are valid for `'a`.
+        let bytes = unsafe { slice::from_raw_parts(self.text_ptr, self.text_len) };
+        str::from_utf8(bytes).context(InvalidUtf8Snafu)
+    }
+
+    #[inline]
+    pub fn options(&self) -> QueryOptions {
+        self.options
+    }
+
+    /// Copies the view into an owned [`QueryRequest`].
+    pub fn to_request(&self) -> Result<QueryRequest> {
+        Ok(QueryRequest::new(self.text()?).with_options(self.options))
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct QueryOptions {
+    /// The maximum number of matches to return. `0` means there is no limit.
+    pub limit: usize,
+}
+
+/// A single match, identified by the half-open range of tokens (`token_start..token_end`) it
+/// covers. This is only FFI-safe if `V` is.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FlatMatch<V> {
+    pub token_start: usize,
+    pub token_end: usize,
+    pub value: V,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QueryResponse<V> {
+    /// The [`QUERY_SCHEMA_VERSION`] this response was created with.
+    pub schema_version: u32,
+    /// The [version](ImmutableTrie::version) of the trie that produced this response.
+    pub trie_version: u32,
+    pub matches: Vec<FlatMatch<V>>,
+}
+
+impl<V> Default for QueryResponse<V> {
+    fn default() -> Self {
+        Self {
+            schema_version: QUERY_SCHEMA_VERSION,
+            trie_version: 0,
+            matches: Vec::new(),
+        }
+    }
+}
+
+/// A `#[repr(C)]` view of a [`QueryResponse`], with its matches as a pointer and a length. Like
+/// [`FlatMatch`], this is only FFI-safe if `V` is.
+#[repr(C)]
+#[derive(Debug)]
+pub struct QueryResponseView<'a, V> {
+    schema_version: u32,
+    trie_version: u32,
+    matches_ptr: *const FlatMatch<V>,
+    matches_len: usize,
+    _matches: PhantomData<&'a [FlatMatch<V>]>,
+}
+
+impl<V> Clone for QueryResponseView<'_, V> {
+    #[inline]
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V> Copy for QueryResponseView<'_, V> {}
+
+impl<V> QueryResponse<V> {
+    #[inline]
+    pub fn as_view(&self) -> QueryResponseView<'_, V> {
+        QueryResponseView {
+            schema_version: self.schema_version,
+            trie_version: self.trie_version,
+            matches_ptr: self.matches.as_ptr(),
+            matches_len: self.matches.len(),
+            _matches: PhantomData,
+        }
+    }
+}
+
+impl<'a, V> QueryResponseView<'a, V> {
+    /// Creates a view of a response that was made on the other side of an FFI boundary.
+    ///
+    /// # Safety
+    ///
+    /// Unless `matches_len` is `0`, `matches_ptr` has to point to `matches_len` matches that stay
+    /// valid and unchanged for `'a`.
+    #[inline]
+    pub unsafe fn from_raw_parts(
+        schema_version: u32,
+        trie_version: u32,
+        matches_ptr: *const FlatMatch<V>,
+        matches_len: usize,
+    ) -> Self {
+        Self {
+            schema_version,
+            trie_version,
+            matches_ptr,
+            matches_len,
+            _matches: PhantomData,
+        }
+    }
+
+    #[inline]
+    pub fn schema_version(&self) -> u32 {
+        self.schema_version
+    }
+
+    #[inline]
+    pub fn trie_version(&self) -> u32 {
+        self.trie_version
+    }
+
+    pub fn matches(&self) -> &'a [FlatMatch<V>] {
+        if self.matches_len == 0 {
+            return &[];
+        }
+        // SAFETY: Views are either made from a `QueryResponse` that outlives them, or with
+        // `from_raw_parts`, whose caller guarantees that the matches are valid for `'a`.
+        unsafe { slice::from_raw_parts(self.matches_ptr, self.matches_len) }
+    }
+
+    /// Copies the view into an owned [`QueryResponse`].
+    pub fn to_response(&self) -> QueryResponse<V>
+    where
+        V: Clone,
+    {
+        QueryResponse {
+            schema_version: self.schema_version,
+            trie_version: self.trie_version,
+            matches: self.matches().to_vec(),
+        }
+    }
+}
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    V: Clone,
+    T: Tokenizer,
+{
+    pub fn query(&self, request: &QueryRequest) -> QueryResponse<V> {
+        let limit = match request.options.limit {
+            0 => usize::MAX,
+            limit => limit,
+        };
+        let tokens = self.tokenizer.tokenize_ref(&request.text);
+        let mut response = QueryResponse {
+            trie_version: self.version,
+            ..Default::default()
+        };
+        for i in 0..tokens.len() {
+            for (depth, value) in self.matches_at(&tokens[i..]) {
+                if response.matches.len() >= limit {
+                    return response;
+                }
+                response.matches.push(FlatMatch {
+                    token_start: i,
+                    token_end: i + depth,
+                    value: value.clone(),
+                });
+            }
+        }
+        response
+    }
+}
@@ -0,0 +1,96 @@
+use super::{ImmutableTrie, ImmutableTrieNode};
+use crate::tokenization::Tokenizer;
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead},
+    ops::Range,
+};
+
+/// A value found on a line read by [`ImmutableTrie::find_in_reader`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LineMatch<'a, V> {
+    pub value: &'a V,
+    /// The line the value was found on, starting at 1.
+    pub line: usize,
+    /// The range of the line's tokens that matched.
+    pub tokens: Range<usize>,
+}
+
+/// An iterator over the values found in a reader, one line at a time. Created by
+/// [`ImmutableTrie::find_in_reader`].
+pub struct ReaderMatches<'a, N, V, T, R>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    trie: &'a ImmutableTrie<N, V, T>,
+    reader: R,
+    line: usize,
+    buf: String,
+    /// The values found on the current line that haven't been yielded yet.
+    pending: VecDeque<LineMatch<'a, V>>,
+    /// If `reader` returned an error, after which nothing else is read.
+    failed: bool,
+}
+
+impl<'a, N, V, T, R> Iterator for ReaderMatches<'a, N, V, T, R>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+    R: BufRead,
+{
+    type Item = io::Result<LineMatch<'a, V>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            if self.failed {
+                return None;
+            }
+            self.buf.clear();
+            match self.reader.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+            }
+            self.line += 1;
+            let tokens = self.trie.tokenizer.tokenize(&self.buf);
+            for i in 0..tokens.len() {
+                if !self.trie.may_match(&tokens[i..]) {
+                    continue;
+                }
+                let found = self.trie.matches_at(&tokens[i..]);
+                self.pending
+                    .extend(found.into_iter().map(|(depth, value)| LineMatch {
+                        value,
+                        line: self.line,
+                        tokens: i..i + depth,
+                    }));
+            }
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    /// Searches the reader one line at a time, so only a single line is ever held in memory.
+    /// Keys can't span multiple lines. Iteration stops early only if `reader` returns an error,
+    /// which is yielded as-is and is always the last item.
+    #[inline]
+    pub fn find_in_reader<R: BufRead>(&self, reader: R) -> ReaderMatches<'_, N, V, T, R> {
+        ReaderMatches {
+            trie: self,
+            reader,
+            line: 0,
+            buf: String::new(),
+            pending: VecDeque::new(),
+            failed: false,
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use super::{ImmutableTrie, ImmutableTrieNode};
+use crate::tokenization::{BoundaryTokenizer, Tokenizer};
+use std::{fmt, ops::Deref};
+use triomphe::Arc;
+
+/// An [`ImmutableTrie`] behind a reference count, so that cloning it is O(1) instead of copying
+/// every node and token. Use this to hand the same trie to many threads. It derefs to the trie,
+/// so it's searched the same way.
+pub struct SharedTrie<N, V, T = BoundaryTokenizer>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    trie: Arc<ImmutableTrie<N, V, T>>,
+}
+
+impl<N, V, T> SharedTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    #[inline]
+    pub fn new(trie: ImmutableTrie<N, V, T>) -> Self {
+        Self {
+            trie: Arc::new(trie),
+        }
+    }
+
+    /// Checks if two shared tries are clones of each other, rather than just having the same
+    /// contents.
+    #[inline]
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.trie, &other.trie)
+    }
+
+    /// Gets the trie back out, as long as this is its only clone.
+    #[inline]
+    pub fn try_unwrap(this: Self) -> Result<ImmutableTrie<N, V, T>, Self> {
+        Arc::try_unwrap(this.trie).map_err(|trie| Self { trie })
+    }
+}
+
+impl<N, V, T> Clone for SharedTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    #[inline]
+    fn clone(&self) -> Self {
+        Self {
+            trie: self.trie.clone(),
+        }
+    }
+}
+
+impl<N, V, T> Deref for SharedTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    type Target = ImmutableTrie<N, V, T>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.trie
+    }
+}
+
+impl<N, V, T> AsRef<ImmutableTrie<N, V, T>> for SharedTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    #[inline]
+    fn as_ref(&self) -> &ImmutableTrie<N, V, T> {
+        &self.trie
+    }
+}
+
+impl<N, V, T> From<ImmutableTrie<N, V, T>> for SharedTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    #[inline]
+    fn from(trie: ImmutableTrie<N, V, T>) -> Self {
+        Self::new(trie)
+    }
+}
+
+impl<N, V, T> fmt::Debug for SharedTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+    ImmutableTrie<N, V, T>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SharedTrie").field(&*self.trie).finish()
+    }
+}
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    /// Moves this trie behind a reference count so that it can be cloned cheaply. See
+    /// [`SharedTrie`].
+    #[inline]
+    pub fn into_shared(self) -> SharedTrie<N, V, T> {
+        SharedTrie::new(self)
+    }
+}
@@ -0,0 +1,268 @@
+use super::{ImmutableTrie, ImmutableTrieNode};
+use crate::tokenization::Tokenizer;
+#[cfg(feature = "tokio")]
+use futures_core::Stream;
+use std::collections::{HashSet, VecDeque};
+use std::ptr;
+#[cfg(feature = "tokio")]
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Matches tokens as they arrive instead of searching a whole string at once. Only the partial
+/// matches that are still in progress are kept, so arbitrarily long token streams can be searched
+/// in constant memory. Created by [`ImmutableTrie::stream_matcher`].
+pub struct StreamMatcher<'a, N, V> {
+    root: &'a N,
+    /// The partial matches that can still be extended.
+    active: Vec<Partial<'a, N>>,
+    max_gap: usize,
+    /// How many tokens have been pushed so far.
+    position: usize,
+    /// With a [max gap](ImmutableTrie::with_max_gap), the same value can be reached from the same
+    /// start in more than one way, so the start and address of each value that's been found are
+    /// kept for as long as a partial match from that start is still active.
+    seen: HashSet<(usize, *const V)>,
+}
+
+/// A match that's in progress.
+struct Partial<'a, N> {
+    /// The node the match has reached.
+    node: &'a N,
+    /// How many of the node's [tail](ImmutableTrieNode::tail) tokens have been matched so far.
+    matched: usize,
+    /// The least and most tokens that can still be skipped before the next one is matched (for
+    /// [gaps](super::nodes::parse_gap)). A gap only ever needs a single entry, however wide it is.
+    min_skip: usize,
+    max_skip: usize,
+    /// How many more unrelated tokens can be skipped because of the trie's max gap.
+    slack: usize,
+    /// The position of the token the match started at.
+    start: usize,
+}
+
+impl<'a, N, V> StreamMatcher<'a, N, V>
+where
+    N: ImmutableTrieNode<V> + 'a,
+    V: 'a,
+{
+    /// Adds the next token, returning the values of every key that ends with it. Each of the
+    /// values are ones that [`ImmutableTrie::find_all`] would return if it was given all of the
+    /// tokens that have been pushed so far, although not necessarily in the same order.
+    ///
+    /// The token has to have already been tokenized by the trie's tokenizer.
+    pub fn push_token<S: AsRef<str>>(&mut self, token: S) -> Vec<&'a V> {
+        let token = token.as_ref();
+        let mut found = Vec::new();
+        let mut active = Vec::new();
+        let root = Partial {
+            node: self.root,
+            matched: 0,
+            min_skip: 0,
+            max_skip: 0,
+            slack: 0,
+            start: self.position,
+        };
+        let mut pending: VecDeque<_> = self.active.drain(..).chain([root]).collect();
+        while let Some(partial) = pending.pop_front() {
+            let Partial {
+                node,
+                matched,
+                min_skip,
+                max_skip,
+                start,
+                ..
+            } = partial;
+            let tail = node.tail();
+            // This token can be skipped because of the max gap instead of being matched.
+            if partial.slack > 0 && max_skip == 0 && (matched < tail.len() || node.len() > 0) {
+                active.push(Partial {
+                    slack: partial.slack - 1,
+                    ..partial
+                });
+            }
+            if matched < tail.len() {
+                if tail[matched].as_ref() == token {
+                    self.reached(node, matched + 1, start, &mut found, &mut active);
+                }
+                continue;
+            }
+            // This token can be one of the ones being skipped.
+            if max_skip > 0 {
+                if min_skip <= 1 {
+                    found.extend(node.value().map(|value| (start, value)));
+                }
+                active.push(Partial {
+                    min_skip: min_skip.saturating_sub(1),
+                    max_skip: max_skip - 1,
+                    ..partial
+                });
+            }
+            if min_skip > 0 {
+                continue;
+            }
+            for (skips, child) in node.gaps() {
+                // A gap that can skip no tokens means its children can match this token. The max
+                // gap applies after the gap as well, which just makes it wider.
+                pending.push_back(Partial {
+                    node: child,
+                    matched: 0,
+                    min_skip: *skips.start(),
+                    max_skip: *skips.end() + self.max_gap,
+                    slack: 0,
+                    start,
+                });
+            }
+            for child in node.get_children(token) {
+                self.reached(child, 0, start, &mut found, &mut active);
+            }
+        }
+        self.active = active;
+        self.position += 1;
+        if self.max_gap == 0 {
+            return found.into_iter().map(|(_, value)| value).collect();
+        }
+        let found = found
+            .into_iter()
+            .filter(|(start, value)| self.seen.insert((*start, ptr::from_ref(*value))))
+            .map(|(_, value)| value)
+            .collect();
+        match self.active.iter().map(|partial| partial.start).min() {
+            Some(oldest) => self.seen.retain(|(start, _)| *start >= oldest),
+            None => self.seen.clear(),
+        }
+        found
+    }
+
+    /// Records that a token took a partial match to `node` with `matched` of its tail tokens
+    /// matched, finding its value if that was the last of them.
+    fn reached(
+        &self,
+        node: &'a N,
+        matched: usize,
+        start: usize,
+        found: &mut Vec<(usize, &'a V)>,
+        active: &mut Vec<Partial<'a, N>>,
+    ) {
+        let remaining = node.tail().len() - matched;
+        if remaining == 0 {
+            found.extend(node.value().map(|value| (start, value)));
+        }
+        if remaining > 0 || node.len() > 0 {
+            active.push(Partial {
+                node,
+                matched,
+                min_skip: 0,
+                max_skip: 0,
+                slack: self.max_gap,
+                start,
+            });
+        }
+    }
+
+    /// Tokenizes the string with `tokenizer` and pushes each of the tokens, returning all of the
+    /// values found.
+    pub fn push_str<S, T>(&mut self, s: S, tokenizer: &T) -> Vec<&'a V>
+    where
+        S: AsRef<str>,
+        T: Tokenizer,
+    {
+        tokenizer
+            .tokenize(s)
+            .into_iter()
+            .flat_map(|token| self.push_token(token))
+            .collect()
+    }
+
+    /// The number of partial matches that are still in progress.
+    #[inline]
+    pub fn num_active(&self) -> usize {
+        self.active.len()
+    }
+
+    /// Drops all of the partial matches, so that the next token is treated as the start of a new
+    /// stream.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.active.clear();
+        self.seen.clear();
+    }
+}
+
+/// An async [`Stream`] of the values found in a stream of tokens or lines. Created by
+/// [`ImmutableTrie::match_stream`].
+#[cfg(feature = "tokio")]
+pub struct MatchStream<'a, N, V, T, St> {
+    matcher: StreamMatcher<'a, N, V>,
+    tokenizer: &'a T,
+    inner: St,
+    /// Values that were found but haven't been yielded yet.
+    pending: VecDeque<&'a V>,
+}
+
+// None of the fields are ever pinned other than `inner`, which has to be `Unpin` anyway.
+#[cfg(feature = "tokio")]
+impl<N, V, T, St: Unpin> Unpin for MatchStream<'_, N, V, T, St> {}
+
+#[cfg(feature = "tokio")]
+impl<'a, N, V, T, St> Stream for MatchStream<'a, N, V, T, St>
+where
+    N: ImmutableTrieNode<V> + 'a,
+    V: 'a,
+    T: Tokenizer,
+    St: Stream<Item = String> + Unpin,
+{
+    type Item = &'a V;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(value) = this.pending.pop_front() {
+                return Poll::Ready(Some(value));
+            }
+            match Pin::new(&mut this.inner).poll_next(cx) {
+                Poll::Ready(Some(item)) => {
+                    let found = this.matcher.push_str(item, this.tokenizer);
+                    this.pending.extend(found);
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<N, V, T> ImmutableTrie<N, V, T>
+where
+    N: ImmutableTrieNode<V>,
+    T: Tokenizer,
+{
+    #[inline]
+    pub fn stream_matcher(&self) -> StreamMatcher<'_, N, V> {
+        StreamMatcher {
+            root: &self.root,
+            active: Vec::new(),
+            max_gap: self.max_gap,
+            position: 0,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Searches an async stream of tokens or lines, yielding each value as soon as the key it
+    /// belongs to is complete. Each item is tokenized with the trie's tokenizer, and matches can
+    /// span multiple items.
+    #[cfg(feature = "tokio")]
+    #[inline]
+    pub fn match_stream<St>(&self, stream: St) -> MatchStream<'_, N, V, T, St>
+    where
+        St: Stream<Item = String> + Unpin,
+    {
+        MatchStream {
+            matcher: self.stream_matcher(),
+            tokenizer: &self.tokenizer,
+            inner: stream,
+            pending: VecDeque::new(),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use super::{ImmutableTrie, ImmutableTrieBuilder, ImmutableTrieNode, ImmutableTrieNodeBuilder};
+use crate::{Result, tokenization::Tokenizer};
+
+/// A value with a weight, used to pick between ambiguous matches with
+/// [`ImmutableTrie::find_best`]. Higher weights are better.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Weighted<V> {
+    pub value: V,
+    pub weight: u32,
+}
+
+impl<V> Weighted<V> {
+    #[inline]
+    pub fn new(value: V, weight: u32) -> Self {
+        Self { value, weight }
+    }
+}
+
+impl<B, V, T> ImmutableTrieBuilder<B, Weighted<V>, T>
+where
+    B: ImmutableTrieNodeBuilder<Weighted<V>>,
+    T: Tokenizer,
+{
+    #[inline]
+    pub fn add_weighted<S: AsRef<str>>(&mut self, key: S, value: V, weight: u32) -> Result<()> {
+        self.add(key, Weighted::new(value, weight))
+    }
+}
+
+impl<N, V, T> ImmutableTrie<N, Weighted<V>, T>
+where
+    N: ImmutableTrieNode<Weighted<V>>,
+    T: Tokenizer,
+{
+    /// Finds the match with the highest weight. Ties go to the match with the most tokens, and
+    /// then to the one that starts first.
+    pub fn find_best<S: AsRef<str>>(&self, search_str: S) -> Option<&Weighted<V>> {
+        let tokens = self.tokenizer.tokenize(search_str.as_ref());
+        let mut best: Option<(usize, &Weighted<V>)> = None;
+        for i in 0..tokens.len() {
+            for (depth, found) in self.matches_at(&tokens[i..]) {
+                let is_better = best.is_none_or(|(best_depth, best)| {
+                    (found.weight, depth) > (best.weight, best_depth)
+                });
+                if is_better {
+                    best = Some((depth, found));
+                }
+            }
+        }
+        best.map(|(_, found)| found)
+    }
+}
@@ -0,0 +1,39 @@
+use super::{ImmutableTrieBuilder, ImmutableTrieNodeBuilder};
+use crate::{Result, error::InvalidLineSnafu, tokenization::Tokenizer};
+use snafu::ResultExt;
+use std::io::BufRead;
+
+impl<B, T> ImmutableTrieBuilder<B, bool, T>
+where
+    B: ImmutableTrieNodeBuilder<bool> + Default,
+    T: Tokenizer + Default,
+{
+    /// Creates a matcher builder out of a wordlist. See [`add_wordlist`](Self::add_wordlist).
+    pub fn from_wordlist<R: BufRead>(reader: R) -> Result<Self> {
+        let mut builder = Self::default();
+        builder.add_wordlist(reader)?;
+        Ok(builder)
+    }
+}
+
+impl<B, T> ImmutableTrieBuilder<B, bool, T>
+where
+    B: ImmutableTrieNodeBuilder<bool>,
+    T: Tokenizer,
+{
+    /// Adds every key in a wordlist, which has one key per line. Blank lines and lines that start
+    /// with `#` are skipped, and whitespace around each key is ignored. If a key can't be added,
+    /// the error says which line it's on, starting at 1.
+    pub fn add_wordlist<R: BufRead>(&mut self, reader: R) -> Result<()> {
+        for (idx, line) in reader.lines().enumerate() {
+            let line = line?;
+            let key = line.trim();
+            if key.is_empty() || key.starts_with('#') {
+                continue;
+            }
+            self.add(key, true)
+                .context(InvalidLineSnafu { line: idx + 1 })?;
+        }
+        Ok(())
+    }
+}
@@ -1,6 +1,12 @@
+#[cfg(feature = "config")]
+pub mod config;
+#[cfg(feature = "test-corpora")]
+pub mod corpora;
 mod error;
+mod hashing;
 pub mod immutable;
 pub mod mutable;
+mod sealed;
 pub mod tokenization;
 
 pub use error::*;
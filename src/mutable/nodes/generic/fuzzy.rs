@@ -0,0 +1,149 @@
+use super::NodeKey;
+use crate::Result;
+use std::{fmt, mem, ops::Deref};
+
+const DEFAULT_MAX_DISTANCE: usize = 1;
+
+/// A key that matches any token within a Levenshtein distance of it, so `bobby` matches `bobyy`
+/// and `boby`. The distance defaults to 1, and can be set by ending the key with `~` and the
+/// distance (e.g. `bobby~2`).
+#[derive(Clone)]
+pub struct FuzzyNodeKey {
+    key: String,
+    max_distance: usize,
+}
+
+impl FuzzyNodeKey {
+    #[inline]
+    pub fn with_max_distance(mut self, max_distance: usize) -> Self {
+        self.max_distance = max_distance;
+        self
+    }
+
+    #[inline]
+    pub fn max_distance(&self) -> usize {
+        self.max_distance
+    }
+}
+
+impl fmt::Display for FuzzyNodeKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}~{}", self.key, self.max_distance)
+    }
+}
+
+impl fmt::Debug for FuzzyNodeKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FuzzyNodeKey")
+            .field("key", &self.key)
+            .field("max_distance", &self.max_distance)
+            .finish()
+    }
+}
+
+impl NodeKey for FuzzyNodeKey {
+    fn new<S: AsRef<str>>(key: S) -> Result<Self> {
+        let key = key.as_ref();
+        let (key, max_distance) = key
+            .rsplit_once('~')
+            .and_then(|(key, distance)| Some((key, distance.parse().ok()?)))
+            .unwrap_or((key, DEFAULT_MAX_DISTANCE));
+        Ok(Self {
+            key: String::from(key),
+            max_distance,
+        })
+    }
+
+    #[inline]
+    fn is_match<S: AsRef<str>>(&self, value: S) -> bool {
+        within_distance(&self.key, value.as_ref(), self.max_distance)
+    }
+}
+
+impl PartialEq for FuzzyNodeKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key && self.max_distance == other.max_distance
+    }
+}
+
+impl PartialEq<str> for FuzzyNodeKey {
+    fn eq(&self, other: &str) -> bool {
+        self.key == other
+    }
+}
+
+impl Deref for FuzzyNodeKey {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.key
+    }
+}
+
+/// Checks if the Levenshtein distance between `a` and `b` is at most `max_distance`, giving up
+/// as soon as every path is already further apart than that.
+fn within_distance(a: &str, b: &str, max_distance: usize) -> bool {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max_distance {
+        return false;
+    }
+    // previous[j] holds the distance between the first i - 1 chars of `a` and the first j of `b`.
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, a_char) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        if current.iter().all(|distance| *distance > max_distance) {
+            return false;
+        }
+        mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()] <= max_distance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_default_distance() {
+        let key = FuzzyNodeKey::new("bobby").unwrap();
+        assert_eq!(key.max_distance(), 1);
+        assert!(key.is_match("bobby"));
+        assert!(key.is_match("bobyy"));
+        assert!(key.is_match("boby"));
+        assert!(key.is_match("bobbys"));
+        assert!(!key.is_match("bob"));
+        assert!(!key.is_match("robyy"));
+    }
+
+    #[test]
+    fn test_fuzzy_distance_thresholds() {
+        let key = FuzzyNodeKey::new("kitten~3").unwrap();
+        assert_eq!(key.as_str(), "kitten");
+        // kitten -> sitting takes exactly three edits.
+        assert!(key.is_match("sitting"));
+        assert!(!key.clone().with_max_distance(2).is_match("sitting"));
+        let key = FuzzyNodeKey::new("exact~0").unwrap();
+        assert!(key.is_match("exact"));
+        assert!(!key.is_match("exacts"));
+        // A suffix that isn't a number is part of the key.
+        let key = FuzzyNodeKey::new("a~b").unwrap();
+        assert_eq!(key.as_str(), "a~b");
+        assert_eq!(key.max_distance(), 1);
+    }
+
+    #[test]
+    fn test_within_distance() {
+        assert!(within_distance("", "", 0));
+        assert!(within_distance("", "ab", 2));
+        assert!(!within_distance("", "abc", 2));
+        assert!(within_distance("flaw", "lawn", 2));
+        assert!(!within_distance("flaw", "lawn", 1));
+        assert!(within_distance("naïve", "naive", 1));
+    }
+}
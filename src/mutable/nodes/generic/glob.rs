@@ -0,0 +1,121 @@
+use super::{GenericTrieNode, NodeKey};
+use crate::{Result, error::pattern_compile};
+use globset::{GlobBuilder, GlobMatcher};
+use std::{fmt, ops::Deref};
+
+/// A key that's a glob pattern, such as `file-*` or `log?.txt`. Globs are much cheaper to build
+/// than [regexes](super::RegexNodeKey), which makes this a better fit for simple prefix and
+/// suffix patterns. Like regex keys, globs match the whole token and ignore case unless they're
+/// created [case-sensitively](Self::with_case_sensitive).
+#[derive(Clone)]
+pub struct GlobNodeKey(GlobMatcher);
+
+impl Deref for GlobNodeKey {
+    type Target = GlobMatcher;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl fmt::Display for GlobNodeKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.0.glob(), f)
+    }
+}
+
+impl fmt::Debug for GlobNodeKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.0.glob().glob(), f)
+    }
+}
+
+impl GlobNodeKey {
+    /// Like [`NodeKey::new`], but the glob is only matched case-insensitively if
+    /// `case_sensitive` isn't set.
+    pub fn with_case_sensitive<S: AsRef<str>>(key: S, case_sensitive: bool) -> Result<Self> {
+        Ok(Self(
+            GlobBuilder::new(key.as_ref())
+                .case_insensitive(!case_sensitive)
+                .build()
+                .map_err(pattern_compile(key.as_ref()))?
+                .compile_matcher(),
+        ))
+    }
+}
+
+impl NodeKey for GlobNodeKey {
+    #[inline]
+    fn new<S: AsRef<str>>(key: S) -> Result<Self> {
+        Self::with_case_sensitive(key, false)
+    }
+
+    #[inline]
+    fn is_match<S: AsRef<str>>(&self, value: S) -> bool {
+        self.0.is_match(value.as_ref())
+    }
+}
+
+impl PartialEq for GlobNodeKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.glob() == other.0.glob()
+    }
+}
+
+impl PartialEq<str> for GlobNodeKey {
+    fn eq(&self, other: &str) -> bool {
+        self.0.glob().glob() == other
+    }
+}
+
+impl<V> GenericTrieNode<GlobNodeKey, V> {
+    /// Like [`add`](super::MutableTrieNode::add), but the globs of new children are matched
+    /// case-sensitively if `case_sensitive` is set.
+    pub fn add_with_case_sensitive<S, I>(
+        &mut self,
+        globs: I,
+        value: V,
+        case_sensitive: bool,
+    ) -> Result<()>
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = S>,
+    {
+        self.add_with_keys(globs.into_iter(), value, &|glob| {
+            GlobNodeKey::with_case_sensitive(glob, case_sensitive)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    #[test]
+    fn test_glob_matching() {
+        let key = GlobNodeKey::new("file-*").unwrap();
+        assert!(key.is_match("file-1"));
+        assert!(key.is_match("FILE-abc"));
+        assert!(!key.is_match("my-file-1"));
+        let key = GlobNodeKey::new("log?.txt").unwrap();
+        assert!(key.is_match("log1.txt"));
+        assert!(!key.is_match("log12.txt"));
+        assert!(key == *"log?.txt");
+    }
+
+    #[test]
+    fn test_case_sensitive_glob_matching() {
+        let key = GlobNodeKey::with_case_sensitive("file-*", true).unwrap();
+        assert!(key.is_match("file-1"));
+        assert!(!key.is_match("FILE-abc"));
+    }
+
+    #[test]
+    fn test_invalid_glob() {
+        assert!(matches!(
+            GlobNodeKey::new("file-[a"),
+            Err(Error::PatternCompile { .. })
+        ));
+    }
+}
@@ -1,6 +1,7 @@
 use super::MutableTrieNode;
 use crate::{
     Result,
+    sealed::Sealed,
     tokenization::{BoundaryTokenizer, Tokenizer},
 };
 use educe::Educe;
@@ -12,14 +13,28 @@ use std::{
 };
 
 mod char;
+mod fuzzy;
+#[cfg(feature = "glob")]
+mod glob;
+mod numeric;
+mod predicate;
+mod prefix;
 #[cfg(feature = ("regex"))]
 mod regex;
 mod string;
+mod suffix;
 
 pub use char::*;
+pub use fuzzy::*;
+#[cfg(feature = "glob")]
+pub use glob::*;
+pub use numeric::*;
+pub use predicate::*;
+pub use prefix::*;
 #[cfg(feature = ("regex"))]
 pub use regex::*;
 pub use string::*;
+pub use suffix::*;
 
 #[derive(Clone)]
 pub struct GenericTrieNode<K, V>
@@ -90,8 +105,38 @@ where
     pub fn is_match<S: AsRef<str>>(&self, tokens: &[S]) -> bool {
         self.match_any(tokens).is_some()
     }
+
+    /// Like [`add`](MutableTrieNode::add), but the keys of new children are created with
+    /// `new_key` instead of [`NodeKey::new`].
+    fn add_with_keys<S, I, F>(&mut self, mut items_iter: I, value: V, new_key: &F) -> Result<()>
+    where
+        S: AsRef<str>,
+        I: Iterator<Item = S>,
+        F: Fn(&str) -> Result<K>,
+    {
+        let pattern = if let Some(part) = items_iter.next() {
+            String::from(part.as_ref())
+        } else {
+            self.value = Some(value);
+            return Ok(());
+        };
+        if !self.children.contains_key(&pattern) {
+            let child = GenericTrieNode {
+                key: new_key(&pattern)?,
+                value: None,
+                children: HashMap::new(),
+            };
+            self.children.insert(pattern.clone(), Box::new(child));
+        }
+        self.children
+            .get_mut(&pattern)
+            .unwrap()
+            .add_with_keys(items_iter, value, new_key)
+    }
 }
 
+impl<K: NodeKey, V> Sealed for GenericTrieNode<K, V> {}
+
 impl<K, V> MutableTrieNode<V> for GenericTrieNode<K, V>
 where
     K: NodeKey,
@@ -122,25 +167,12 @@ where
                 .sum::<usize>()
     }
 
-    fn add<S, I>(&mut self, mut items_iter: I, value: V) -> Result<()>
+    fn add<S, I>(&mut self, items_iter: I, value: V) -> Result<()>
     where
         S: AsRef<str>,
         I: Iterator<Item = S>,
     {
-        let pattern = if let Some(part) = items_iter.next() {
-            String::from(part.as_ref())
-        } else {
-            self.value = Some(value);
-            return Ok(());
-        };
-        if !self.children.contains_key(&pattern) {
-            let child = GenericTrieNode::new(&pattern)?;
-            self.children.insert(pattern.clone(), Box::new(child));
-        }
-        self.children
-            .get_mut(&pattern)
-            .unwrap()
-            .add(items_iter, value)
+        self.add_with_keys(items_iter, value, &|pattern| K::new(pattern))
     }
 }
 
@@ -158,6 +190,8 @@ where
     }
 }
 
+/// How a [`GenericTrieNode`] matches tokens against its keys. Unlike the node traits, this is
+/// meant to be implemented outside of this crate.
 pub trait NodeKey: Sized + Clone + fmt::Debug + PartialEq<str> {
     fn new<S: AsRef<str>>(key: S) -> Result<Self>;
     fn is_match<S: AsRef<str>>(&self, value: S) -> bool;
@@ -0,0 +1,135 @@
+use super::NodeKey;
+use crate::{Result, error::InvalidNumericRangeSnafu};
+use std::{
+    fmt,
+    ops::{Bound, RangeBounds},
+};
+
+/// A key that matches any token that's a number within a range. Ranges use Rust's syntax, so
+/// `100..500` excludes 500, `100..=500` includes it, and either end can be left off (e.g.
+/// `500..`). A single number, such as `503`, only matches that number. Integers and floats can
+/// both be used, and tokens that aren't numbers never match.
+#[derive(Clone)]
+pub struct NumericRangeNodeKey {
+    key: String,
+    start: Bound<f64>,
+    end: Bound<f64>,
+}
+
+impl NumericRangeNodeKey {
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.key
+    }
+
+    fn parse_bound(bound: &str, inclusive: bool) -> Option<Bound<f64>> {
+        let bound = bound.trim();
+        if bound.is_empty() {
+            return Some(Bound::Unbounded);
+        }
+        let number: f64 = bound.parse().ok().filter(|n: &f64| !n.is_nan())?;
+        Some(if inclusive {
+            Bound::Included(number)
+        } else {
+            Bound::Excluded(number)
+        })
+    }
+}
+
+impl fmt::Display for NumericRangeNodeKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.key, f)
+    }
+}
+
+impl fmt::Debug for NumericRangeNodeKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("NumericRangeNodeKey")
+            .field(&self.key)
+            .finish()
+    }
+}
+
+impl NodeKey for NumericRangeNodeKey {
+    fn new<S: AsRef<str>>(key: S) -> Result<Self> {
+        let key = key.as_ref();
+        let bounds = if let Some((start, end)) = key.split_once("..=") {
+            Self::parse_bound(start, true).zip(Self::parse_bound(end, true))
+        } else if let Some((start, end)) = key.split_once("..") {
+            Self::parse_bound(start, true).zip(Self::parse_bound(end, false))
+        } else {
+            Self::parse_bound(key, true)
+                .filter(|bound| *bound != Bound::Unbounded)
+                .map(|bound| (bound, bound))
+        };
+        let (start, end) = bounds.ok_or_else(|| InvalidNumericRangeSnafu { key }.build())?;
+        Ok(Self {
+            key: String::from(key),
+            start,
+            end,
+        })
+    }
+
+    #[inline]
+    fn is_match<S: AsRef<str>>(&self, value: S) -> bool {
+        value
+            .as_ref()
+            .parse::<f64>()
+            .is_ok_and(|n| (self.start, self.end).contains(&n))
+    }
+}
+
+impl PartialEq for NumericRangeNodeKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl PartialEq<str> for NumericRangeNodeKey {
+    fn eq(&self, other: &str) -> bool {
+        self.key == other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    #[test]
+    fn test_numeric_ranges() {
+        let key = NumericRangeNodeKey::new("100..500").unwrap();
+        assert!(key.is_match("100"));
+        assert!(key.is_match("499.5"));
+        assert!(!key.is_match("500"));
+        assert!(!key.is_match("99"));
+        assert!(!key.is_match("abc"));
+        let key = NumericRangeNodeKey::new("100..=500").unwrap();
+        assert!(key.is_match("500"));
+        assert!(!key.is_match("500.1"));
+        let key = NumericRangeNodeKey::new("500..").unwrap();
+        assert!(key.is_match("1e9"));
+        assert!(!key.is_match("499"));
+        let key = NumericRangeNodeKey::new("..=-1").unwrap();
+        assert!(key.is_match("-1"));
+        assert!(!key.is_match("0"));
+        let key = NumericRangeNodeKey::new("503").unwrap();
+        assert!(key.is_match("503"));
+        assert!(key.is_match("503.0"));
+        assert!(!key.is_match("504"));
+        assert!(!key.is_match("NaN"));
+    }
+
+    #[test]
+    fn test_invalid_numeric_ranges() {
+        for key in ["", "abc", "1..abc", "x..=2", "NaN", "NaN..1", "1..2..3"] {
+            assert!(
+                matches!(
+                    NumericRangeNodeKey::new(key),
+                    Err(Error::InvalidNumericRange { .. })
+                ),
+                "{key:?} should be rejected"
+            );
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use super::{GenericTrieNode, NodeKey};
+use crate::{Result, error::UnknownPredicateSnafu};
+use std::{collections::HashMap, fmt, sync::Arc};
+
+pub type Predicate = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Predicates that can be used as keys by their label. Tries of [`FnNodeKey`]s are built with
+/// [`GenericTrieNode::with_predicate`] and [`GenericTrieNode::add_predicates`], which look up
+/// each token of a key in the registry they're given.
+#[derive(Clone, Default)]
+pub struct PredicateRegistry {
+    predicates: HashMap<String, Predicate>,
+}
+
+impl PredicateRegistry {
+    /// Makes `predicate` available to keys with the given label, replacing whatever predicate
+    /// the label had before. Keys that were already created keep their old predicate.
+    pub fn register<S, F>(&mut self, label: S, predicate: F)
+    where
+        S: Into<String>,
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.predicates.insert(label.into(), Arc::new(predicate));
+    }
+
+    /// Creates a key from the predicate registered under `label`.
+    pub fn key<S: AsRef<str>>(&self, label: S) -> Result<FnNodeKey> {
+        let label = label.as_ref();
+        let predicate = self
+            .predicates
+            .get(label)
+            .cloned()
+            .ok_or_else(|| UnknownPredicateSnafu { label }.build())?;
+        Ok(FnNodeKey {
+            label: String::from(label),
+            predicate,
+        })
+    }
+}
+
+impl fmt::Debug for PredicateRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.predicates.keys()).finish()
+    }
+}
+
+/// A key that matches any token an arbitrary function accepts. Since keys are created from the
+/// tokens they were added with, predicates have to be registered under a label in a
+/// [`PredicateRegistry`] first, and then the label is used as the key's token.
+#[derive(Clone)]
+pub struct FnNodeKey {
+    label: String,
+    predicate: Predicate,
+}
+
+impl FnNodeKey {
+    /// Creates a key without registering its predicate.
+    pub fn from_fn<S, F>(label: S, predicate: F) -> Self
+    where
+        S: Into<String>,
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            label: label.into(),
+            predicate: Arc::new(predicate),
+        }
+    }
+
+    #[inline]
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+impl fmt::Display for FnNodeKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.label, f)
+    }
+}
+
+impl fmt::Debug for FnNodeKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FnNodeKey").field(&self.label).finish()
+    }
+}
+
+impl NodeKey for FnNodeKey {
+    /// Always fails, since a label can't be turned into a predicate without a
+    /// [`PredicateRegistry`]. Use [`PredicateRegistry::key`] instead.
+    fn new<S: AsRef<str>>(key: S) -> Result<Self> {
+        UnknownPredicateSnafu {
+            label: key.as_ref(),
+        }
+        .fail()
+    }
+
+    #[inline]
+    fn is_match<S: AsRef<str>>(&self, value: S) -> bool {
+        (self.predicate)(value.as_ref())
+    }
+}
+
+/// Keys are compared by their labels, since functions can't be compared.
+impl PartialEq for FnNodeKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.label == other.label
+    }
+}
+
+impl PartialEq<str> for FnNodeKey {
+    fn eq(&self, other: &str) -> bool {
+        self.label == other
+    }
+}
+
+impl<V> GenericTrieNode<FnNodeKey, V> {
+    /// Creates a node whose key is the predicate registered under `label`.
+    pub fn with_predicate<S: AsRef<str>>(label: S, registry: &PredicateRegistry) -> Result<Self> {
+        Ok(Self {
+            key: registry.key(label)?,
+            value: None,
+            children: HashMap::new(),
+        })
+    }
+
+    /// Adds a key whose tokens are the labels of predicates in `registry`.
+    pub fn add_predicates<S, I>(
+        &mut self,
+        labels: I,
+        value: V,
+        registry: &PredicateRegistry,
+    ) -> Result<()>
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = S>,
+    {
+        self.add_with_keys(labels.into_iter(), value, &|label| registry.key(label))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    fn registry() -> PredicateRegistry {
+        let mut registry = PredicateRegistry::default();
+        registry.register("digits", |token| {
+            !token.is_empty() && token.chars().all(|c| c.is_ascii_digit())
+        });
+        registry.register("any", |_| true);
+        registry
+    }
+
+    #[test]
+    fn test_registered_predicates() {
+        let registry = registry();
+        let key = registry.key("digits").unwrap();
+        assert_eq!(key.label(), "digits");
+        assert!(key.is_match("123"));
+        assert!(!key.is_match("12a"));
+        assert!(!key.is_match(""));
+        assert!(matches!(
+            registry.key("unregistered"),
+            Err(Error::UnknownPredicate { .. })
+        ));
+        assert!(matches!(
+            FnNodeKey::new("digits"),
+            Err(Error::UnknownPredicate { .. })
+        ));
+        // Registries are separate from each other.
+        assert!(PredicateRegistry::default().key("digits").is_err());
+    }
+
+    #[test]
+    fn test_predicate_trie() {
+        let registry = registry();
+        let mut node = GenericTrieNode::with_predicate("any", &registry).unwrap();
+        node.add_predicates(["digits"], "number", &registry)
+            .unwrap();
+        node.add_predicates(["any", "digits"], "then number", &registry)
+            .unwrap();
+        assert!(matches!(
+            node.add_predicates(["missing"], "missing", &registry),
+            Err(Error::UnknownPredicate { .. })
+        ));
+        assert_eq!(node.match_all(&["id", "42"]), vec![&"number"]);
+        assert_eq!(node.match_all(&["id", "x", "42"]), vec![&"then number"]);
+        assert!(node.match_all(&["id", "x"]).is_empty());
+    }
+
+    #[test]
+    fn test_predicates_from_fns() {
+        let key = FnNodeKey::from_fn("short", |token| token.len() < 4);
+        assert!(key.is_match("abc"));
+        assert!(!key.is_match("abcd"));
+        assert!(key == FnNodeKey::from_fn("short", |_| false));
+    }
+}
@@ -0,0 +1,69 @@
+use super::NodeKey;
+use crate::Result;
+use std::{fmt, ops::Deref};
+
+/// A key that matches any token starting with it, so `connect` matches `connect`, `connected`,
+/// and `connection`. Like [`StringNodeKey`](super::StringNodeKey), the comparison is
+/// case-sensitive.
+#[derive(Clone)]
+pub struct PrefixNodeKey(String);
+
+impl fmt::Display for PrefixNodeKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}*", self.0)
+    }
+}
+
+impl fmt::Debug for PrefixNodeKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PrefixNodeKey").field(&self.0).finish()
+    }
+}
+
+impl NodeKey for PrefixNodeKey {
+    #[inline]
+    fn new<S: AsRef<str>>(key: S) -> Result<Self> {
+        Ok(Self(String::from(key.as_ref())))
+    }
+
+    #[inline]
+    fn is_match<S: AsRef<str>>(&self, value: S) -> bool {
+        value.as_ref().starts_with(self.0.as_str())
+    }
+}
+
+impl PartialEq for PrefixNodeKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl PartialEq<str> for PrefixNodeKey {
+    fn eq(&self, other: &str) -> bool {
+        self.0.as_str() == other
+    }
+}
+
+impl Deref for PrefixNodeKey {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_matching() {
+        let key = PrefixNodeKey::new("connect").unwrap();
+        assert!(key.is_match("connect"));
+        assert!(key.is_match("connection"));
+        assert!(!key.is_match("Connection"));
+        assert!(!key.is_match("conn"));
+        assert!(!key.is_match("reconnect"));
+        assert_eq!(key.to_string(), "connect*");
+    }
+}
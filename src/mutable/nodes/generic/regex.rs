@@ -1,3 +1,5 @@
+use super::{GenericTrieNode, NodeKey};
+use crate::{Result, error::pattern_compile};
 use regex::{Regex, RegexBuilder};
 use std::{
     collections::{BTreeSet, HashMap},
@@ -29,16 +31,25 @@ impl fmt::Debug for RegexNodeKey {
     }
 }
 
-impl NodeKey for RegexNodeKey {
-    #[inline]
-    fn new<S: AsRef<str>>(key: S) -> Result<Self> {
+impl RegexNodeKey {
+    /// Like [`NodeKey::new`], but the pattern is only matched case-insensitively if
+    /// `case_sensitive` isn't set.
+    pub fn with_case_sensitive<S: AsRef<str>>(key: S, case_sensitive: bool) -> Result<Self> {
         Ok(Self(
             RegexBuilder::new(&format!("^{}$", key.as_ref()))
                 .unicode(true)
-                .case_insensitive(true)
-                .build()?,
+                .case_insensitive(!case_sensitive)
+                .build()
+                .map_err(pattern_compile(key.as_ref()))?,
         ))
     }
+}
+
+impl NodeKey for RegexNodeKey {
+    #[inline]
+    fn new<S: AsRef<str>>(key: S) -> Result<Self> {
+        Self::with_case_sensitive(key, false)
+    }
 
     #[inline]
     fn is_match<S: AsRef<str>>(&self, value: S) -> bool {
@@ -63,3 +74,22 @@ impl<'a> PartialEq<&'a str> for RegexNodeKey {
         self.0.as_str() == *other
     }
 }
+
+impl<V> GenericTrieNode<RegexNodeKey, V> {
+    /// Like [`add`](super::MutableTrieNode::add), but the patterns of new children are matched
+    /// case-sensitively if `case_sensitive` is set.
+    pub fn add_with_case_sensitive<S, I>(
+        &mut self,
+        patterns: I,
+        value: V,
+        case_sensitive: bool,
+    ) -> Result<()>
+    where
+        S: AsRef<str>,
+        I: IntoIterator<Item = S>,
+    {
+        self.add_with_keys(patterns.into_iter(), value, &|pattern| {
+            RegexNodeKey::with_case_sensitive(pattern, case_sensitive)
+        })
+    }
+}
@@ -0,0 +1,68 @@
+use super::NodeKey;
+use crate::Result;
+use std::{fmt, ops::Deref};
+
+/// A key that matches any token ending with it, so `ing` matches `ing`, `testing`, and
+/// `matching`. Like [`StringNodeKey`](super::StringNodeKey), the comparison is case-sensitive.
+#[derive(Clone)]
+pub struct SuffixNodeKey(String);
+
+impl fmt::Display for SuffixNodeKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "*{}", self.0)
+    }
+}
+
+impl fmt::Debug for SuffixNodeKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SuffixNodeKey").field(&self.0).finish()
+    }
+}
+
+impl NodeKey for SuffixNodeKey {
+    #[inline]
+    fn new<S: AsRef<str>>(key: S) -> Result<Self> {
+        Ok(Self(String::from(key.as_ref())))
+    }
+
+    #[inline]
+    fn is_match<S: AsRef<str>>(&self, value: S) -> bool {
+        value.as_ref().ends_with(self.0.as_str())
+    }
+}
+
+impl PartialEq for SuffixNodeKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl PartialEq<str> for SuffixNodeKey {
+    fn eq(&self, other: &str) -> bool {
+        self.0.as_str() == other
+    }
+}
+
+impl Deref for SuffixNodeKey {
+    type Target = String;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suffix_matching() {
+        let key = SuffixNodeKey::new("ing").unwrap();
+        assert!(key.is_match("ing"));
+        assert!(key.is_match("testing"));
+        assert!(!key.is_match("TESTING"));
+        assert!(!key.is_match("ingest"));
+        assert!(!key.is_match("in"));
+        assert_eq!(key.to_string(), "*ing");
+    }
+}
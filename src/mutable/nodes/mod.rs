@@ -1,4 +1,4 @@
-use crate::Result;
+use crate::{Result, sealed::Sealed};
 
 mod char;
 mod generic;
@@ -12,7 +12,9 @@ pub use generic::*;
 pub use regex::*;
 pub use string::*;
 
-pub trait MutableTrieNode<V> {
+/// A node of a mutable trie. This is sealed, so it's only implemented by the nodes in this crate;
+/// a [`GenericTrieNode`] can match keys in custom ways with a [`NodeKey`] instead.
+pub trait MutableTrieNode<V>: Sealed {
     fn add<S, I>(&mut self, items_iter: I, value: V) -> Result<()>
     where
         S: AsRef<str>,
@@ -62,6 +64,23 @@ pub trait MutableTrieNode<V> {
         child.value()
     }
 
+    /// Gets the value stored under exactly the given tokens. Unlike [`match_any`](Self::match_any),
+    /// every token must be consumed and only the value of the final node is returned.
+    fn match_exact<S: AsRef<str>>(&self, tokens: &[S]) -> Option<&V> {
+        match tokens.split_first() {
+            Some((token, rest)) => self
+                .match_children(token)
+                .into_iter()
+                .find_map(|child| child.match_exact(rest)),
+            None => self.value(),
+        }
+    }
+
+    #[inline]
+    fn contains_key<S: AsRef<str>>(&self, tokens: &[S]) -> bool {
+        self.match_exact(tokens).is_some()
+    }
+
     fn match_all<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<&V> {
         let mut values = Vec::new();
         if let Some(token) = tokens.first() {
@@ -1,5 +1,5 @@
 use super::MutableTrieNode;
-use crate::Result;
+use crate::{Result, error::pattern_compile, sealed::Sealed};
 use regex::{Regex, RegexBuilder};
 use std::{borrow::BorrowMut, collections::HashMap, fmt, ops::Deref};
 
@@ -7,9 +7,26 @@ use std::{borrow::BorrowMut, collections::HashMap, fmt, ops::Deref};
 pub struct RegexTrieNode<V> {
     value: Option<V>,
     children: HashMap<String, Box<Self>>,
+    case_sensitive: bool,
 }
 
 impl<V> RegexTrieNode<V> {
+    /// Sets whether the patterns of new children are matched case-sensitively. Children inherit
+    /// this from the node they're added to.
+    #[inline]
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Creates an empty child that matches case the same way this node does.
+    fn new_child(&self) -> Self {
+        Self {
+            case_sensitive: self.case_sensitive,
+            ..Self::default()
+        }
+    }
+
     fn get_child_mut<S: AsRef<str>>(&mut self, token: S) -> Option<&mut Self> {
         self.children
             .iter_mut()
@@ -18,6 +35,8 @@ impl<V> RegexTrieNode<V> {
     }
 }
 
+impl<V> Sealed for RegexTrieNode<V> {}
+
 impl<V> MutableTrieNode<V> for RegexTrieNode<V> {
     fn add<S, I>(&mut self, mut items_iter: I, value: V) -> Result<()>
     where
@@ -36,18 +55,19 @@ impl<V> MutableTrieNode<V> for RegexTrieNode<V> {
         let mut child = if let Some(child) = self.children.entry(pattern.as_str()) {
             child
         } else {
-            let child = Self::default();
+            let child = self.new_child();
             let regex = RegexBuilder::new(&pattern)
-                .case_insensitive(true)
+                .case_insensitive(!self.case_sensitive)
                 .unicode(true)
-                .build()?;
+                .build()
+                .map_err(pattern_compile(&pattern))?;
             self.children.insert(regex, Box::new(child));
             self.children.get_mut(idx).unwrap()
         };
         let mut child = if let Some(child) = self.get_child_mut(&key) {
             child
         } else {
-            let child = Self::default();
+            let child = self.new_child();
             self.children
                 .entry(&key)
                 .or_default()
@@ -96,6 +116,7 @@ impl<V> Default for RegexTrieNode<V> {
         Self {
             value: None,
             children: Default::default(),
+            case_sensitive: false,
         }
     }
 }
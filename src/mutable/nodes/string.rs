@@ -1,5 +1,5 @@
 use super::MutableTrieNode;
-use crate::Result;
+use crate::{Result, sealed::Sealed};
 use std::{borrow::BorrowMut as _, collections::HashMap, fmt};
 
 #[derive(Clone)]
@@ -25,6 +25,8 @@ impl<V> Default for StringTrieNode<V> {
     }
 }
 
+impl<V> Sealed for StringTrieNode<V> {}
+
 impl<V> MutableTrieNode<V> for StringTrieNode<V> {
     fn add<S, I>(&mut self, mut items_iter: I, value: V) -> Result<()>
     where
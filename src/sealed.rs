@@ -0,0 +1,9 @@
+//! Traits in this crate that have `Sealed` as a supertrait can only be implemented inside of it,
+//! which lets new methods be added to them without breaking anything downstream. The traits that
+//! are meant to be implemented by users aren't sealed: [`Tokenizer`](crate::Tokenizer),
+//! [`SpannedTokenizer`](crate::SpannedTokenizer), [`TokenStage`](crate::TokenStage) and
+//! [`NodeKey`](crate::NodeKey). Extension traits such as [`TokenizerExt`](crate::TokenizerExt) are
+//! implemented for everything that can use them instead, so they can't be implemented by hand
+//! either.
+
+pub trait Sealed {}
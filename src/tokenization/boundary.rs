@@ -1,8 +1,25 @@
-use super::Tokenizer;
+use super::{
+    SpannedTokenizer, Tokenizer, TokenizerExt, hash_config_str, offset_in, to_lowercase_cow,
+};
 use convert_case::Boundary;
+use std::{borrow::Cow, hash::Hasher, ops::Range};
+
+/// The delimiters a [`BoundaryTokenizer`] splits on by default, on top of convert_case's
+/// [default boundaries](Boundary::defaults).
+pub const DEFAULT_TOKEN_DELIMS: [&str; 23] = [
+    ".", ",", ":", ";", "/", "\\", "(", ")", "[", "]", "{", "}", "<", ">", "\"", "'", "!", "?",
+    "|", "=", "\t", "\n", "\r",
+];
 
 #[derive(Debug, Clone)]
-pub struct BoundaryTokenizer(Vec<Boundary>);
+pub struct BoundaryTokenizer {
+    boundaries: Vec<Boundary>,
+    /// Split on before the boundaries are applied. These could be boundaries too (see
+    /// [`Boundary::from_delim`]), but convert_case checks those against the whole rest of the
+    /// string at every position, which makes long strings very slow to tokenize.
+    delims: Vec<&'static str>,
+    case_sensitive: bool,
+}
 
 impl BoundaryTokenizer {
     #[inline]
@@ -10,23 +27,218 @@ impl BoundaryTokenizer {
     where
         I: IntoIterator<Item = Boundary>,
     {
-        Self(boundaries.into_iter().collect())
+        Self {
+            boundaries: boundaries.into_iter().collect(),
+            delims: Vec::new(),
+            case_sensitive: false,
+        }
+    }
+
+    /// Starts building a tokenizer, which is useful for tweaking the default boundaries and
+    /// delimiters. The builder starts out without any of them.
+    #[inline]
+    pub fn builder() -> BoundaryTokenizerBuilder {
+        BoundaryTokenizerBuilder::default()
+    }
+
+    #[inline]
+    pub fn boundaries(&self) -> &[Boundary] {
+        &self.boundaries
+    }
+
+    #[inline]
+    pub fn delims(&self) -> &[&'static str] {
+        &self.delims
+    }
+
+    /// Sets whether the case of each token is kept as-is. By default, tokens are lowercased.
+    #[inline]
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    #[inline]
+    pub fn is_case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    #[inline]
+    fn normalize<'s>(&self, token: &'s str) -> Cow<'s, str> {
+        if self.case_sensitive {
+            Cow::Borrowed(token)
+        } else {
+            to_lowercase_cow(token)
+        }
+    }
+
+    /// Splits the string on the delimiters. The pieces are found lazily, and some of them may be
+    /// empty.
+    fn pieces<'s>(&self, source: &'s str) -> impl Iterator<Item = &'s str> {
+        let (mut start, mut i, mut done) = (0, 0, false);
+        std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            while let Some(c) = source[i..].chars().next() {
+                let rest = &source[i..];
+                match self
+                    .delims
+                    .iter()
+                    .find(|delim| !delim.is_empty() && rest.starts_with(**delim))
+                {
+                    Some(delim) => {
+                        let piece = &source[start..i];
+                        i += delim.len();
+                        start = i;
+                        return Some(piece);
+                    }
+                    None => i += c.len_utf8(),
+                }
+            }
+            done = true;
+            Some(&source[start..])
+        })
+    }
+
+    /// Splits the string on the delimiters, and then splits each piece on the boundaries. The
+    /// byte span of each part is returned. Each piece is only split on the boundaries once the
+    /// parts before it have been used, so stopping early skips the rest of the string.
+    fn split(&self, source: &str) -> impl Iterator<Item = Range<usize>> {
+        self.pieces(source)
+            .filter(|piece| !piece.is_empty())
+            .flat_map(move |piece| {
+                convert_case::split(&piece, &self.boundaries)
+                    .into_iter()
+                    .filter(|part| !part.is_empty())
+                    .map(|part| {
+                        let start = offset_in(source, part);
+                        start..start + part.len()
+                    })
+                    .collect::<Vec<_>>()
+            })
     }
 }
 
-//impl Default for BoundaryTokenizer {
-//    #[inline]
-//    fn default() -> Self {
-//        Self::new(DEFAULT_TOKEN_BOUNDARIES)
-//    }
-//}
+impl Default for BoundaryTokenizer {
+    #[inline]
+    fn default() -> Self {
+        Self::builder().with_default().build()
+    }
+}
+
+/// Builds a [`BoundaryTokenizer`]. See [`BoundaryTokenizer::builder`].
+#[derive(Debug, Clone, Default)]
+pub struct BoundaryTokenizerBuilder {
+    boundaries: Vec<Boundary>,
+    delims: Vec<&'static str>,
+    case_sensitive: bool,
+}
+
+impl BoundaryTokenizerBuilder {
+    /// Adds convert_case's [default boundaries](Boundary::defaults) and the
+    /// [default delimiters](DEFAULT_TOKEN_DELIMS).
+    #[inline]
+    pub fn with_default(self) -> Self {
+        self.add_boundaries(Boundary::defaults())
+            .add_delims(DEFAULT_TOKEN_DELIMS)
+    }
+
+    /// Adds boundaries, skipping any that were already added.
+    pub fn add_boundaries<I>(mut self, boundaries: I) -> Self
+    where
+        I: IntoIterator<Item = Boundary>,
+    {
+        for boundary in boundaries {
+            if !self.boundaries.contains(&boundary) {
+                self.boundaries.push(boundary);
+            }
+        }
+        self
+    }
+
+    /// Adds delimiters, which are removed from the tokens they split. Delimiters that were
+    /// already added are skipped.
+    pub fn add_delims<I>(mut self, delims: I) -> Self
+    where
+        I: IntoIterator<Item = &'static str>,
+    {
+        for delim in delims {
+            if !self.delims.contains(&delim) {
+                self.delims.push(delim);
+            }
+        }
+        self
+    }
+
+    /// Removes a boundary, if it was added.
+    pub fn remove_boundary(mut self, boundary: Boundary) -> Self {
+        self.boundaries.retain(|other| *other != boundary);
+        self
+    }
+
+    /// Removes a delimiter, if it was added, such as `.` to keep version numbers together.
+    pub fn remove_delim(mut self, delim: &str) -> Self {
+        self.delims.retain(|other| *other != delim);
+        self
+    }
+
+    /// Sets whether the case of each token is kept as-is. By default, tokens are lowercased.
+    #[inline]
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    #[inline]
+    pub fn build(self) -> BoundaryTokenizer {
+        BoundaryTokenizer {
+            boundaries: self.boundaries,
+            delims: self.delims,
+            case_sensitive: self.case_sensitive,
+        }
+    }
+}
 
 impl Tokenizer for BoundaryTokenizer {
     fn tokenize<S: AsRef<str>>(&self, s: S) -> Vec<String> {
-        convert_case::split(&s, &self.0)
+        let source = s.as_ref();
+        self.split(source)
+            .map(|span| self.normalize(&source[span]).into_owned())
+            .collect()
+    }
+
+    #[inline]
+    fn tokenize_borrowed<'s>(&self, s: &'s str) -> Vec<Cow<'s, str>> {
+        self.tokenize_iter(s).collect()
+    }
+
+    #[inline]
+    fn tokenize_lazily<'s>(&self, s: &'s str) -> impl Iterator<Item = Cow<'s, str>> {
+        self.split(s).map(move |span| self.normalize(&s[span]))
+    }
+
+    fn write_config(&self, state: &mut dyn Hasher) {
+        hash_config_str(state, "boundary");
+        // Boundaries are compared by name, so the name and argument are all that tell them apart.
+        state.write_u64(self.boundaries.len() as u64);
+        for boundary in &self.boundaries {
+            hash_config_str(state, boundary.name);
+            hash_config_str(state, boundary.arg.unwrap_or_default());
+        }
+        state.write_u64(self.delims.len() as u64);
+        self.delims
             .iter()
-            .filter(|s| !s.is_empty())
-            .map(|s| s.to_lowercase())
+            .for_each(|delim| hash_config_str(state, delim));
+        state.write_u8(u8::from(self.case_sensitive));
+    }
+}
+
+impl SpannedTokenizer for BoundaryTokenizer {
+    fn tokenize_spanned<S: AsRef<str>>(&self, s: S) -> Vec<(String, Range<usize>)> {
+        let source = s.as_ref();
+        self.split(source)
+            .map(|span| (self.normalize(&source[span.clone()]).into_owned(), span))
             .collect()
     }
 }
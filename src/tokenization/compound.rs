@@ -0,0 +1,130 @@
+use super::{SpannedTokenizer, Tokenizer, TokenizerExt, hash_config_str};
+use std::{borrow::Cow, collections::HashSet, hash::Hasher, ops::Range};
+
+const DEFAULT_MIN_LEN: usize = 6;
+
+/// Wraps another tokenizer and splits compound tokens that aren't in a vocabulary into tokens
+/// that are (e.g. `creditcardnumber` into `credit card number`). Tokens that can't be completely
+/// split into known tokens are left alone.
+#[derive(Debug, Clone)]
+pub struct CompoundSplitter<T: Tokenizer> {
+    inner: T,
+    vocabulary: HashSet<String>,
+    min_len: usize,
+}
+
+impl<T: Tokenizer> CompoundSplitter<T> {
+    pub fn new<I, S>(inner: T, vocabulary: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            inner,
+            vocabulary: vocabulary.into_iter().map(Into::into).collect(),
+            min_len: DEFAULT_MIN_LEN,
+        }
+    }
+
+    /// Sets the minimum length (in bytes) a token needs to be before it's split.
+    #[inline]
+    pub fn with_min_len(mut self, min_len: usize) -> Self {
+        self.min_len = min_len;
+        self
+    }
+
+    #[inline]
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn vocabulary(&self) -> &HashSet<String> {
+        &self.vocabulary
+    }
+
+    /// Splits a token into the fewest number of vocabulary tokens possible.
+    pub fn split(&self, token: &str) -> Option<Vec<String>> {
+        if token.len() < self.min_len || self.vocabulary.contains(token) {
+            return None;
+        }
+        let boundaries: Vec<usize> = token
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain([token.len()])
+            .collect();
+        // best[i] holds the fewest pieces token[..boundaries[i]] splits into, and where the last
+        // piece starts.
+        let mut best: Vec<Option<(usize, usize)>> = vec![None; boundaries.len()];
+        best[0] = Some((0, 0));
+        for end in 1..boundaries.len() {
+            best[end] = (0..end)
+                .filter_map(|start| {
+                    let (pieces, _) = best[start]?;
+                    self.vocabulary
+                        .contains(&token[boundaries[start]..boundaries[end]])
+                        .then_some((pieces + 1, start))
+                })
+                .min();
+        }
+        let mut pieces = Vec::new();
+        let mut end = boundaries.len() - 1;
+        while end > 0 {
+            let (_, start) = best[end]?;
+            pieces.push(String::from(&token[boundaries[start]..boundaries[end]]));
+            end = start;
+        }
+        pieces.reverse();
+        (pieces.len() > 1).then_some(pieces)
+    }
+}
+
+impl<T: Tokenizer> Tokenizer for CompoundSplitter<T> {
+    fn tokenize<S: AsRef<str>>(&self, s: S) -> Vec<String> {
+        self.inner
+            .tokenize(s)
+            .into_iter()
+            .flat_map(|token| self.split(&token).unwrap_or_else(|| vec![token]))
+            .collect()
+    }
+
+    fn tokenize_lazily<'s>(&self, s: &'s str) -> impl Iterator<Item = Cow<'s, str>> {
+        self.inner
+            .tokenize_iter(s)
+            .flat_map(|token| match self.split(&token) {
+                Some(parts) => parts.into_iter().map(Cow::Owned).collect(),
+                None => vec![token],
+            })
+    }
+
+    fn write_config(&self, state: &mut dyn Hasher) {
+        hash_config_str(state, "compound");
+        let mut vocabulary: Vec<_> = self.vocabulary.iter().collect();
+        vocabulary.sort_unstable();
+        state.write_u64(vocabulary.len() as u64);
+        vocabulary
+            .into_iter()
+            .for_each(|word| hash_config_str(state, word));
+        state.write_u64(self.min_len as u64);
+        self.inner.hash_config(state);
+    }
+}
+
+impl<T: SpannedTokenizer> SpannedTokenizer for CompoundSplitter<T> {
+    fn tokenize_spanned<S: AsRef<str>>(&self, s: S) -> Vec<(String, Range<usize>)> {
+        let mut tokens = Vec::new();
+        for (token, span) in self.inner.tokenize_spanned(s) {
+            let Some(pieces) = self.split(&token) else {
+                tokens.push((token, span));
+                continue;
+            };
+            let mut start = span.start;
+            for piece in pieces {
+                let end = (start + piece.len()).min(span.end);
+                tokens.push((piece, start..end));
+                start = end;
+            }
+        }
+        tokens
+    }
+}
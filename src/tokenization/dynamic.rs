@@ -0,0 +1,77 @@
+use super::{SpannedTokenizer, Tokenizer, TokenizerExt};
+use std::{borrow::Cow, hash::Hasher, ops::Range, rc::Rc, sync::Arc};
+
+/// An object-safe version of [`Tokenizer`], so tokenizers can be chosen at runtime and stored as
+/// `Box<dyn DynTokenizer>`. Every [`Tokenizer`] is a `DynTokenizer`, and boxed (or otherwise
+/// pointed to) `DynTokenizer`s are [`Tokenizer`]s again, so they can be used anywhere a tokenizer
+/// can, such as with an [`ImmutableTrie`](crate::immutable::ImmutableTrie). Since every
+/// [`Tokenizer`] already implements it, this never needs to be implemented by hand.
+pub trait DynTokenizer {
+    fn tokenize_dyn(&self, s: &str) -> Vec<String>;
+
+    fn tokenize_ref_dyn<'s>(&self, s: &'s str) -> Vec<Cow<'s, str>>;
+
+    fn hash_config_dyn(&self, state: &mut dyn Hasher);
+}
+
+impl<T: Tokenizer> DynTokenizer for T {
+    #[inline]
+    fn tokenize_dyn(&self, s: &str) -> Vec<String> {
+        self.tokenize(s)
+    }
+
+    #[inline]
+    fn tokenize_ref_dyn<'s>(&self, s: &'s str) -> Vec<Cow<'s, str>> {
+        self.tokenize_ref(s)
+    }
+
+    #[inline]
+    fn hash_config_dyn(&self, state: &mut dyn Hasher) {
+        self.hash_config(state)
+    }
+}
+
+/// An object-safe version of [`SpannedTokenizer`], which like [`DynTokenizer`] is implemented for
+/// every [`SpannedTokenizer`].
+pub trait DynSpannedTokenizer: DynTokenizer {
+    fn tokenize_spanned_dyn(&self, s: &str) -> Vec<(String, Range<usize>)>;
+}
+
+impl<T: SpannedTokenizer> DynSpannedTokenizer for T {
+    #[inline]
+    fn tokenize_spanned_dyn(&self, s: &str) -> Vec<(String, Range<usize>)> {
+        self.tokenize_spanned(s)
+    }
+}
+
+macro_rules! impl_pointer_tokenizer {
+    ($($pointer:ident),+) => {
+        $(
+            impl<T: DynTokenizer + ?Sized> Tokenizer for $pointer<T> {
+                #[inline]
+                fn tokenize<S: AsRef<str>>(&self, s: S) -> Vec<String> {
+                    (**self).tokenize_dyn(s.as_ref())
+                }
+
+                #[inline]
+                fn tokenize_borrowed<'s>(&self, s: &'s str) -> Vec<Cow<'s, str>> {
+                    (**self).tokenize_ref_dyn(s)
+                }
+
+                #[inline]
+                fn write_config(&self, state: &mut dyn Hasher) {
+                    (**self).hash_config_dyn(state)
+                }
+            }
+
+            impl<T: DynSpannedTokenizer + ?Sized> SpannedTokenizer for $pointer<T> {
+                #[inline]
+                fn tokenize_spanned<S: AsRef<str>>(&self, s: S) -> Vec<(String, Range<usize>)> {
+                    (**self).tokenize_spanned_dyn(s.as_ref())
+                }
+            }
+        )+
+    };
+}
+
+impl_pointer_tokenizer!(Box, Rc, Arc);
@@ -0,0 +1,118 @@
+use super::{SpannedTokenizer, Tokenizer, TokenizerExt, hash_config_str};
+use std::{borrow::Cow, hash::Hasher, ops::Range};
+use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
+
+/// Wraps another tokenizer and strips the diacritics from each of its tokens, so `séptimo`
+/// becomes `septimo`. Letters that don't decompose into a base letter and accents, such as `ß`
+/// and `ø`, are replaced with their usual ASCII spellings. Only the marks on Latin, Greek and
+/// Cyrillic letters are stripped, so kana keep their voicing marks and Hangul is left intact.
+/// Anything else is left as-is, so the tokens aren't guaranteed to be ASCII. Spans still point at
+/// the original text.
+#[derive(Debug, Clone, Default)]
+pub struct AsciiFoldingTokenizer<T: Tokenizer> {
+    inner: T,
+}
+
+impl<T: Tokenizer> AsciiFoldingTokenizer<T> {
+    #[inline]
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+
+    #[inline]
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    /// Folds a single token. ASCII tokens are returned as-is.
+    pub fn fold(token: String) -> String {
+        if token.is_ascii() {
+            return token;
+        }
+        let mut folded = String::with_capacity(token.len());
+        // Whether the marks that follow belong to a letter they should be stripped from.
+        let mut strip_marks = false;
+        for c in token.nfd() {
+            if is_combining_mark(c) {
+                if !strip_marks {
+                    folded.push(c);
+                }
+                continue;
+            }
+            strip_marks = has_strippable_marks(c);
+            match fold_letter(c) {
+                Some(replacement) => folded.push_str(replacement),
+                None => folded.push(c),
+            }
+        }
+        // Recompose whatever was left decomposed, such as kana with voicing marks and Hangul.
+        folded.nfc().collect()
+    }
+}
+
+/// Checks if a letter is Latin, Greek or Cyrillic, whose marks are accents that can be dropped.
+/// The marks of other scripts often change which letter it is.
+fn has_strippable_marks(c: char) -> bool {
+    matches!(
+        c,
+        'A'..='Z'
+            | 'a'..='z'
+            | '\u{c0}'..='\u{24f}'
+            | '\u{370}'..='\u{52f}'
+            | '\u{1e00}'..='\u{1fff}'
+            | '\u{2c60}'..='\u{2c7f}'
+            | '\u{2de0}'..='\u{2dff}'
+            | '\u{a640}'..='\u{a69f}'
+            | '\u{a720}'..='\u{a7ff}'
+    )
+}
+
+/// Gets the ASCII spelling of letters that Unicode doesn't consider to be accented.
+fn fold_letter(c: char) -> Option<&'static str> {
+    let replacement = match c {
+        'ß' => "ss",
+        'ẞ' => "SS",
+        'æ' => "ae",
+        'Æ' => "AE",
+        'œ' => "oe",
+        'Œ' => "OE",
+        'ø' => "o",
+        'Ø' => "O",
+        'đ' | 'ð' => "d",
+        'Đ' | 'Ð' => "D",
+        'ł' => "l",
+        'Ł' => "L",
+        'þ' => "th",
+        'Þ' => "TH",
+        'ı' => "i",
+        _ => return None,
+    };
+    Some(replacement)
+}
+
+impl<T: Tokenizer> Tokenizer for AsciiFoldingTokenizer<T> {
+    fn tokenize<S: AsRef<str>>(&self, s: S) -> Vec<String> {
+        self.inner.tokenize(s).into_iter().map(Self::fold).collect()
+    }
+
+    fn tokenize_lazily<'s>(&self, s: &'s str) -> impl Iterator<Item = Cow<'s, str>> {
+        self.inner
+            .tokenize_iter(s)
+            .map(|token| Cow::Owned(Self::fold(token.into_owned())))
+    }
+
+    fn write_config(&self, state: &mut dyn Hasher) {
+        hash_config_str(state, "ascii_folding");
+        self.inner.hash_config(state);
+    }
+}
+
+impl<T: SpannedTokenizer> SpannedTokenizer for AsciiFoldingTokenizer<T> {
+    fn tokenize_spanned<S: AsRef<str>>(&self, s: S) -> Vec<(String, Range<usize>)> {
+        self.inner
+            .tokenize_spanned(s)
+            .into_iter()
+            .map(|(token, span)| (Self::fold(token), span))
+            .collect()
+    }
+}
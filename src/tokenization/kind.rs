@@ -0,0 +1,87 @@
+#[cfg(feature = "boundary-tokenizer")]
+use super::BoundaryTokenizer;
+#[cfg(feature = "unicode-segmentation")]
+use super::UnicodeWordTokenizer;
+use super::{CharTokenizer, DynTokenizer, NoOpTokenizer, PathTokenizer, WhitespaceTokenizer};
+use crate::{Error, error::UnknownTokenizerSnafu};
+use std::{fmt, str::FromStr};
+
+/// The built-in tokenizers, so that one can be picked by name at runtime, such as in a rule
+/// file. Each one is built with its default settings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum TokenizerKind {
+    #[default]
+    Whitespace,
+    #[cfg(feature = "boundary-tokenizer")]
+    Boundary,
+    Char,
+    Path,
+    #[cfg(feature = "unicode-segmentation")]
+    UnicodeWords,
+    NoOp,
+}
+
+impl TokenizerKind {
+    /// Every kind of tokenizer that's enabled.
+    pub const ALL: &[Self] = &[
+        Self::Whitespace,
+        #[cfg(feature = "boundary-tokenizer")]
+        Self::Boundary,
+        Self::Char,
+        Self::Path,
+        #[cfg(feature = "unicode-segmentation")]
+        Self::UnicodeWords,
+        Self::NoOp,
+    ];
+
+    /// The name the tokenizer is picked by, such as `unicode-words`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Whitespace => "whitespace",
+            #[cfg(feature = "boundary-tokenizer")]
+            Self::Boundary => "boundary",
+            Self::Char => "char",
+            Self::Path => "path",
+            #[cfg(feature = "unicode-segmentation")]
+            Self::UnicodeWords => "unicode-words",
+            Self::NoOp => "no-op",
+        }
+    }
+
+    pub fn build(&self) -> Box<dyn DynTokenizer + Send + Sync> {
+        match self {
+            Self::Whitespace => Box::new(WhitespaceTokenizer::new()),
+            #[cfg(feature = "boundary-tokenizer")]
+            Self::Boundary => Box::new(BoundaryTokenizer::default()),
+            Self::Char => Box::new(CharTokenizer::new()),
+            Self::Path => Box::new(PathTokenizer::new()),
+            #[cfg(feature = "unicode-segmentation")]
+            Self::UnicodeWords => Box::new(UnicodeWordTokenizer::new()),
+            Self::NoOp => Box::new(NoOpTokenizer),
+        }
+    }
+}
+
+impl fmt::Display for TokenizerKind {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for TokenizerKind {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(name))
+            .copied()
+            .ok_or_else(|| UnknownTokenizerSnafu { name }.build())
+    }
+}
@@ -1,27 +1,334 @@
-use std::{ops::Deref as _, sync::LazyLock};
+use std::{
+    borrow::Cow,
+    hash::Hasher,
+    ops::{Deref as _, Range},
+    sync::LazyLock,
+};
 
 #[cfg(feature = ("boundary-tokenizer"))]
 mod boundary;
+mod char;
+mod compound;
+mod dynamic;
+#[cfg(feature = "unicode-normalization")]
+mod fold;
+mod kind;
+mod ngram;
+#[cfg(feature = "unicode-normalization")]
+mod normalize;
+mod path;
+mod pipeline;
+mod shingle;
+#[cfg(feature = "stemming")]
+mod stem;
+mod stop;
+#[cfg(feature = "unicode-segmentation")]
+mod words;
+
 #[cfg(feature = ("boundary-tokenizer"))]
 pub use boundary::*;
+pub use char::*;
+pub use compound::*;
+pub use dynamic::*;
+#[cfg(feature = "unicode-normalization")]
+pub use fold::*;
+pub use kind::*;
+pub use ngram::*;
+#[cfg(feature = "unicode-normalization")]
+pub use normalize::*;
+pub use path::*;
+pub use pipeline::*;
+pub use shingle::*;
+#[cfg(feature = "stemming")]
+pub use stem::*;
+pub use stop::*;
+#[cfg(feature = "unicode-segmentation")]
+pub use words::*;
 
 const WHITESPACE_CHARS: &[char] = &[' ', '\t', '\n', '\r'];
 
+/// Splits strings into tokens. This is meant to be implemented outside of this crate: only
+/// [`tokenize`](Self::tokenize) is required, and the other methods are hooks with default
+/// implementations that tokenizers can override to be faster or more precise. They're called
+/// through [`TokenizerExt`], which is where new provided methods are added, so implementing this
+/// won't be broken by them.
+///
+/// Unlike this, the node traits ([`ImmutableTrieNode`](crate::immutable::nodes::ImmutableTrieNode)
+/// and friends) are sealed.
 pub trait Tokenizer {
     fn tokenize<S: AsRef<str>>(&self, s: S) -> Vec<String>;
+
+    /// Backs [`TokenizerExt::tokenize_ref`]. The default implementation always copies, so
+    /// tokenizers should override this if they can borrow tokens that don't need to be
+    /// normalized from `s`.
+    fn tokenize_borrowed<'s>(&self, s: &'s str) -> Vec<Cow<'s, str>> {
+        self.tokenize(s).into_iter().map(Cow::Owned).collect()
+    }
+
+    /// Backs [`TokenizerExt::tokenize_iter`]. The default implementation tokenizes everything up
+    /// front, so tokenizers should override this if they can produce tokens lazily.
+    fn tokenize_lazily<'s>(&self, s: &'s str) -> impl Iterator<Item = Cow<'s, str>> {
+        self.tokenize_borrowed(s).into_iter()
+    }
+
+    /// Backs [`TokenizerExt::hash_config`]. The default implementation only writes the type's
+    /// name, which isn't guaranteed to stay the same between Rust versions, so tokenizers should
+    /// override this with a name and their options.
+    fn write_config(&self, state: &mut dyn Hasher) {
+        hash_config_str(state, std::any::type_name::<Self>());
+    }
+}
+
+/// The methods every [`Tokenizer`] gets. This is implemented for every tokenizer, which also
+/// means it can't be implemented by hand, so methods can be added to it without breaking anything
+/// downstream.
+pub trait TokenizerExt: Tokenizer {
+    /// Like [`tokenize`](Tokenizer::tokenize), but tokens that don't need to be normalized are
+    /// borrowed from `s` instead of being copied, if the tokenizer
+    /// [supports it](Tokenizer::tokenize_borrowed).
+    #[inline]
+    fn tokenize_ref<'s>(&self, s: &'s str) -> Vec<Cow<'s, str>> {
+        self.tokenize_borrowed(s)
+    }
+
+    /// Like [`tokenize_ref`](Self::tokenize_ref), but the tokens are produced lazily (if the
+    /// tokenizer [supports it](Tokenizer::tokenize_lazily)), so callers that stop early don't pay
+    /// for tokenizing the rest of `s`.
+    #[inline]
+    fn tokenize_iter<'s>(&self, s: &'s str) -> impl Iterator<Item = Cow<'s, str>> {
+        self.tokenize_lazily(s)
+    }
+
+    /// Feeds everything that changes how this tokenizer splits strings into `state`, so that a
+    /// [manifest](crate::immutable::Manifest) can tell when a trie is searched with a different
+    /// tokenizer than it was built with. See [`Tokenizer::write_config`].
+    #[inline]
+    fn hash_config(&self, state: &mut dyn Hasher) {
+        self.write_config(state);
+    }
+
+    /// Runs every token this tokenizer produces through `stage`. See [`TokenizerPipeline`].
+    #[inline]
+    fn then<S: TokenStage>(self, stage: S) -> TokenizerPipeline<Self, S>
+    where
+        Self: Sized,
+    {
+        TokenizerPipeline::new(self, stage)
+    }
+}
+
+impl<T: Tokenizer + ?Sized> TokenizerExt for T {}
+
+/// A [`Tokenizer`] that can also report where in the original string each token came from. Like
+/// [`Tokenizer`], this is meant to be implemented outside of this crate.
+pub trait SpannedTokenizer: Tokenizer {
+    /// Tokenizes the string, pairing each token with its byte span in `s`. The tokens must be the
+    /// same ones that [`Tokenizer::tokenize`] would return.
+    fn tokenize_spanned<S: AsRef<str>>(&self, s: S) -> Vec<(String, Range<usize>)>;
+
+    /// Tokenizes the string, pairing each (normalized) token with the original text it came
+    /// from, such as `Bobby` for the token `bobby`.
+    fn tokenize_with_original<'s>(&self, s: &'s str) -> Vec<OriginalToken<'s>> {
+        self.tokenize_spanned(s)
+            .into_iter()
+            .map(|(token, span)| OriginalToken {
+                token,
+                original: &s[span.clone()],
+                span,
+            })
+            .collect()
+    }
 }
 
+/// A token along with the exact text it was created from. See
+/// [`SpannedTokenizer::tokenize_with_original`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OriginalToken<'s> {
+    /// The token, as it's matched against the trie.
+    pub token: String,
+    /// The text in the original string that the token came from.
+    pub original: &'s str,
+    /// The byte span of [`original`](Self::original) in the original string.
+    pub span: Range<usize>,
+}
+
+/// Writes a string for [`Tokenizer::write_config`], followed by a separator so that
+/// neighboring strings can't run together.
+pub(crate) fn hash_config_str(state: &mut dyn Hasher, s: &str) {
+    state.write(s.as_bytes());
+    state.write_u8(0xff);
+}
+
+/// Lowercases `s`, only copying it if any of its characters actually change.
+pub(crate) fn to_lowercase_cow(s: &str) -> Cow<'_, str> {
+    let unchanged = s.chars().all(|c| {
+        let mut lower = c.to_lowercase();
+        lower.next() == Some(c) && lower.next().is_none()
+    });
+    if unchanged {
+        Cow::Borrowed(s)
+    } else {
+        Cow::Owned(s.to_lowercase())
+    }
+}
+
+/// Gets the byte offset of `part` in `source`. `part` must be a subslice of `source`.
+#[inline]
+pub(crate) fn offset_in(source: &str, part: &str) -> usize {
+    part.as_ptr() as usize - source.as_ptr() as usize
+}
+
+/// Finds the byte span of each token in the string it was created from. Tokens are searched for
+/// in order, ignoring case. If a token can't be found (for example, because the tokenizer
+/// transformed it), an empty span is used at the position where the search left off. This is
+/// meant to help implement [`SpannedTokenizer`] for tokenizers that don't track offsets.
+pub fn token_spans<S: AsRef<str>>(source: &str, tokens: &[S]) -> Vec<Range<usize>> {
+    let mut cursor = 0;
+    tokens
+        .iter()
+        .map(|token| match locate_token(source, token.as_ref(), cursor) {
+            Some(span) => {
+                cursor = span.end;
+                span
+            }
+            None => cursor..cursor,
+        })
+        .collect()
+}
+
+fn locate_token(source: &str, token: &str, from: usize) -> Option<Range<usize>> {
+    if token.is_empty() {
+        return None;
+    }
+    source[from..]
+        .char_indices()
+        .map(|(i, _)| from + i)
+        .find_map(|start| {
+            let mut expected = token.chars().peekable();
+            for (i, c) in source[start..].char_indices() {
+                if expected.peek() == Some(&c) {
+                    expected.next();
+                } else {
+                    for lower in c.to_lowercase() {
+                        if expected.next() != Some(lower) {
+                            return None;
+                        }
+                    }
+                }
+                if expected.peek().is_none() {
+                    return Some(start..start + i + c.len_utf8());
+                }
+            }
+            None
+        })
+}
+
+const UTF16_BOM: u16 = 0xFEFF;
+
+/// Lossily decodes UTF-16 text, such as what's found in Windows event logs and registry exports.
+/// A leading byte order mark and any trailing NUL terminators are removed, and unpaired
+/// surrogates are replaced with [`char::REPLACEMENT_CHARACTER`].
+pub fn decode_utf16_lossy(s: &[u16]) -> String {
+    let s = s.strip_prefix(&[UTF16_BOM]).unwrap_or(s);
+    let end = s.iter().rposition(|c| *c != 0).map_or(0, |i| i + 1);
+    String::from_utf16_lossy(&s[..end])
+}
+
+/// A token that was produced outside of this crate, such as by an NLP pipeline, along with its
+/// byte offsets in the source text and an optional part-of-speech tag.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Token {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+    pub pos_tag: Option<String>,
+}
+
+impl Token {
+    #[inline]
+    pub fn new<S: Into<String>>(text: S, start: usize, end: usize) -> Self {
+        Self {
+            text: text.into(),
+            start,
+            end,
+            pos_tag: None,
+        }
+    }
+
+    #[inline]
+    pub fn with_pos_tag<S: Into<String>>(mut self, pos_tag: S) -> Self {
+        self.pos_tag = Some(pos_tag.into());
+        self
+    }
+}
+
+impl AsRef<str> for Token {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        &self.text
+    }
+}
+
+/// What a [`CustomWhitespaceTokenizer`] splits on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Whitespace {
+    /// Splits on any of the given characters. By default, these are spaces, tabs, and line
+    /// breaks, and other Unicode whitespace is trimmed off of both ends of the string. Other
+    /// sets are split on exactly as given.
+    Chars(&'static [char]),
+    /// Splits on anything Unicode considers to be whitespace (see [`char::is_whitespace`]), such
+    /// as non-breaking and ideographic spaces.
+    Unicode,
+}
+
+impl Whitespace {
+    #[inline]
+    pub fn contains(&self, c: char) -> bool {
+        match self {
+            Self::Chars(chars) => chars.contains(&c),
+            Self::Unicode => c.is_whitespace(),
+        }
+    }
+}
+
+impl Default for Whitespace {
+    #[inline]
+    fn default() -> Self {
+        Self::Chars(WHITESPACE_CHARS)
+    }
+}
+
+/// Splits strings on spaces, tabs and line breaks, lowercasing each token. Any other Unicode
+/// whitespace at the start or end of the string is trimmed off. Use
+/// [`CustomWhitespaceTokenizer`] to keep the case of tokens or to split on other characters.
 #[derive(Debug, Clone, Default, Copy)]
 pub struct WhitespaceTokenizer;
 
 impl WhitespaceTokenizer {
+    #[inline]
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Creates a [`CustomWhitespaceTokenizer`] that keeps the case of each token as-is if
+    /// `case_sensitive` is set.
+    #[inline]
+    pub fn with_case_sensitive(self, case_sensitive: bool) -> CustomWhitespaceTokenizer {
+        CustomWhitespaceTokenizer::new().with_case_sensitive(case_sensitive)
+    }
+
+    /// Creates a [`CustomWhitespaceTokenizer`] that splits on `whitespace`.
+    #[inline]
+    pub fn with_whitespace(self, whitespace: Whitespace) -> CustomWhitespaceTokenizer {
+        CustomWhitespaceTokenizer::new().with_whitespace(whitespace)
+    }
+
     pub fn tokenize<S: AsRef<str>>(s: S) -> Vec<String> {
-        s.as_ref()
-            .trim()
-            .split(WHITESPACE_CHARS)
-            .filter(|s| !s.is_empty())
-            .map(|s| s.to_lowercase())
-            .collect()
+        Tokenizer::tokenize(&CustomWhitespaceTokenizer::new(), s)
+    }
+
+    pub fn tokenize_spanned<S: AsRef<str>>(s: S) -> Vec<(String, Range<usize>)> {
+        SpannedTokenizer::tokenize_spanned(&CustomWhitespaceTokenizer::new(), s)
     }
 }
 
@@ -30,6 +337,135 @@ impl Tokenizer for WhitespaceTokenizer {
     fn tokenize<S: AsRef<str>>(&self, s: S) -> Vec<String> {
         Self::tokenize(s)
     }
+
+    #[inline]
+    fn tokenize_borrowed<'s>(&self, s: &'s str) -> Vec<Cow<'s, str>> {
+        CustomWhitespaceTokenizer::new().tokenize_ref(s)
+    }
+
+    #[inline]
+    fn tokenize_lazily<'s>(&self, s: &'s str) -> impl Iterator<Item = Cow<'s, str>> {
+        CustomWhitespaceTokenizer::new()
+            .split(s)
+            .map(|(token, _)| token)
+    }
+
+    #[inline]
+    fn write_config(&self, state: &mut dyn Hasher) {
+        CustomWhitespaceTokenizer::new().hash_config(state);
+    }
+}
+
+impl SpannedTokenizer for WhitespaceTokenizer {
+    #[inline]
+    fn tokenize_spanned<S: AsRef<str>>(&self, s: S) -> Vec<(String, Range<usize>)> {
+        Self::tokenize_spanned(s)
+    }
+}
+
+/// Like a [`WhitespaceTokenizer`], but what counts as whitespace and whether tokens are
+/// lowercased can be changed. Tokens are lowercased unless the tokenizer is made
+/// [case-sensitive](Self::with_case_sensitive).
+#[derive(Debug, Clone, Default, Copy)]
+pub struct CustomWhitespaceTokenizer {
+    whitespace: Whitespace,
+    case_sensitive: bool,
+}
+
+impl CustomWhitespaceTokenizer {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether the case of each token is kept as-is.
+    #[inline]
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    #[inline]
+    pub fn is_case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    /// Sets what's treated as whitespace, such as
+    /// `Whitespace::Chars(&[' ', '\t', '\n', '\r', ','])` to also split on commas.
+    #[inline]
+    pub fn with_whitespace(mut self, whitespace: Whitespace) -> Self {
+        self.whitespace = whitespace;
+        self
+    }
+
+    #[inline]
+    pub fn whitespace(&self) -> Whitespace {
+        self.whitespace
+    }
+
+    fn split<'s>(self, source: &'s str) -> impl Iterator<Item = (Cow<'s, str>, Range<usize>)> {
+        let (whitespace, case_sensitive) = (self.whitespace, self.case_sensitive);
+        // The default set doesn't split on other Unicode whitespace, such as non-breaking
+        // spaces, but it's still trimmed off of both ends so it never ends up in the first or
+        // last token.
+        let trimmed = if whitespace == Whitespace::default() {
+            source.trim()
+        } else {
+            source
+        };
+        trimmed
+            .split(move |c| whitespace.contains(c))
+            .filter(|s| !s.is_empty())
+            .map(move |part| {
+                let start = offset_in(source, part);
+                let token = if case_sensitive {
+                    Cow::Borrowed(part)
+                } else {
+                    to_lowercase_cow(part)
+                };
+                (token, start..start + part.len())
+            })
+    }
+}
+
+impl Tokenizer for CustomWhitespaceTokenizer {
+    #[inline]
+    fn tokenize<S: AsRef<str>>(&self, s: S) -> Vec<String> {
+        self.split(s.as_ref())
+            .map(|(token, _)| token.into_owned())
+            .collect()
+    }
+
+    #[inline]
+    fn tokenize_borrowed<'s>(&self, s: &'s str) -> Vec<Cow<'s, str>> {
+        self.split(s).map(|(token, _)| token).collect()
+    }
+
+    #[inline]
+    fn tokenize_lazily<'s>(&self, s: &'s str) -> impl Iterator<Item = Cow<'s, str>> {
+        self.split(s).map(|(token, _)| token)
+    }
+
+    fn write_config(&self, state: &mut dyn Hasher) {
+        hash_config_str(state, "whitespace");
+        match self.whitespace {
+            Whitespace::Chars(chars) => {
+                hash_config_str(state, "chars");
+                chars.iter().for_each(|c| state.write_u32(u32::from(*c)));
+            }
+            Whitespace::Unicode => hash_config_str(state, "unicode"),
+        }
+        state.write_u8(u8::from(self.case_sensitive));
+    }
+}
+
+impl SpannedTokenizer for CustomWhitespaceTokenizer {
+    #[inline]
+    fn tokenize_spanned<S: AsRef<str>>(&self, s: S) -> Vec<(String, Range<usize>)> {
+        self.split(s.as_ref())
+            .map(|(token, span)| (token.into_owned(), span))
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Default, Copy)]
@@ -46,11 +482,35 @@ impl Tokenizer for NoOpTokenizer {
     fn tokenize<S: AsRef<str>>(&self, s: S) -> Vec<String> {
         Self::tokenize(s)
     }
+
+    #[inline]
+    fn tokenize_borrowed<'s>(&self, s: &'s str) -> Vec<Cow<'s, str>> {
+        vec![Cow::Borrowed(s)]
+    }
+
+    #[inline]
+    fn tokenize_lazily<'s>(&self, s: &'s str) -> impl Iterator<Item = Cow<'s, str>> {
+        std::iter::once(Cow::Borrowed(s))
+    }
+
+    #[inline]
+    fn write_config(&self, state: &mut dyn Hasher) {
+        hash_config_str(state, "noop");
+    }
+}
+
+impl SpannedTokenizer for NoOpTokenizer {
+    #[inline]
+    fn tokenize_spanned<S: AsRef<str>>(&self, s: S) -> Vec<(String, Range<usize>)> {
+        let s = s.as_ref();
+        vec![(s.into(), 0..s.len())]
+    }
 }
 
 //#[cfg(test)]
 mod test {
     use super::*;
+    use std::sync::Arc;
 
     #[test]
     fn test_whitespace_tokenizer() {
@@ -65,5 +525,420 @@ mod test {
             WhitespaceTokenizer::tokenize("Test  test"),
             vec!["test", "test"]
         );
+        assert_eq!(
+            Tokenizer::tokenize(&WhitespaceTokenizer, "Test  test"),
+            vec!["test", "test"]
+        );
+    }
+
+    #[test]
+    fn test_whitespace_tokenizer_case_sensitive() {
+        let tokenizer = WhitespaceTokenizer::new().with_case_sensitive(true);
+        assert_eq!(tokenizer.tokenize(" Test TEST "), vec!["Test", "TEST"]);
+        assert_eq!(
+            tokenizer.tokenize_spanned("Test"),
+            vec![(String::from("Test"), 0..4)]
+        );
+        assert_eq!(WhitespaceTokenizer::new().tokenize("TEST"), vec!["test"]);
+    }
+
+    #[test]
+    fn test_whitespace_tokenizer_custom_whitespace() {
+        let tokenizer = WhitespaceTokenizer::new().with_whitespace(Whitespace::Unicode);
+        assert_eq!(
+            tokenizer.tokenize("a\u{a0}b\u{3000}c d"),
+            vec!["a", "b", "c", "d"]
+        );
+        let tokenizer = WhitespaceTokenizer::new().with_whitespace(Whitespace::Chars(&[' ', ',']));
+        assert_eq!(
+            tokenizer.tokenize_spanned("a, b\tc"),
+            vec![(String::from("a"), 0..1), (String::from("b\tc"), 3..6)]
+        );
+        assert_eq!(WhitespaceTokenizer::tokenize("a\u{a0}b"), vec!["a\u{a0}b"]);
+        assert_eq!(
+            WhitespaceTokenizer::tokenize_spanned("\u{a0}a b\u{3000}"),
+            vec![(String::from("a"), 2..3), (String::from("b"), 4..5)]
+        );
+        let tokenizer = WhitespaceTokenizer::new().with_whitespace(Whitespace::Chars(&[',']));
+        assert_eq!(tokenizer.tokenize(" a,b "), vec![" a", "b "]);
+    }
+
+    #[test]
+    fn test_tokenize_with_original() {
+        let tokens = WhitespaceTokenizer::new().tokenize_with_original(" Mister BOBBY");
+        assert_eq!(
+            tokens,
+            vec![
+                OriginalToken {
+                    token: String::from("mister"),
+                    original: "Mister",
+                    span: 1..7,
+                },
+                OriginalToken {
+                    token: String::from("bobby"),
+                    original: "BOBBY",
+                    span: 8..13,
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn test_normalizing_tokenizer() {
+        let tokenizer =
+            NormalizingTokenizer::new(WhitespaceTokenizer::new(), NormalizationForm::Nfc);
+        assert_eq!(
+            tokenizer.tokenize("caf\u{e9} cafe\u{301}"),
+            vec!["caf\u{e9}", "caf\u{e9}"]
+        );
+        assert_eq!(
+            tokenizer.tokenize_spanned("cafe\u{301}"),
+            vec![(String::from("caf\u{e9}"), 0..6)]
+        );
+        assert_eq!(tokenizer.tokenize("\u{fb01}le"), vec!["\u{fb01}le"]);
+        let tokenizer =
+            NormalizingTokenizer::new(WhitespaceTokenizer::new(), NormalizationForm::Nfkc);
+        assert_eq!(tokenizer.tokenize("\u{fb01}le"), vec!["file"]);
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn test_ascii_folding_tokenizer() {
+        let tokenizer = AsciiFoldingTokenizer::new(WhitespaceTokenizer::new());
+        assert_eq!(
+            tokenizer.tokenize("Séptimo Straße cafe\u{301} Øre"),
+            vec!["septimo", "strasse", "cafe", "ore"]
+        );
+        assert_eq!(
+            tokenizer.tokenize_spanned("año"),
+            vec![(String::from("ano"), 0..4)]
+        );
+        assert_eq!(tokenizer.tokenize("東京"), vec!["東京"]);
+        assert_eq!(tokenizer.tokenize("Ёлка ἄλφα"), vec!["елка", "αλφα"]);
+    }
+
+    #[cfg(feature = "unicode-normalization")]
+    #[test]
+    fn test_ascii_folding_keeps_kana_and_hangul() {
+        let tokenizer = AsciiFoldingTokenizer::new(WhitespaceTokenizer::new());
+        assert_eq!(
+            tokenizer.tokenize("がっこう パン ブドウ"),
+            vec!["がっこう", "パン", "ブドウ"]
+        );
+        assert_eq!(
+            tokenizer.tokenize("か\u{3099} 한국어"),
+            vec!["が", "한국어"]
+        );
+        assert_eq!(tokenizer.tokenize("résumé ガ"), vec!["resume", "ガ"]);
+    }
+
+    #[cfg(feature = "unicode-segmentation")]
+    #[test]
+    fn test_unicode_word_tokenizer() {
+        let tokenizer = UnicodeWordTokenizer::new();
+        assert_eq!(
+            tokenizer.tokenize("Hello, World! It's v1.2"),
+            vec!["hello", "world", "it's", "v1.2"]
+        );
+        assert_eq!(tokenizer.tokenize("東京タワー"), vec!["東", "京", "タワー"]);
+        assert_eq!(
+            tokenizer
+                .with_case_sensitive(true)
+                .tokenize_spanned("¡Hola!"),
+            vec![(String::from("Hola"), 2..6)]
+        );
+    }
+
+    #[test]
+    fn test_char_tokenizer() {
+        let tokenizer = CharTokenizer::new();
+        assert_eq!(tokenizer.tokenize("Hi é!"), vec!["h", "i", " ", "é", "!"]);
+        assert_eq!(
+            tokenizer.with_case_sensitive(true).tokenize_spanned("Aé"),
+            vec![(String::from("A"), 0..1), (String::from("é"), 1..3)]
+        );
+        assert!(tokenizer.tokenize("").is_empty());
+    }
+
+    #[test]
+    fn test_ngram_tokenizer() {
+        let tokenizer = NgramTokenizer::new(2, 3);
+        assert_eq!(tokenizer.tokenize("Cat a"), vec!["ca", "cat", "at"]);
+        assert_eq!(
+            tokenizer.tokenize_spanned(" héy"),
+            vec![
+                (String::from("hé"), 1..4),
+                (String::from("héy"), 1..5),
+                (String::from("éy"), 2..5),
+            ]
+        );
+        assert_eq!(NgramTokenizer::new(0, 0).tokenize("ab"), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_shingle_tokenizer() {
+        let tokenizer = ShingleTokenizer::new(WhitespaceTokenizer::new());
+        assert_eq!(
+            tokenizer.tokenize("New York City"),
+            vec!["new_york", "york_city"]
+        );
+        assert_eq!(tokenizer.tokenize("Paris"), vec!["paris"]);
+        assert!(tokenizer.tokenize("").is_empty());
+        assert_eq!(
+            tokenizer
+                .with_size(3)
+                .with_separator(" ")
+                .tokenize_spanned("in new york city"),
+            vec![
+                (String::from("in new york"), 0..11),
+                (String::from("new york city"), 3..16),
+            ]
+        );
+    }
+
+    #[cfg(feature = "boundary-tokenizer")]
+    #[test]
+    fn test_boundary_tokenizer_builder() {
+        use convert_case::Boundary;
+
+        let tokenizer = BoundaryTokenizer::default();
+        assert_eq!(
+            tokenizer.tokenize("runVersion 1.2.3"),
+            vec!["run", "version", "1", "2", "3"]
+        );
+        assert_eq!(
+            tokenizer.tokenize_spanned("(a)"),
+            vec![(String::from("a"), 1..2)]
+        );
+        let tokenizer = BoundaryTokenizer::builder()
+            .with_default()
+            .remove_delim(".")
+            .add_delims(["+", "::"])
+            .build();
+        assert_eq!(
+            tokenizer.tokenize("runVersion 1.2.3+build::x"),
+            vec!["run", "version", "1.2.3", "build", "x"]
+        );
+        let tokenizer = BoundaryTokenizer::builder()
+            .add_boundaries(Boundary::defaults())
+            .remove_boundary(Boundary::LOWER_UPPER)
+            .build();
+        assert_eq!(
+            tokenizer.tokenize("runVersion 1.2"),
+            vec!["runversion", "1.2"]
+        );
+    }
+
+    #[test]
+    fn test_stop_word_filter() {
+        let filter = StopWordFilter::new(WhitespaceTokenizer::new(), ["the", "of"]);
+        assert_eq!(
+            filter.tokenize("The Bank of America"),
+            vec!["bank", "america"]
+        );
+        assert_eq!(
+            filter.tokenize_spanned("the end"),
+            vec![(String::from("end"), 4..7)]
+        );
+    }
+
+    #[cfg(feature = "stop-words")]
+    #[test]
+    fn test_english_stop_word_filter() {
+        let filter = StopWordFilter::english(WhitespaceTokenizer::new());
+        assert_eq!(filter.tokenize("This is not a drill"), vec!["drill"]);
+    }
+
+    #[cfg(feature = "stemming")]
+    #[test]
+    fn test_stemming_tokenizer() {
+        let tokenizer = StemmingTokenizer::english(WhitespaceTokenizer::new());
+        assert_eq!(
+            tokenizer.tokenize("Running runs run"),
+            vec!["run", "run", "run"]
+        );
+        assert_eq!(
+            tokenizer.clone().tokenize_spanned(" connections"),
+            vec![(String::from("connect"), 1..12)]
+        );
+    }
+
+    #[test]
+    fn test_tokenizer_pipeline() {
+        let tokenizer = WhitespaceTokenizer::new()
+            .then(|token: String| (token != "the").then_some(token))
+            .then(|token: String| Some(token.replace('-', "")));
+        assert_eq!(
+            tokenizer.tokenize("The e-mail the X-ray"),
+            vec!["email", "xray"]
+        );
+        assert_eq!(
+            tokenizer.tokenize_spanned("the co-op"),
+            vec![(String::from("coop"), 4..9)]
+        );
+    }
+
+    #[test]
+    fn test_tokenize_ref() {
+        let tokens = WhitespaceTokenizer::new().tokenize_ref("mister Bobby");
+        assert_eq!(tokens, vec!["mister", "bobby"]);
+        assert!(matches!(tokens[0], Cow::Borrowed(_)));
+        assert!(matches!(tokens[1], Cow::Owned(_)));
+        let tokens = PathTokenizer::new()
+            .with_percent_decoding(true)
+            .tokenize_ref("/a/b%20c");
+        assert_eq!(tokens, vec!["a", "b c"]);
+        assert!(matches!(tokens[0], Cow::Borrowed(_)));
+        // Tokenizers that don't override it still work.
+        let tokens = NgramTokenizer::new(2, 2).tokenize_ref("abc");
+        assert_eq!(tokens, vec!["ab", "bc"]);
+    }
+
+    #[test]
+    fn test_tokenize_iter() {
+        let tokenizer = WhitespaceTokenizer::new();
+        let mut tokens = tokenizer.tokenize_iter("mister  Bobby hill");
+        assert_eq!(tokens.next().as_deref(), Some("mister"));
+        assert_eq!(tokens.next().as_deref(), Some("bobby"));
+        for s in ["", "a/b//c/", "One two", "getHTTPResponse(code).body"] {
+            assert_eq!(
+                PathTokenizer::new().tokenize_iter(s).collect::<Vec<_>>(),
+                PathTokenizer::new().tokenize_ref(s)
+            );
+            assert_eq!(
+                BoundaryTokenizer::default()
+                    .tokenize_iter(s)
+                    .collect::<Vec<_>>(),
+                BoundaryTokenizer::default().tokenize(s)
+            );
+            assert_eq!(
+                NgramTokenizer::new(1, 2)
+                    .tokenize_iter(s)
+                    .collect::<Vec<_>>(),
+                NgramTokenizer::new(1, 2).tokenize_ref(s)
+            );
+        }
+    }
+
+    #[test]
+    fn test_tokenizer_ext() {
+        // A tokenizer that only implements the required method still gets all of the others.
+        struct CommaTokenizer;
+
+        impl Tokenizer for CommaTokenizer {
+            fn tokenize<S: AsRef<str>>(&self, s: S) -> Vec<String> {
+                s.as_ref().split(',').map(String::from).collect()
+            }
+        }
+
+        assert_eq!(CommaTokenizer.tokenize_ref("a,b"), vec!["a", "b"]);
+        assert_eq!(CommaTokenizer.tokenize_iter("a,b").count(), 2);
+        let tokenizer = CommaTokenizer.then(|token: String| Some(token.to_uppercase()));
+        assert_eq!(tokenizer.tokenize("a,b"), vec!["A", "B"]);
+        let mut hasher = std::hash::DefaultHasher::new();
+        CommaTokenizer.hash_config(&mut hasher);
+    }
+
+    #[test]
+    fn test_tokenizer_kind() {
+        for kind in TokenizerKind::ALL {
+            assert_eq!(kind.name().parse::<TokenizerKind>().unwrap(), *kind);
+        }
+        assert_eq!(
+            "Path"
+                .parse::<TokenizerKind>()
+                .unwrap()
+                .build()
+                .tokenize("/a/B"),
+            vec!["a", "B"]
+        );
+        assert!("shouting".parse::<TokenizerKind>().is_err());
+    }
+
+    #[test]
+    fn test_dyn_tokenizer() {
+        let tokenizers: Vec<Box<dyn DynSpannedTokenizer>> = vec![
+            Box::new(WhitespaceTokenizer::new()),
+            Box::new(PathTokenizer::new()),
+        ];
+        assert_eq!(tokenizers[0].tokenize("Bobby/Hill"), vec!["bobby/hill"]);
+        assert_eq!(tokenizers[1].tokenize("Bobby/Hill"), vec!["Bobby", "Hill"]);
+        assert_eq!(
+            tokenizers[1].tokenize_spanned("/a/b"),
+            vec![(String::from("a"), 1..2), (String::from("b"), 3..4)]
+        );
+        let tokenizer: Arc<dyn DynTokenizer + Send + Sync> = Arc::new(CharTokenizer::new());
+        assert_eq!(tokenizer.tokenize_ref("Ab"), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_compound_splitter() {
+        let splitter = CompoundSplitter::new(
+            WhitespaceTokenizer::new(),
+            ["credit", "card", "cardnumber", "number", "red"],
+        );
+        assert_eq!(
+            splitter.tokenize("my creditcardnumber"),
+            vec!["my", "credit", "cardnumber"]
+        );
+        assert_eq!(splitter.tokenize("creditcards"), vec!["creditcards"]);
+        assert_eq!(splitter.tokenize("cardnumber"), vec!["cardnumber"]);
+        assert_eq!(
+            splitter.with_min_len(20).tokenize("creditcardnumber"),
+            vec!["creditcardnumber"]
+        );
+    }
+
+    #[test]
+    fn test_whitespace_tokenizer_spanned() {
+        assert_eq!(
+            WhitespaceTokenizer::tokenize_spanned(" Test\ttest  "),
+            vec![(String::from("test"), 1..5), (String::from("test"), 6..10)]
+        );
+        assert!(WhitespaceTokenizer::tokenize_spanned("  ").is_empty());
+    }
+
+    #[test]
+    fn test_path_tokenizer() {
+        let tokenizer = PathTokenizer::new();
+        assert_eq!(
+            tokenizer.tokenize("/api/V1.2/users/"),
+            vec!["api", "V1.2", "users"]
+        );
+        assert_eq!(tokenizer.tokenize("api//users"), vec!["api", "", "users"]);
+        assert!(tokenizer.tokenize("/").is_empty());
+        assert!(tokenizer.tokenize("").is_empty());
+        assert_eq!(
+            tokenizer.tokenize_spanned("/a%20b/c"),
+            vec![(String::from("a%20b"), 1..6), (String::from("c"), 7..8)]
+        );
+        let tokenizer = tokenizer.with_percent_decoding(true);
+        assert_eq!(
+            tokenizer.tokenize("/a%20b/c%2Fd/%zz/%+f"),
+            vec!["a b", "c/d", "%zz", "%+f"]
+        );
+        assert_eq!(tokenizer.tokenize("caf%C3%A9"), vec!["café"]);
+    }
+
+    #[test]
+    fn test_decode_utf16_lossy() {
+        let encoded: Vec<u16> = "Mister Bobby".encode_utf16().collect();
+        assert_eq!(decode_utf16_lossy(&encoded), "Mister Bobby");
+        let mut with_bom = vec![UTF16_BOM];
+        with_bom.extend(&encoded);
+        with_bom.extend([0, 0]);
+        assert_eq!(decode_utf16_lossy(&with_bom), "Mister Bobby");
+        assert_eq!(decode_utf16_lossy(&[0x62, 0xD800, 0x62]), "b\u{FFFD}b");
+        assert_eq!(decode_utf16_lossy(&[]), "");
+        assert_eq!(decode_utf16_lossy(&[0, 0]), "");
+    }
+
+    #[test]
+    fn test_token_spans() {
+        let source = "  Mister  BOBBY went";
+        let tokens = WhitespaceTokenizer::tokenize(source);
+        assert_eq!(token_spans(source, &tokens), vec![2..8, 10..15, 16..20]);
+        assert_eq!(token_spans("ab", &["zz", "b"]), vec![0..0, 1..2]);
     }
 }
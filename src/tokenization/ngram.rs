@@ -0,0 +1,88 @@
+use super::{SpannedTokenizer, Tokenizer, WhitespaceTokenizer, hash_config_str};
+use std::{hash::Hasher, ops::Range};
+
+/// Splits strings into words like the [`WhitespaceTokenizer`], then splits each word into every
+/// character n-gram between `min` and `max` characters long (e.g. `cat` into `ca`, `cat`, and
+/// `at` for 2 to 3). The n-grams are ordered by where they start, and then by length. Words that
+/// are shorter than `min` don't produce any tokens.
+#[derive(Debug, Clone, Copy)]
+pub struct NgramTokenizer {
+    min: usize,
+    max: usize,
+}
+
+impl NgramTokenizer {
+    /// Creates a tokenizer for n-grams of `min` to `max` characters. `min` is raised to 1 if it's
+    /// 0, and `max` is raised to `min` if it's smaller.
+    #[inline]
+    pub fn new(min: usize, max: usize) -> Self {
+        let min = min.max(1);
+        Self {
+            min,
+            max: max.max(min),
+        }
+    }
+
+    #[inline]
+    pub fn min(&self) -> usize {
+        self.min
+    }
+
+    #[inline]
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    fn ngrams(&self, word: &str, span: Range<usize>, tokens: &mut Vec<(String, Range<usize>)>) {
+        // The byte offset of every char boundary in the word, including the end.
+        let boundaries: Vec<usize> = word
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain([word.len()])
+            .collect();
+        let num_chars = boundaries.len() - 1;
+        for start in 0..num_chars {
+            for len in self.min..=self.max.min(num_chars - start) {
+                let (from, to) = (boundaries[start], boundaries[start + len]);
+                tokens.push((
+                    String::from(&word[from..to]),
+                    span.start + from..span.start + to,
+                ));
+            }
+        }
+    }
+}
+
+impl Tokenizer for NgramTokenizer {
+    #[inline]
+    fn tokenize<S: AsRef<str>>(&self, s: S) -> Vec<String> {
+        self.tokenize_spanned(s)
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect()
+    }
+
+    fn write_config(&self, state: &mut dyn Hasher) {
+        hash_config_str(state, "ngram");
+        state.write_u64(self.min as u64);
+        state.write_u64(self.max as u64);
+    }
+}
+
+impl SpannedTokenizer for NgramTokenizer {
+    fn tokenize_spanned<S: AsRef<str>>(&self, s: S) -> Vec<(String, Range<usize>)> {
+        let mut tokens = Vec::new();
+        for (word, span) in WhitespaceTokenizer::tokenize_spanned(s) {
+            // Lowercasing can change a word's length, in which case the spans can only cover
+            // the whole word.
+            if word.len() == span.len() {
+                self.ngrams(&word, span, &mut tokens);
+            } else {
+                let mut ngrams = Vec::new();
+                self.ngrams(&word, span.clone(), &mut ngrams);
+                tokens.extend(ngrams.into_iter().map(|(ngram, _)| (ngram, span.clone())));
+            }
+        }
+        tokens
+    }
+}
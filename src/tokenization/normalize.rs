@@ -0,0 +1,87 @@
+use super::{SpannedTokenizer, Tokenizer, TokenizerExt, hash_config_str};
+use std::{borrow::Cow, hash::Hasher, ops::Range};
+use unicode_normalization::{UnicodeNormalization, is_nfc, is_nfkc};
+
+/// The Unicode normalization forms that a [`NormalizingTokenizer`] can use.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum NormalizationForm {
+    /// Canonical composition, which only merges characters that look identical, such as an `e`
+    /// followed by a combining accent into `é`.
+    #[default]
+    Nfc,
+    /// Compatibility composition, which also folds characters that mean the same thing, such as
+    /// `ﬁ` into `fi` and full-width letters into ASCII.
+    Nfkc,
+}
+
+/// Wraps another tokenizer and normalizes each of its tokens, so visually identical text always
+/// produces the same tokens. Spans still point at the original text.
+#[derive(Debug, Clone, Default)]
+pub struct NormalizingTokenizer<T: Tokenizer> {
+    inner: T,
+    form: NormalizationForm,
+}
+
+impl<T: Tokenizer> NormalizingTokenizer<T> {
+    #[inline]
+    pub fn new(inner: T, form: NormalizationForm) -> Self {
+        Self { inner, form }
+    }
+
+    #[inline]
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn form(&self) -> NormalizationForm {
+        self.form
+    }
+
+    /// Normalizes a single token. Tokens that are already normalized are returned as-is.
+    pub fn normalize(&self, token: String) -> String {
+        match self.form {
+            NormalizationForm::Nfc if !is_nfc(&token) => token.nfc().collect(),
+            NormalizationForm::Nfkc if !is_nfkc(&token) => token.nfkc().collect(),
+            _ => token,
+        }
+    }
+}
+
+impl<T: Tokenizer> Tokenizer for NormalizingTokenizer<T> {
+    fn tokenize<S: AsRef<str>>(&self, s: S) -> Vec<String> {
+        self.inner
+            .tokenize(s)
+            .into_iter()
+            .map(|token| self.normalize(token))
+            .collect()
+    }
+
+    fn tokenize_lazily<'s>(&self, s: &'s str) -> impl Iterator<Item = Cow<'s, str>> {
+        self.inner
+            .tokenize_iter(s)
+            .map(|token| Cow::Owned(self.normalize(token.into_owned())))
+    }
+
+    fn write_config(&self, state: &mut dyn Hasher) {
+        hash_config_str(state, "normalizing");
+        hash_config_str(
+            state,
+            match self.form {
+                NormalizationForm::Nfc => "nfc",
+                NormalizationForm::Nfkc => "nfkc",
+            },
+        );
+        self.inner.hash_config(state);
+    }
+}
+
+impl<T: SpannedTokenizer> SpannedTokenizer for NormalizingTokenizer<T> {
+    fn tokenize_spanned<S: AsRef<str>>(&self, s: S) -> Vec<(String, Range<usize>)> {
+        self.inner
+            .tokenize_spanned(s)
+            .into_iter()
+            .map(|(token, span)| (self.normalize(token), span))
+            .collect()
+    }
+}
@@ -0,0 +1,120 @@
+use super::{SpannedTokenizer, Tokenizer, hash_config_str, offset_in};
+use std::{borrow::Cow, hash::Hasher, ops::Range};
+
+/// Splits paths (such as URL paths) into their segments. Unlike the other tokenizers, case is
+/// preserved and nothing inside a segment is treated as a boundary, so `v1.2` stays one token.
+///
+/// A single leading and trailing `/` is ignored, so `/users/` and `users` are the same path.
+/// Every other empty segment is kept as an empty token, so `users//posts` doesn't match
+/// `users/posts`.
+#[derive(Debug, Clone, Default, Copy)]
+pub struct PathTokenizer {
+    percent_decode: bool,
+}
+
+impl PathTokenizer {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether `%XX` escapes in each segment are decoded. Segments are split before they're
+    /// decoded, so an encoded `%2F` stays inside its segment. Escapes that aren't valid are left
+    /// as-is.
+    #[inline]
+    pub fn with_percent_decoding(mut self, percent_decode: bool) -> Self {
+        self.percent_decode = percent_decode;
+        self
+    }
+
+    #[inline]
+    pub fn percent_decoding(&self) -> bool {
+        self.percent_decode
+    }
+
+    fn segments<'s>(&self, source: &'s str) -> impl Iterator<Item = &'s str> {
+        let path = source.strip_prefix('/').unwrap_or(source);
+        let path = path.strip_suffix('/').unwrap_or(path);
+        // Without this, an empty path would be one empty segment.
+        (!path.is_empty())
+            .then(|| path.split('/'))
+            .into_iter()
+            .flatten()
+    }
+
+    fn decode<'s>(&self, segment: &'s str) -> Cow<'s, str> {
+        if self.percent_decode && segment.contains('%') {
+            Cow::Owned(percent_decode(segment))
+        } else {
+            Cow::Borrowed(segment)
+        }
+    }
+}
+
+impl Tokenizer for PathTokenizer {
+    fn tokenize<S: AsRef<str>>(&self, s: S) -> Vec<String> {
+        self.segments(s.as_ref())
+            .map(|segment| self.decode(segment).into_owned())
+            .collect()
+    }
+
+    fn tokenize_borrowed<'s>(&self, s: &'s str) -> Vec<Cow<'s, str>> {
+        self.segments(s)
+            .map(|segment| self.decode(segment))
+            .collect()
+    }
+
+    fn tokenize_lazily<'s>(&self, s: &'s str) -> impl Iterator<Item = Cow<'s, str>> {
+        self.segments(s).map(|segment| self.decode(segment))
+    }
+
+    fn write_config(&self, state: &mut dyn Hasher) {
+        hash_config_str(state, "path");
+        state.write_u8(u8::from(self.percent_decode));
+    }
+}
+
+impl SpannedTokenizer for PathTokenizer {
+    fn tokenize_spanned<S: AsRef<str>>(&self, s: S) -> Vec<(String, Range<usize>)> {
+        let source = s.as_ref();
+        self.segments(source)
+            .map(|segment| {
+                let start = offset_in(source, segment);
+                (
+                    self.decode(segment).into_owned(),
+                    start..start + segment.len(),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Decodes `%XX` escapes. If the decoded bytes aren't valid UTF-8, the invalid parts are replaced
+/// with [`char::REPLACEMENT_CHARACTER`].
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes.get(i..i + 3) {
+            Some([b'%', high, low]) => hex_value(*high).zip(hex_value(*low)),
+            _ => None,
+        };
+        match escaped {
+            Some((high, low)) => {
+                decoded.push(high << 4 | low);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[inline]
+fn hex_value(digit: u8) -> Option<u8> {
+    (digit as char).to_digit(16).map(|value| value as u8)
+}
@@ -0,0 +1,85 @@
+use super::{SpannedTokenizer, Tokenizer, TokenizerExt, hash_config_str};
+use std::{borrow::Cow, hash::Hasher, ops::Range};
+
+/// A step of a [`TokenizerPipeline`] that's applied to each token. Returning `None` drops the
+/// token. This is implemented for closures, so `|token| Some(token.replace('-', ""))` maps
+/// tokens and `|token| (token.len() > 2).then_some(token)` filters them. Like [`Tokenizer`], this
+/// is meant to be implemented outside of this crate.
+pub trait TokenStage {
+    fn apply(&self, token: String) -> Option<String>;
+
+    /// Like [`Tokenizer::write_config`]. The default implementation only writes the type's name,
+    /// which for closures is made up by the compiler, so stages that are kept around should
+    /// override this.
+    fn write_config(&self, state: &mut dyn Hasher) {
+        hash_config_str(state, std::any::type_name::<Self>());
+    }
+}
+
+impl<F> TokenStage for F
+where
+    F: Fn(String) -> Option<String>,
+{
+    #[inline]
+    fn apply(&self, token: String) -> Option<String> {
+        self(token)
+    }
+}
+
+/// A tokenizer followed by a [`TokenStage`]. Pipelines are created with
+/// [`TokenizerExt::then`], and can be chained as many times as needed (e.g.
+/// `base.then(filter).then(mapper)`). Spans are left as they were for every token that's kept.
+#[derive(Debug, Clone, Default)]
+pub struct TokenizerPipeline<T: Tokenizer, S: TokenStage> {
+    inner: T,
+    stage: S,
+}
+
+impl<T: Tokenizer, S: TokenStage> TokenizerPipeline<T, S> {
+    #[inline]
+    pub fn new(inner: T, stage: S) -> Self {
+        Self { inner, stage }
+    }
+
+    #[inline]
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn stage(&self) -> &S {
+        &self.stage
+    }
+}
+
+impl<T: Tokenizer, S: TokenStage> Tokenizer for TokenizerPipeline<T, S> {
+    fn tokenize<R: AsRef<str>>(&self, s: R) -> Vec<String> {
+        self.inner
+            .tokenize(s)
+            .into_iter()
+            .filter_map(|token| self.stage.apply(token))
+            .collect()
+    }
+
+    fn tokenize_lazily<'s>(&self, s: &'s str) -> impl Iterator<Item = Cow<'s, str>> {
+        self.inner
+            .tokenize_iter(s)
+            .filter_map(|token| self.stage.apply(token.into_owned()).map(Cow::Owned))
+    }
+
+    fn write_config(&self, state: &mut dyn Hasher) {
+        hash_config_str(state, "pipeline");
+        self.inner.hash_config(state);
+        self.stage.write_config(state);
+    }
+}
+
+impl<T: SpannedTokenizer, S: TokenStage> SpannedTokenizer for TokenizerPipeline<T, S> {
+    fn tokenize_spanned<R: AsRef<str>>(&self, s: R) -> Vec<(String, Range<usize>)> {
+        self.inner
+            .tokenize_spanned(s)
+            .into_iter()
+            .filter_map(|(token, span)| Some((self.stage.apply(token)?, span)))
+            .collect()
+    }
+}
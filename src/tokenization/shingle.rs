@@ -0,0 +1,99 @@
+use super::{SpannedTokenizer, Tokenizer, TokenizerExt, WhitespaceTokenizer, hash_config_str};
+use std::{hash::Hasher, ops::Range};
+
+const DEFAULT_SIZE: usize = 2;
+const DEFAULT_SEPARATOR: &str = "_";
+
+/// Wraps another tokenizer and joins every run of `size` adjacent tokens into a single token, so
+/// `new york city` becomes `new_york` and `york_city`. This makes multi-word keys shorter, since
+/// each shingle is a single edge in the trie. Strings with fewer tokens than `size` are joined
+/// into a single token, so keys and search strings should have at least `size` words for them
+/// to match each other reliably.
+#[derive(Debug, Clone)]
+pub struct ShingleTokenizer<T: Tokenizer = WhitespaceTokenizer> {
+    inner: T,
+    size: usize,
+    separator: String,
+}
+
+impl<T: Tokenizer + Default> Default for ShingleTokenizer<T> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T: Tokenizer> ShingleTokenizer<T> {
+    #[inline]
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            size: DEFAULT_SIZE,
+            separator: String::from(DEFAULT_SEPARATOR),
+        }
+    }
+
+    /// Sets how many tokens go into each shingle. Sizes smaller than 1 are raised to 1.
+    #[inline]
+    pub fn with_size(mut self, size: usize) -> Self {
+        self.size = size.max(1);
+        self
+    }
+
+    /// Sets what goes between the tokens of each shingle.
+    #[inline]
+    pub fn with_separator<S: Into<String>>(mut self, separator: S) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    #[inline]
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    #[inline]
+    pub fn separator(&self) -> &str {
+        &self.separator
+    }
+
+    fn windows<'t, I>(&self, tokens: &'t [I]) -> impl Iterator<Item = &'t [I]> {
+        // Without this, strings with too few tokens wouldn't have any shingles at all.
+        let size = self.size.min(tokens.len()).max(1);
+        tokens.windows(size)
+    }
+}
+
+impl<T: Tokenizer> Tokenizer for ShingleTokenizer<T> {
+    fn tokenize<S: AsRef<str>>(&self, s: S) -> Vec<String> {
+        let tokens = self.inner.tokenize(s);
+        self.windows(&tokens)
+            .map(|window| window.join(&self.separator))
+            .collect()
+    }
+
+    fn write_config(&self, state: &mut dyn Hasher) {
+        hash_config_str(state, "shingle");
+        state.write_u64(self.size as u64);
+        hash_config_str(state, &self.separator);
+        self.inner.hash_config(state);
+    }
+}
+
+impl<T: SpannedTokenizer> SpannedTokenizer for ShingleTokenizer<T> {
+    fn tokenize_spanned<S: AsRef<str>>(&self, s: S) -> Vec<(String, Range<usize>)> {
+        let tokens = self.inner.tokenize_spanned(s);
+        self.windows(&tokens)
+            .map(|window| {
+                let shingle: Vec<&str> = window.iter().map(|(token, _)| token.as_str()).collect();
+                let span = window[0].1.start..window[window.len() - 1].1.end;
+                (shingle.join(&self.separator), span)
+            })
+            .collect()
+    }
+}
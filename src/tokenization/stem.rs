@@ -0,0 +1,92 @@
+use super::{SpannedTokenizer, Tokenizer, TokenizerExt, hash_config_str};
+use educe::Educe;
+use rust_stemmers::{Algorithm, Stemmer};
+use std::{borrow::Cow, hash::Hasher, ops::Range};
+
+/// Wraps another tokenizer and reduces each of its tokens to its stem with one of the
+/// [Snowball](https://snowballstem.org/) stemmers, so `running` and `runs` both become `run`.
+/// Stemmers only strip suffixes, so irregular forms such as `ran` are left alone. The stemmers
+/// expect lowercase tokens.
+#[derive(Educe)]
+#[educe(Debug)]
+pub struct StemmingTokenizer<T: Tokenizer> {
+    inner: T,
+    algorithm: Algorithm,
+    #[educe(Debug(ignore))]
+    stemmer: Stemmer,
+}
+
+impl<T: Tokenizer> StemmingTokenizer<T> {
+    #[inline]
+    pub fn new(inner: T, algorithm: Algorithm) -> Self {
+        Self {
+            inner,
+            algorithm,
+            stemmer: Stemmer::create(algorithm),
+        }
+    }
+
+    /// Creates a tokenizer that uses the English (Porter2) stemmer.
+    #[inline]
+    pub fn english(inner: T) -> Self {
+        Self::new(inner, Algorithm::English)
+    }
+
+    #[inline]
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    #[inline]
+    pub fn stem(&self, token: String) -> String {
+        match self.stemmer.stem(&token) {
+            stemmed if stemmed == token.as_str() => token,
+            stemmed => stemmed.into_owned(),
+        }
+    }
+}
+
+/// [`Stemmer`] isn't `Clone`, so a new one is created instead.
+impl<T: Tokenizer + Clone> Clone for StemmingTokenizer<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.inner.clone(), self.algorithm)
+    }
+}
+
+impl<T: Tokenizer> Tokenizer for StemmingTokenizer<T> {
+    fn tokenize<S: AsRef<str>>(&self, s: S) -> Vec<String> {
+        self.inner
+            .tokenize(s)
+            .into_iter()
+            .map(|token| self.stem(token))
+            .collect()
+    }
+
+    fn tokenize_lazily<'s>(&self, s: &'s str) -> impl Iterator<Item = Cow<'s, str>> {
+        self.inner
+            .tokenize_iter(s)
+            .map(|token| Cow::Owned(self.stem(token.into_owned())))
+    }
+
+    fn write_config(&self, state: &mut dyn Hasher) {
+        hash_config_str(state, "stemming");
+        // The algorithms don't have any fields, so this is just the variant's name.
+        hash_config_str(state, &format!("{:?}", self.algorithm));
+        self.inner.hash_config(state);
+    }
+}
+
+impl<T: SpannedTokenizer> SpannedTokenizer for StemmingTokenizer<T> {
+    fn tokenize_spanned<S: AsRef<str>>(&self, s: S) -> Vec<(String, Range<usize>)> {
+        self.inner
+            .tokenize_spanned(s)
+            .into_iter()
+            .map(|(token, span)| (self.stem(token), span))
+            .collect()
+    }
+}
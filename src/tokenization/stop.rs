@@ -0,0 +1,100 @@
+use super::{SpannedTokenizer, Tokenizer, TokenizerExt, hash_config_str};
+use std::{borrow::Cow, collections::HashSet, hash::Hasher, ops::Range};
+
+/// Lucene's list of English stop words.
+#[cfg(feature = "stop-words")]
+pub const ENGLISH_STOP_WORDS: [&str; 33] = [
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it",
+    "no", "not", "of", "on", "or", "such", "that", "the", "their", "then", "there", "these",
+    "they", "this", "to", "was", "will", "with",
+];
+
+/// Wraps another tokenizer and drops any of its tokens that are stop words, such as `the` and
+/// `of`. Since the trie uses the same tokenizer for keys and search strings, this lets
+/// `bank of america` match `the bank america`. Stop words are compared against the tokens
+/// exactly, so they should be normalized the same way the inner tokenizer normalizes tokens.
+#[derive(Debug, Clone)]
+pub struct StopWordFilter<T: Tokenizer> {
+    inner: T,
+    stop_words: HashSet<String>,
+}
+
+impl<T: Tokenizer> StopWordFilter<T> {
+    pub fn new<I, S>(inner: T, stop_words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            inner,
+            stop_words: stop_words.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Creates a filter for the [English stop words](ENGLISH_STOP_WORDS).
+    #[cfg(feature = "stop-words")]
+    #[inline]
+    pub fn english(inner: T) -> Self {
+        Self::new(inner, ENGLISH_STOP_WORDS)
+    }
+
+    #[inline]
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    #[inline]
+    pub fn stop_words(&self) -> &HashSet<String> {
+        &self.stop_words
+    }
+
+    #[inline]
+    pub fn is_stop_word(&self, token: &str) -> bool {
+        self.stop_words.contains(token)
+    }
+}
+
+impl<T: Tokenizer> Tokenizer for StopWordFilter<T> {
+    fn tokenize<S: AsRef<str>>(&self, s: S) -> Vec<String> {
+        self.inner
+            .tokenize(s)
+            .into_iter()
+            .filter(|token| !self.is_stop_word(token))
+            .collect()
+    }
+
+    fn tokenize_borrowed<'s>(&self, s: &'s str) -> Vec<Cow<'s, str>> {
+        self.inner
+            .tokenize_ref(s)
+            .into_iter()
+            .filter(|token| !self.is_stop_word(token))
+            .collect()
+    }
+
+    fn tokenize_lazily<'s>(&self, s: &'s str) -> impl Iterator<Item = Cow<'s, str>> {
+        self.inner
+            .tokenize_iter(s)
+            .filter(|token| !self.is_stop_word(token))
+    }
+
+    fn write_config(&self, state: &mut dyn Hasher) {
+        hash_config_str(state, "stop_words");
+        let mut stop_words: Vec<_> = self.stop_words.iter().collect();
+        stop_words.sort_unstable();
+        state.write_u64(stop_words.len() as u64);
+        stop_words
+            .into_iter()
+            .for_each(|word| hash_config_str(state, word));
+        self.inner.hash_config(state);
+    }
+}
+
+impl<T: SpannedTokenizer> SpannedTokenizer for StopWordFilter<T> {
+    fn tokenize_spanned<S: AsRef<str>>(&self, s: S) -> Vec<(String, Range<usize>)> {
+        self.inner
+            .tokenize_spanned(s)
+            .into_iter()
+            .filter(|(token, _)| !self.is_stop_word(token))
+            .collect()
+    }
+}
@@ -0,0 +1,77 @@
+use super::{SpannedTokenizer, Tokenizer, hash_config_str, to_lowercase_cow};
+use std::{borrow::Cow, hash::Hasher, ops::Range};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Splits strings into words using the Unicode word boundaries from
+/// [UAX #29](https://www.unicode.org/reports/tr29/). Unlike the
+/// [`WhitespaceTokenizer`](super::WhitespaceTokenizer), this handles scripts that don't use
+/// spaces, such as Chinese and Japanese, and leaves punctuation out of the tokens. Tokens are
+/// lowercased unless the tokenizer is made [case-sensitive](Self::with_case_sensitive).
+#[derive(Debug, Clone, Default, Copy)]
+pub struct UnicodeWordTokenizer {
+    case_sensitive: bool,
+}
+
+impl UnicodeWordTokenizer {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether the case of each token is kept as-is.
+    #[inline]
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    #[inline]
+    pub fn is_case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    fn split<'s>(&self, source: &'s str) -> impl Iterator<Item = (Cow<'s, str>, Range<usize>)> {
+        let case_sensitive = self.case_sensitive;
+        source.unicode_word_indices().map(move |(start, word)| {
+            let token = if case_sensitive {
+                Cow::Borrowed(word)
+            } else {
+                to_lowercase_cow(word)
+            };
+            (token, start..start + word.len())
+        })
+    }
+}
+
+impl Tokenizer for UnicodeWordTokenizer {
+    #[inline]
+    fn tokenize<S: AsRef<str>>(&self, s: S) -> Vec<String> {
+        self.split(s.as_ref())
+            .map(|(token, _)| token.into_owned())
+            .collect()
+    }
+
+    #[inline]
+    fn tokenize_borrowed<'s>(&self, s: &'s str) -> Vec<Cow<'s, str>> {
+        self.split(s).map(|(token, _)| token).collect()
+    }
+
+    #[inline]
+    fn tokenize_lazily<'s>(&self, s: &'s str) -> impl Iterator<Item = Cow<'s, str>> {
+        self.split(s).map(|(token, _)| token)
+    }
+
+    fn write_config(&self, state: &mut dyn Hasher) {
+        hash_config_str(state, "unicode_words");
+        state.write_u8(u8::from(self.case_sensitive));
+    }
+}
+
+impl SpannedTokenizer for UnicodeWordTokenizer {
+    #[inline]
+    fn tokenize_spanned<S: AsRef<str>>(&self, s: S) -> Vec<(String, Range<usize>)> {
+        self.split(s.as_ref())
+            .map(|(token, span)| (token.into_owned(), span))
+            .collect()
+    }
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "config")]
+use jtm::{
+    config::{ConfiguredTrie, NodeBackend, TrieConfig},
+    *,
+};
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_toml_config() {
+    let config: TrieConfig<u32> = TrieConfig::from_toml(
+        r#"
+        backend = "ordered"
+
+        [options]
+        version = 7
+
+        [entries]
+        "new york" = 1
+        york = 2
+        "#,
+    )
+    .unwrap();
+    let trie = config.build().unwrap();
+    assert_eq!(trie.backend(), NodeBackend::Ordered);
+    assert_eq!(trie.version(), 7);
+    assert_eq!(trie.find_all("flying to new york"), vec![&1, &2]);
+    assert_eq!(trie.get_exact("York"), Some(&2));
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_auto_config_picks_small_keys() {
+    let config: TrieConfig<u32> = TrieConfig::from_toml(
+        r#"
+        backend = "auto"
+
+        [entries]
+        "new york" = 1
+        "new york city hall" = 2
+        "#,
+    )
+    .unwrap();
+    let trie = config.clone().build().unwrap();
+    assert_eq!(trie.backend(), NodeBackend::SmallKey);
+    assert_eq!(trie.find_all("new york city hall"), vec![&1, &2]);
+
+    let mut config = config;
+    config.entries.insert(String::from("a b c d e"), 3);
+    let trie = config.build().unwrap();
+    assert_eq!(trie.backend(), NodeBackend::Auto);
+    assert_eq!(trie.find_any("a b c d e"), Some(&3));
+}
+
+#[cfg(all(feature = "yaml", feature = "regex"))]
+#[test]
+fn test_yaml_config() {
+    let config: TrieConfig<String> = TrieConfig::from_yaml(
+        r#"
+        backend: auto
+        tokenizer: whitespace
+        search-tokenizer: path
+        entries:
+          "api re:v[0-9]+": versioned
+          "api docs": docs
+        "#,
+    )
+    .unwrap();
+    let trie = config.build().unwrap();
+    assert!(matches!(trie, ConfiguredTrie::Auto(_)));
+    assert_eq!(
+        trie.find_any("/api/v2/users"),
+        Some(&"versioned".to_owned())
+    );
+    assert!(trie.find_any("api v2").is_none());
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_toml_config_errors() {
+    assert!(matches!(
+        TrieConfig::<u32>::from_toml("tokenizer = \"shouting\""),
+        Err(Error::Toml { .. })
+    ));
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_yaml_config_errors() {
+    assert!(matches!(
+        TrieConfig::<u32>::from_yaml("backend: sideways"),
+        Err(Error::Yaml { .. })
+    ));
+    let config: TrieConfig<u32> = TrieConfig::from_yaml(
+        "options:\n  reject-duplicates: true\nentries:\n  York: 1\n  york: 2\n",
+    )
+    .unwrap();
+    assert!(matches!(config.build(), Err(Error::DuplicateKey { .. })));
+}
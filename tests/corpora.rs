@@ -0,0 +1,132 @@
+#![cfg(feature = "test-corpora")]
+use jtm::{
+    corpora::{self, CITIES, NAMES, PROFANITY},
+    immutable::{ImmutableTrieBuilder, nodes::*},
+    *,
+};
+
+const DOCUMENT: &str = "Mary Ann flew from New York to Rio de Janeiro with John and Priya \
+    and said damn what the heck it is bloody hot in Sao Paulo";
+
+/// The expected number of matches in [`DOCUMENT`] for each corpus.
+const EXPECTED_NAMES: usize = 5;
+const EXPECTED_CITIES: usize = 4;
+const EXPECTED_PROFANITY: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Name,
+    City,
+    Profanity,
+}
+
+fn add_corpora<B, T>(builder: &mut ImmutableTrieBuilder<B, Kind, T>)
+where
+    B: ImmutableTrieNodeBuilder<Kind>,
+    T: Tokenizer,
+{
+    for (corpus, kind) in [
+        (NAMES, Kind::Name),
+        (CITIES, Kind::City),
+        (PROFANITY, Kind::Profanity),
+    ] {
+        for entry in corpora::entries(corpus) {
+            builder.add(entry, kind).unwrap();
+        }
+    }
+}
+
+fn count(found: &[&Kind], kind: Kind) -> usize {
+    found.iter().filter(|found| ***found == kind).count()
+}
+
+fn check<B>(builder: B)
+where
+    B: ImmutableTrieNodeBuilder<Kind>,
+{
+    check_with::<B, WhitespaceTokenizer>(builder);
+}
+
+fn check_with<B, T>(builder: B)
+where
+    B: ImmutableTrieNodeBuilder<Kind>,
+    T: Tokenizer + Default,
+{
+    let mut trie_builder: ImmutableTrieBuilder<B, Kind, T> =
+        ImmutableTrieBuilder::with_builder(builder);
+    add_corpora(&mut trie_builder);
+    let trie: ImmutableTrie<B::Node, Kind, T> = trie_builder.build_default().unwrap();
+    let found = trie.find_all(DOCUMENT);
+    assert_eq!(count(&found, Kind::Name), EXPECTED_NAMES);
+    assert_eq!(count(&found, Kind::City), EXPECTED_CITIES);
+    assert_eq!(count(&found, Kind::Profanity), EXPECTED_PROFANITY);
+    assert_eq!(trie.find_iter(DOCUMENT).count(), found.len());
+    assert_eq!(
+        trie.num_keys(),
+        [NAMES, CITIES, PROFANITY]
+            .into_iter()
+            .map(|corpus| corpora::entries(corpus).count())
+            .sum::<usize>()
+    );
+}
+
+#[test]
+fn test_string_trie_corpora() {
+    check(StringTrieNodeBuilder::default());
+}
+
+#[test]
+fn test_auto_trie_corpora() {
+    check(AutoTrieNodeBuilder::default());
+}
+
+#[test]
+fn test_small_key_trie_corpora() {
+    check(SmallKeyTrieNodeBuilder::<Kind, 8>::default());
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_regex_set_trie_corpora() {
+    check(RegexSetTrieNodeBuilder::default());
+}
+
+#[cfg(feature = "regex-filtered")]
+#[test]
+fn test_regex_filtered_trie_corpora() {
+    check(RegexFilteredTrieNodeBuilder::default());
+}
+
+#[cfg(feature = "boundary-tokenizer")]
+#[test]
+fn test_boundary_tokenizer_corpora() {
+    check_with::<_, BoundaryTokenizer>(StringTrieNodeBuilder::default());
+    check_with::<_, BoundaryTokenizer>(AutoTrieNodeBuilder::default());
+    check_with::<_, BoundaryTokenizer>(SmallKeyTrieNodeBuilder::<Kind, 8>::default());
+    #[cfg(feature = "regex")]
+    check_with::<_, BoundaryTokenizer>(RegexSetTrieNodeBuilder::default());
+    #[cfg(feature = "regex-filtered")]
+    check_with::<_, BoundaryTokenizer>(RegexFilteredTrieNodeBuilder::default());
+}
+
+#[test]
+fn test_no_op_tokenizer_corpora() {
+    let mut trie_builder: ImmutableTrieBuilder<StringTrieNodeBuilder<Kind>, Kind, NoOpTokenizer> =
+        Default::default();
+    add_corpora(&mut trie_builder);
+    let trie: ImmutableTrie<StringTrieNode<Kind>, Kind, NoOpTokenizer> =
+        trie_builder.build_default().unwrap();
+    // Every entry is a single token, so only whole entries match.
+    assert_eq!(trie.find_all("rio de janeiro"), vec![&Kind::City]);
+    assert!(trie.find_all(DOCUMENT).is_empty());
+}
+
+#[test]
+fn test_compound_splitter_corpora() {
+    let mut trie_builder: StringTrieBuilder<Kind> = Default::default();
+    add_corpora(&mut trie_builder);
+    let trie: StringTrie<Kind, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+    let trie = trie.with_compound_splitting();
+    let found = trie.find_all("greetings from shanghaibeijing");
+    assert_eq!(count(&found, Kind::City), 2);
+}
@@ -0,0 +1,25 @@
+#![cfg(feature = "tokio")]
+use jtm::*;
+use tokio_stream::StreamExt as _;
+
+#[tokio::test]
+async fn test_string_trie_match_stream() {
+    let mut trie_builder: StringTrieBuilder<&str> = Default::default();
+    trie_builder.add("out of memory", "oom").unwrap();
+    trie_builder.add("disk", "disk").unwrap();
+    let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+    let lines = tokio_stream::iter(["ran out", "of memory", "and disk space"].map(String::from));
+    let found: Vec<_> = trie.match_stream(lines).collect().await;
+    assert_eq!(found, vec![&"oom", &"disk"]);
+}
+
+#[tokio::test]
+async fn test_string_trie_match_stream_empty() {
+    let trie_builder: StringTrieBuilder<&str> = Default::default();
+    let trie: StringTrie<&str, WhitespaceTokenizer> = trie_builder.build_default().unwrap();
+    let found: Vec<_> = trie
+        .match_stream(tokio_stream::iter(vec![String::from("anything")]))
+        .collect()
+        .await;
+    assert!(found.is_empty());
+}